@@ -0,0 +1,329 @@
+//! This file defines the tree of documentation that the parser produces from a documentation
+//! comment.
+//!
+//! The terminology here follows the [comments](https://dev.enso.org/docs/enso/syntax/comments)
+//! section of the Enso syntax documentation.
+
+use crate::prelude::*;
+
+
+
+// =====================
+// === Documentation ===
+// =====================
+
+/// The documentation given by a documentation comment.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Documentation {
+    /// The tags at the top of the comment.
+    pub tags : Vec<Tag>,
+    /// The sections of the comment, in order.
+    pub sections : Vec<Section>,
+}
+
+impl Documentation {
+    /// The synopsis of the documented construct, which is the first section of the comment.
+    pub fn synopsis(&self) -> Option<&Section> {
+        self.sections.first()
+    }
+
+    /// The body of the documentation, made up of all sections after the synopsis.
+    pub fn body(&self) -> &[Section] {
+        self.sections.get(1..).unwrap_or_default()
+    }
+}
+
+
+
+// ===========
+// === Tag ===
+// ===========
+
+/// A tag describing the usage state of the documented construct.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Tag {
+    /// The kind of the tag.
+    pub kind : TagKind,
+    /// The description that follows the tag on its line.
+    pub description : Option<String>,
+}
+
+impl Tag {
+    /// Constructor.
+    pub fn new(name:impl Str, description:Option<String>) -> Tag {
+        let kind = TagKind::from_name(name.as_ref());
+        Tag{kind,description}
+    }
+}
+
+
+// === TagKind ===
+
+/// The kinds of tag that the documentation recognises.
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
+pub enum TagKind {
+    /// The construct should no longer be used, and may be removed in the future.
+    Deprecated,
+    /// The behaviour of the construct changed after a certain version.
+    Modified,
+    /// The construct was added in a certain version.
+    Added,
+    /// The construct will be added in a future version.
+    Upcoming,
+    /// The construct has been removed and is no longer functional.
+    Removed,
+    /// A tag that the documentation does not define, which is kept as written.
+    Unrecognized(String),
+}
+
+impl TagKind {
+    /// Get the kind of the tag written as `name`.
+    pub fn from_name(name:&str) -> TagKind {
+        match name {
+            "DEPRECATED" => TagKind::Deprecated,
+            "MODIFIED"   => TagKind::Modified,
+            "ADDED"      => TagKind::Added,
+            "UPCOMING"   => TagKind::Upcoming,
+            "REMOVED"    => TagKind::Removed,
+            _            => TagKind::Unrecognized(name.into()),
+        }
+    }
+
+    /// The name of the tag as written in a comment.
+    pub fn name(&self) -> &str {
+        match self {
+            TagKind::Deprecated         => "DEPRECATED",
+            TagKind::Modified           => "MODIFIED",
+            TagKind::Added              => "ADDED",
+            TagKind::Upcoming           => "UPCOMING",
+            TagKind::Removed            => "REMOVED",
+            TagKind::Unrecognized(name) => name,
+        }
+    }
+}
+
+
+
+// ===============
+// === Section ===
+// ===============
+
+/// A section of the documentation, delimited by blank lines.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Section {
+    /// The kind of the section.
+    pub kind : SectionKind,
+    /// The title of the section, if it has one.
+    pub title : Option<Vec<Inline>>,
+    /// The elements making up the content of the section.
+    pub elements : Vec<Element>,
+}
+
+impl Section {
+    /// Constructor.
+    pub fn new(kind:SectionKind, title:Option<Vec<Inline>>) -> Section {
+        let elements = Vec::new();
+        Section{kind,title,elements}
+    }
+}
+
+
+// === SectionKind ===
+
+/// The kinds of section in the documentation.
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub enum SectionKind {
+    /// A section of plain text.
+    Raw,
+    /// A section describing important details of the construct, marked by `!`.
+    Important,
+    /// A section providing non-crucial information about the construct, marked by `?`.
+    Info,
+    /// A section with examples of using the construct, marked by `>`.
+    Example,
+}
+
+impl SectionKind {
+    /// Get the kind of section that `marker` introduces, if any.
+    pub fn from_marker(marker:char) -> Option<SectionKind> {
+        match marker {
+            '!' => Some(SectionKind::Important),
+            '?' => Some(SectionKind::Info),
+            '>' => Some(SectionKind::Example),
+            _   => None,
+        }
+    }
+
+    /// The character that introduces the section, if it has one.
+    pub fn marker(self) -> Option<char> {
+        match self {
+            SectionKind::Raw       => None,
+            SectionKind::Important => Some('!'),
+            SectionKind::Info      => Some('?'),
+            SectionKind::Example   => Some('>'),
+        }
+    }
+}
+
+
+
+// ===============
+// === Element ===
+// ===============
+
+/// A block-level element in the content of a section.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Element {
+    /// A paragraph of text.
+    Paragraph(Vec<Inline>),
+    /// A list, which may contain nested lists.
+    List(List),
+    /// A block of code, given as its lines with the indentation of the block removed.
+    Code(Vec<String>),
+}
+
+
+
+// ============
+// === List ===
+// ============
+
+/// A list of items.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct List {
+    /// The kind of the list.
+    pub kind : ListKind,
+    /// The items of the list.
+    pub items : Vec<ListItem>,
+}
+
+/// An item of a list.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct ListItem {
+    /// The content of the item.
+    pub content : Vec<Inline>,
+    /// The lists nested in the item.
+    pub lists : Vec<List>,
+}
+
+
+// === ListKind ===
+
+/// The kinds of list in the documentation.
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub enum ListKind {
+    /// A list whose items are marked by `-`.
+    Unordered,
+    /// A list whose items are marked by `*`.
+    Ordered,
+}
+
+impl ListKind {
+    /// Get the kind of list whose items are marked by `marker`, if any.
+    pub fn from_marker(marker:char) -> Option<ListKind> {
+        match marker {
+            '-' => Some(ListKind::Unordered),
+            '*' => Some(ListKind::Ordered),
+            _   => None,
+        }
+    }
+
+    /// The character that marks the items of the list.
+    pub fn marker(self) -> char {
+        match self {
+            ListKind::Unordered => '-',
+            ListKind::Ordered   => '*',
+        }
+    }
+}
+
+
+
+// ==============
+// === Inline ===
+// ==============
+
+/// An element of the text in paragraphs, titles and list items.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Inline {
+    /// Plain text.
+    Text(String),
+    /// Inline code, enclosed in backticks.
+    Code(String),
+    /// Text formatted with the provided `style`.
+    Formatted{style:Style, content:Vec<Inline>},
+    /// Text following a formatting marker that is never closed.
+    Unclosed{style:Style, content:Vec<Inline>},
+    /// A link to the provided `url`.
+    Link{title:String, url:String},
+    /// An image loaded from the provided `url`.
+    Image{name:String, url:String},
+}
+
+impl Inline {
+    /// Constructor.
+    pub fn text(text:impl Str) -> Inline {
+        Inline::Text(text.into())
+    }
+
+    /// Constructor.
+    pub fn code(code:impl Str) -> Inline {
+        Inline::Code(code.into())
+    }
+
+    /// Constructor.
+    pub fn formatted(style:Style, content:Vec<Inline>) -> Inline {
+        Inline::Formatted{style,content}
+    }
+
+    /// Constructor.
+    pub fn unclosed(style:Style, content:Vec<Inline>) -> Inline {
+        Inline::Unclosed{style,content}
+    }
+
+    /// Constructor.
+    pub fn link(title:impl Str, url:impl Str) -> Inline {
+        Inline::Link{title:title.into(),url:url.into()}
+    }
+
+    /// Constructor.
+    pub fn image(name:impl Str, url:impl Str) -> Inline {
+        Inline::Image{name:name.into(),url:url.into()}
+    }
+}
+
+
+// === Style ===
+
+/// The styles of text formatting.
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub enum Style {
+    /// Bold text, enclosed in `*`.
+    Bold,
+    /// Italic text, enclosed in `_`.
+    Italic,
+    /// Struck through text, enclosed in `~`.
+    Strikethrough,
+}
+
+impl Style {
+    /// Get the style that `marker` delimits, if any.
+    pub fn from_marker(marker:char) -> Option<Style> {
+        match marker {
+            '*' => Some(Style::Bold),
+            '_' => Some(Style::Italic),
+            '~' => Some(Style::Strikethrough),
+            _   => None,
+        }
+    }
+
+    /// The character that delimits text in the style.
+    pub fn marker(self) -> char {
+        match self {
+            Style::Bold          => '*',
+            Style::Italic        => '_',
+            Style::Strikethrough => '~',
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! This module renders documentation as HTML.
+//!
+//! The HTML is annotated with classes rather than styled, so that each of its users can present
+//! the documentation in their own way. Mistakes in the documentation, such as formatting that is
+//! never closed, are marked with the `unclosed` class so that they can be highlighted.
+//!
+//! Links and images are only rendered for relative URLs and URLs with one of the
+//! [`SAFE_URL_SCHEMES`], as others, such as `javascript:` URLs, could run code in the page showing
+//! the documentation. Their text is marked with the `invalid` class instead.
+
+use crate::prelude::*;
+
+use crate::ast::Documentation;
+use crate::ast::Element;
+use crate::ast::Inline;
+use crate::ast::List;
+use crate::ast::ListKind;
+use crate::ast::Section;
+use crate::ast::SectionKind;
+use crate::ast::Style;
+use crate::ast::Tag;
+use crate::ast::TagKind;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The URL schemes that links and images may use.
+pub const SAFE_URL_SCHEMES:&[&str] = &["http","https","mailto"];
+
+
+
+// ============
+// === Html ===
+// ============
+
+/// Documentation that can be rendered as HTML.
+pub trait Html {
+    /// Append the HTML for `self` to `output`.
+    fn write_html(&self, output:&mut String);
+
+    /// Render `self` as HTML.
+    fn to_html(&self) -> String {
+        let mut output = String::new();
+        self.write_html(&mut output);
+        output
+    }
+}
+
+/// Escape the characters of `text` that have a meaning in HTML.
+pub fn escape(text:&str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&'  => escaped.push_str("&amp;"),
+            '<'  => escaped.push_str("&lt;"),
+            '>'  => escaped.push_str("&gt;"),
+            '"'  => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _    => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+/// Check whether `url` is relative or uses one of the [`SAFE_URL_SCHEMES`].
+///
+/// Browsers ignore whitespace and control characters in the scheme of a URL, so they are ignored
+/// here as well.
+pub fn is_safe_url(url:&str) -> bool {
+    let url         = url.chars().filter(|char| !char.is_whitespace() && !char.is_control());
+    let url         = url.collect::<String>();
+    let scheme      = url.split(&[':','/','?','#'][..]).next().unwrap_or_default();
+    let is_relative = !url[scheme.len()..].starts_with(':');
+    is_relative || SAFE_URL_SCHEMES.iter().any(|safe| scheme.eq_ignore_ascii_case(safe))
+}
+
+/// Render `text` as the text of a link or image whose URL is not safe.
+fn write_invalid(text:&str, output:&mut String) {
+    output.push_str(&format!(r#"<span class="invalid">{}</span>"#,escape(text)));
+}
+
+
+// === Trait Impls ===
+
+impl<T:Html> Html for [T] {
+    fn write_html(&self, output:&mut String) {
+        self.iter().for_each(|item| item.write_html(output));
+    }
+}
+
+impl<T:Html> Html for Vec<T> {
+    fn write_html(&self, output:&mut String) {
+        self.as_slice().write_html(output)
+    }
+}
+
+impl Html for Documentation {
+    fn write_html(&self, output:&mut String) {
+        output.push_str(r#"<div class="doc">"#);
+        if !self.tags.is_empty() {
+            output.push_str(r#"<div class="tags">"#);
+            self.tags.write_html(output);
+            output.push_str("</div>");
+        }
+        if let Some(synopsis) = self.synopsis() {
+            output.push_str(r#"<div class="synopsis">"#);
+            synopsis.write_html(output);
+            output.push_str("</div>");
+        }
+        if !self.body().is_empty() {
+            output.push_str(r#"<div class="body">"#);
+            self.body().write_html(output);
+            output.push_str("</div>");
+        }
+        output.push_str("</div>");
+    }
+}
+
+impl Html for Tag {
+    fn write_html(&self, output:&mut String) {
+        let class = match &self.kind {
+            TagKind::Unrecognized(_) => "unrecognized".into(),
+            kind                     => kind.name().to_lowercase(),
+        };
+        output.push_str(&format!(r#"<div class="tag {}">"#,class));
+        output.push_str(&format!(r#"<span class="name">{}</span>"#,escape(self.kind.name())));
+        if let Some(description) = &self.description {
+            let description = escape(description);
+            output.push_str(&format!(r#"<span class="description">{}</span>"#,description));
+        }
+        output.push_str("</div>");
+    }
+}
+
+impl Html for Section {
+    fn write_html(&self, output:&mut String) {
+        let class = match self.kind {
+            SectionKind::Raw       => "raw",
+            SectionKind::Important => "important",
+            SectionKind::Info      => "info",
+            SectionKind::Example   => "example",
+        };
+        output.push_str(&format!(r#"<div class="section {}">"#,class));
+        if let Some(title) = &self.title {
+            output.push_str(r#"<h2 class="title">"#);
+            title.write_html(output);
+            output.push_str("</h2>");
+        }
+        self.elements.write_html(output);
+        output.push_str("</div>");
+    }
+}
+
+impl Html for Element {
+    fn write_html(&self, output:&mut String) {
+        match self {
+            Element::Paragraph(content) => {
+                output.push_str("<p>");
+                content.write_html(output);
+                output.push_str("</p>");
+            }
+            Element::List(list) => list.write_html(output),
+            Element::Code(lines) => {
+                output.push_str("<pre><code>");
+                output.push_str(&escape(&lines.join("\n")));
+                output.push_str("</code></pre>");
+            }
+        }
+    }
+}
+
+impl Html for List {
+    fn write_html(&self, output:&mut String) {
+        let tag = match self.kind {
+            ListKind::Unordered => "ul",
+            ListKind::Ordered   => "ol",
+        };
+        output.push_str(&format!("<{}>",tag));
+        for item in &self.items {
+            output.push_str("<li>");
+            item.content.write_html(output);
+            item.lists.write_html(output);
+            output.push_str("</li>");
+        }
+        output.push_str(&format!("</{}>",tag));
+    }
+}
+
+impl Html for Inline {
+    fn write_html(&self, output:&mut String) {
+        match self {
+            Inline::Text(text) => output.push_str(&escape(text)),
+            Inline::Code(code) => output.push_str(&format!("<code>{}</code>",escape(code))),
+            Inline::Formatted{style,content} => {
+                let tag = match style {
+                    Style::Bold          => "b",
+                    Style::Italic        => "i",
+                    Style::Strikethrough => "s",
+                };
+                output.push_str(&format!("<{}>",tag));
+                content.write_html(output);
+                output.push_str(&format!("</{}>",tag));
+            }
+            Inline::Unclosed{style,content} => {
+                output.push_str(r#"<span class="unclosed">"#);
+                output.push(style.marker());
+                content.write_html(output);
+                output.push_str("</span>");
+            }
+            Inline::Link{title,url} if is_safe_url(url) => {
+                let link = format!(r#"<a href="{}">{}</a>"#,escape(url),escape(title));
+                output.push_str(&link);
+            }
+            Inline::Image{name,url} if is_safe_url(url) => {
+                let image = format!(r#"<img src="{}" alt="{}">"#,escape(url),escape(name));
+                output.push_str(&image);
+            }
+            Inline::Link{title,..} => write_invalid(title,output),
+            Inline::Image{name,..} => write_invalid(name,output),
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_text() {
+        let expected = "a &lt; b &amp;&amp; &quot;c&quot; &gt; &#39;d&#39;";
+        assert_eq!(escape(r#"a < b && "c" > 'd'"#),expected);
+    }
+
+    #[test]
+    fn render_inline() {
+        let content = vec![
+            Inline::text("Use "),
+            Inline::code("a<b"),
+            Inline::formatted(Style::Bold,vec![Inline::text("now")]),
+            Inline::unclosed(Style::Italic,vec![Inline::link("docs","https://enso.org")]),
+        ];
+        let expected = concat!(
+            "Use <code>a&lt;b</code><b>now</b>",
+            r#"<span class="unclosed">_<a href="https://enso.org">docs</a></span>"#
+        );
+        assert_eq!(content.to_html(),expected);
+    }
+
+    #[test]
+    fn safe_urls() {
+        assert!(is_safe_url("https://enso.org"));
+        assert!(is_safe_url("HTTP://enso.org"));
+        assert!(is_safe_url("mailto:contact@enso.org"));
+        assert!(is_safe_url("../docs/index.html"));
+        assert!(is_safe_url("images/a:b.png"));
+        assert!(is_safe_url("#section"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url(" JavaScript:alert(1)"));
+        assert!(!is_safe_url("java\tscript:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<script>alert(1)</script>"));
+    }
+
+    #[test]
+    fn render_unsafe_urls() {
+        let content = vec![
+            Inline::link("click","javascript:alert(1)"),
+            Inline::image("a<b","vbscript:msgbox(1)"),
+        ];
+        let expected = concat!(
+            r#"<span class="invalid">click</span>"#,
+            r#"<span class="invalid">a&lt;b</span>"#,
+        );
+        assert_eq!(content.to_html(),expected);
+    }
+
+    #[test]
+    fn render_documentation() {
+        let tag      = Tag::new("DEPRECATED",Some("Use `foo`.".into()));
+        let unknown  = Tag::new("ALPHA",None);
+        let synopsis = Section{
+            kind     : SectionKind::Raw,
+            title    : None,
+            elements : vec![Element::Paragraph(vec![Inline::text("Adds.")])],
+        };
+        let example = Section{
+            kind     : SectionKind::Example,
+            title    : Some(vec![Inline::text("Example")]),
+            elements : vec![
+                Element::List(List{kind:ListKind::Ordered,items:vec![default()]}),
+                Element::Code(vec!["1 + 2".into(),"  <3".into()]),
+            ],
+        };
+        let documentation = Documentation{tags:vec![tag,unknown],sections:vec![synopsis,example]};
+        let expected      = concat!(
+            r#"<div class="doc">"#,
+            r#"<div class="tags">"#,
+            r#"<div class="tag deprecated"><span class="name">DEPRECATED</span>"#,
+            r#"<span class="description">Use `foo`.</span></div>"#,
+            r#"<div class="tag unrecognized"><span class="name">ALPHA</span></div>"#,
+            "</div>",
+            r#"<div class="synopsis"><div class="section raw"><p>Adds.</p></div></div>"#,
+            r#"<div class="body"><div class="section example">"#,
+            r#"<h2 class="title">Example</h2><ol><li></li></ol>"#,
+            "<pre><code>1 + 2\n  &lt;3</code></pre>",
+            "</div></div>",
+            "</div>",
+        );
+        assert_eq!(documentation.to_html(),expected);
+    }
+}
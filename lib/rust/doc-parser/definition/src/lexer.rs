@@ -0,0 +1,463 @@
+//! This module contains the definition of the lexer for Enso documentation comments.
+
+use crate::prelude::*;
+use flexer::*;
+
+use crate::library::ast::Style;
+use crate::library::token::Marker;
+use crate::library::token::Segment;
+use crate::library::token::Token;
+use crate::library::token;
+
+use flexer::automata::pattern::Pattern;
+use flexer::group::Group;
+use flexer::group::Registry;
+use flexer::prelude::logger::Disabled;
+use flexer::prelude::reader;
+use flexer::State as FlexerState;
+use flexer;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+type Flexer = flexer::Flexer<State<Logger>,token::Stream,Logger>;
+
+
+
+// =================
+// === Doc Lexer ===
+// =================
+
+/// The lexer for the body of Enso documentation comments.
+///
+/// The body of a comment is the text following its `##`, with the margin of the comment removed
+/// from each line after the first.
+#[derive(Clone,Debug)]
+pub struct DocLexer(Flexer);
+
+impl Deref for DocLexer {
+    type Target = Flexer;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DocLexer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Functions for working with the lexer.
+impl DocLexer {
+    /// Construct a new instance of the documentation lexer.
+    pub fn new() -> Self {
+        let logger = Logger::new("DocLexer");
+        let lexer  = Flexer::new(logger);
+        DocLexer(lexer)
+    }
+}
+
+
+// === Result Functionality ===
+
+impl DocLexer {
+    /// Append the provided `token` to the lexer output.
+    pub fn append_token(&mut self, token:Token) {
+        debug!(self.logger,"Append: {&token:?}");
+        self.output.append(token);
+    }
+
+    /// Consume the current match and replace it with the empty string.
+    pub fn consume_current(&mut self) -> String {
+        debug!(self.logger,"Consume: {self.current_match:?}");
+        mem::take(&mut self.current_match)
+    }
+
+    /// Discard the current match and replace it with the empty string.
+    pub fn discard_current(&mut self) {
+        debug!(self.logger,"Discard: {self.current_match:?}");
+        self.current_match.clear();
+    }
+}
+
+
+
+// === Common Patterns ===
+
+/// Basic character classification
+#[allow(dead_code)]
+impl DocLexer {
+    /// Match at least one space.
+    fn spaces() -> Pattern {
+        c!(' ').many1()
+    }
+
+    /// Match a newline, in either the Unix (LF) or the Windows (CRLF) style.
+    fn newline() -> Pattern {
+        c!('\n') | l!("\r\n")
+    }
+
+    /// Match the rest of a line, not including its line ending.
+    fn rest_of_line() -> Pattern {
+        Pattern::none_of("\r\n").many()
+    }
+}
+
+
+
+// === Tags ===
+
+/// The set of rules for lexing the tags at the top of a comment.
+#[allow(dead_code)]
+impl DocLexer {
+
+    /// Triggered on a line holding a tag, along with the line ending that follows it.
+    fn on_tag<R:LazyReader>(&mut self, _reader:&mut R) {
+        let current     = self.consume_current();
+        let line        = current.trim_end_matches(&['\r','\n'][..]).trim_start_matches(' ');
+        let (name,rest) = match line.find(' ') {
+            Some(ix) => (&line[..ix],line[ix + 1..].trim_end()),
+            None     => (line,""),
+        };
+        let description = if rest.is_empty() { None } else { Some(rest) };
+        self.append_token(Token::tag(name,description));
+    }
+
+    /// Triggered on the first line of the comment that does not hold a tag.
+    ///
+    /// The first line of a comment directly follows the `##`, so its indentation is not
+    /// significant.
+    fn on_tags_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let newline    = self.newline;
+        let line_start = self.line_start;
+        self.discard_current();
+        self.line.begin(0);
+        self.push_state(newline);
+        self.push_state(line_start);
+    }
+
+    /// The rules for lexing tags.
+    fn add_tag_rules(lexer:&mut DocLexer) {
+        let upper       = Pattern::range('A'..='Z');
+        let opt_spaces  = DocLexer::spaces().opt();
+        let description = c!(' ') >> DocLexer::rest_of_line();
+        let line_end    = DocLexer::newline() | Pattern::eof();
+        let tag         = &opt_spaces >> &upper >> upper.many1() >> description.opt() >> &line_end;
+        let blank_line  = &opt_spaces >> DocLexer::newline();
+        let eof         = &opt_spaces >> Pattern::eof();
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        initial_state.create_rule(&tag,       "self.on_tag(reader)");
+        initial_state.create_rule(&blank_line,"self.on_blank_line(reader)");
+        initial_state.create_rule(&eof,       "self.on_eof(reader)");
+        initial_state.create_rule(&opt_spaces,"self.on_tags_end(reader)");
+    }
+}
+
+
+
+// === Lines ===
+
+/// The set of rules for lexing the lines of a comment.
+#[allow(dead_code)]
+impl DocLexer {
+
+    /// Triggered at the start of each line after the first, once its indentation has been matched.
+    fn on_line_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let line_start = self.line_start;
+        let indent     = self.consume_current().chars().count();
+        self.line.begin(indent);
+        self.push_state(line_start);
+    }
+
+    /// Triggered on a line that contains only whitespace.
+    fn on_blank_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        self.append_token(Token::BlankLine);
+    }
+
+    /// Triggered on a list or section marker at the start of a line.
+    fn on_marker<R:LazyReader>(&mut self, reader:&mut R) {
+        let current      = self.consume_current();
+        self.line.marker = current.chars().next().and_then(Marker::from_char);
+        self.on_line_text_start(reader);
+    }
+
+    /// Triggered at the start of the text on a line, once its marker has been matched.
+    fn on_line_text_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_line = self.text_line;
+        self.pop_state();
+        self.push_state(text_line);
+    }
+
+    /// Triggered at the end of a line.
+    fn on_line_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        let line = self.line.end();
+        self.append_token(line);
+        self.pop_state();
+    }
+
+    /// Triggered when the end of the comment is reached outside of a line.
+    fn on_eof<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+    }
+
+    /// The rules for lexing the lines of a comment.
+    fn add_line_rules(lexer:&mut DocLexer) {
+        let opt_spaces = DocLexer::spaces().opt();
+        let blank_line = &opt_spaces >> DocLexer::newline();
+        let eof        = &opt_spaces >> Pattern::eof();
+        let marker     = Pattern::any_of("-*!?>") >> c!(' ');
+
+        let newline_id = lexer.newline;
+        let newline    = lexer.group_mut(newline_id);
+        newline.create_rule(&opt_spaces,"self.on_line_start(reader)");
+        newline.create_rule(&blank_line,"self.on_blank_line(reader)");
+        newline.create_rule(&eof,       "self.on_eof(reader)");
+
+        let line_start_id = lexer.line_start;
+        let line_start    = lexer.group_mut(line_start_id);
+        line_start.create_rule(&marker,           "self.on_marker(reader)");
+        line_start.create_rule(&Pattern::always(),"self.on_line_text_start(reader)");
+    }
+}
+
+
+
+// === Inline Text ===
+
+/// The set of rules for lexing the text on a line.
+#[allow(dead_code)]
+impl DocLexer {
+
+    /// Triggered on plain text.
+    fn on_text<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text = self.consume_current();
+        self.line.push_text(text);
+    }
+
+    /// Triggered on inline code enclosed in backticks.
+    fn on_code<R:LazyReader>(&mut self, _reader:&mut R) {
+        let current = self.consume_current();
+        let code    = &current[1..current.len() - 1];
+        self.line.push_segment(Segment::code(code));
+    }
+
+    /// Triggered on a formatting marker.
+    fn on_formatter<R:LazyReader>(&mut self, _reader:&mut R) {
+        let current = self.consume_current();
+        if let Some(style) = current.chars().next().and_then(Style::from_marker) {
+            self.line.push_segment(Segment::Formatter(style));
+        }
+    }
+
+    /// Triggered on a link.
+    fn on_link<R:LazyReader>(&mut self, _reader:&mut R) {
+        let current     = self.consume_current();
+        let (title,url) = DocLexer::split_link(&current);
+        self.line.push_segment(Segment::link(title,url));
+    }
+
+    /// Triggered on an image.
+    fn on_image<R:LazyReader>(&mut self, _reader:&mut R) {
+        let current    = self.consume_current();
+        let (name,url) = DocLexer::split_link(&current[1..]);
+        self.line.push_segment(Segment::image(name,url));
+    }
+
+    /// Split a link of the form `[title](url)` into its title and its url.
+    fn split_link(link:&str) -> (&str,&str) {
+        let link = &link[1..link.len() - 1];
+        match link.find("](") {
+            Some(ix) => (&link[..ix],&link[ix + 2..]),
+            None     => (link,""),
+        }
+    }
+
+    /// The rules for lexing the text on a line.
+    fn add_inline_rules(lexer:&mut DocLexer) {
+        let text      = Pattern::none_of("`*_~[!\r\n").many1();
+        let code      = c!('`') >> Pattern::none_of("`\r\n").many() >> c!('`');
+        let formatter = Pattern::any_of("*_~");
+        let title     = Pattern::none_of("]\r\n").many();
+        let url       = Pattern::none_of(")\r\n").many();
+        let link      = c!('[') >> title >> l!("](") >> url >> c!(')');
+        let image     = c!('!') >> &link;
+        let line_end  = DocLexer::newline() | Pattern::eof();
+
+        let text_line_id = lexer.text_line;
+        let text_line    = lexer.group_mut(text_line_id);
+        text_line.create_rule(&text,          "self.on_text(reader)");
+        text_line.create_rule(&code,          "self.on_code(reader)");
+        text_line.create_rule(&formatter,     "self.on_formatter(reader)");
+        text_line.create_rule(&link,          "self.on_link(reader)");
+        text_line.create_rule(&image,         "self.on_image(reader)");
+        text_line.create_rule(&line_end,      "self.on_line_end(reader)");
+        text_line.create_rule(&Pattern::any(),"self.on_text(reader)");
+    }
+}
+
+
+
+// === Trait Impls ===
+
+impl flexer::Definition for DocLexer {
+    fn define() -> Self {
+        let mut lexer = DocLexer::new();
+
+        DocLexer::add_tag_rules(&mut lexer);
+        DocLexer::add_line_rules(&mut lexer);
+        DocLexer::add_inline_rules(&mut lexer);
+
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        &self.lexer_states
+    }
+
+    fn set_up(&mut self) {}
+
+    fn tear_down(&mut self) {}
+}
+
+impl Default for DocLexer {
+    fn default() -> Self {
+        DocLexer::new()
+    }
+}
+
+
+
+// ===================
+// === Lexer State ===
+// ===================
+
+/// The state for the documentation lexer.
+#[derive(Clone,Debug)]
+pub struct State<Logger> {
+    /// The logger for the lexing state.
+    logger : Logger,
+    /// The bookmarks used by the lexer.
+    bookmarks : reader::BookmarkManager,
+    /// The registry of states for the lexer.
+    lexer_states : group::Registry,
+    /// The initial state of the lexer, in which the tags at the top of the comment are lexed.
+    initial_state : group::Identifier,
+    /// The state entered at the start of each line after the first.
+    newline : group::Identifier,
+    /// The state for lexing the marker at the start of a line.
+    line_start : group::Identifier,
+    /// The state for lexing the text on a line.
+    text_line : group::Identifier,
+    /// The line being lexed.
+    line : LineState,
+}
+
+impl<Logger:AnyLogger<Owned=Logger>> State<Logger> {
+    /// Get a reference to the group for the provided identifier.
+    pub fn group(&self, group:group::Identifier) -> &Group {
+        self.groups().group(group)
+    }
+
+    /// Get a mutable reference to the group for the provided identifier.
+    pub fn group_mut(&mut self, group:group::Identifier) -> &mut Group {
+        self.groups_mut().group_mut(group)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
+    fn new(parent_logger:&impl AnyLogger) -> Self {
+        let logger           = <Logger>::sub(parent_logger, "State");
+        let bookmarks        = default();
+        let mut lexer_states = group::Registry::default();
+        let initial_state    = lexer_states.define_group("ROOT",None);
+        let newline          = lexer_states.define_group("NEWLINE",None);
+        let line_start       = lexer_states.define_group("LINE_START",None);
+        let text_line        = lexer_states.define_group("TEXT_LINE",None);
+        let line             = default();
+        Self{logger,bookmarks,lexer_states,initial_state,newline,line_start,text_line,line}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        self.initial_state
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &reader::BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut reader::BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String, GenError> {
+        generate::specialize(self,"DocLexer","token::Stream")
+    }
+
+    fn reset(&mut self) {
+        self.line = default();
+    }
+}
+
+
+
+// =================
+// === LineState ===
+// =================
+
+/// The state of the line of the comment that is being lexed.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct LineState {
+    /// The indentation of the line.
+    pub indent : usize,
+    /// The marker at the start of the line.
+    pub marker : Option<Marker>,
+    /// The segments lexed so far on the line.
+    pub segments : Vec<Segment>,
+}
+
+impl LineState {
+    /// Begin a line with the provided `indent`.
+    pub fn begin(&mut self, indent:usize) {
+        *self       = default();
+        self.indent = indent;
+    }
+
+    /// Append plain `text` to the line, joining it with any text directly before it.
+    pub fn push_text(&mut self, text:String) {
+        match self.segments.last_mut() {
+            Some(Segment::Text(last)) => last.push_str(&text),
+            _                         => self.segments.push(Segment::Text(text)),
+        }
+    }
+
+    /// Append a `segment` to the line.
+    pub fn push_segment(&mut self, segment:Segment) {
+        self.segments.push(segment);
+    }
+
+    /// End the line, returning its token.
+    pub fn end(&mut self) -> Token {
+        let line = mem::take(self);
+        Token::line(line.indent,line.marker,line.segments)
+    }
+}
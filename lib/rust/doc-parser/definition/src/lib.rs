@@ -0,0 +1,38 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This library defines the parser for Enso documentation comments, which turns the body of a
+//! comment into a tree of documentation that can be rendered as HTML.
+//!
+//! Parsing happens in two phases. The lexer, defined with the flexer, splits the comment into
+//! lines and lexes the inline syntax of each of them. The parser then assembles these lines into
+//! tags, sections, paragraphs, lists and code blocks, based on their markers and indentation.
+
+pub mod ast;
+pub mod html;
+pub mod lexer;
+pub mod parser;
+pub mod token;
+
+/// A module that can be re-exported under the same name in the generation crate.
+///
+/// This is necessary to avoid issues with paths getting wonky when the code is generated from the
+/// documentation lexer definition. In this project, imports should _not_ be made from the crate
+/// root _except_ through use of this `library` module.
+pub mod library {
+    pub use crate::ast;
+    pub use crate::token;
+}
+
+/// A collection of functionality for working with the documentation parser definition.
+pub mod prelude {
+    pub use flexer::prelude::*;
+    pub use flexer::prelude::logger::*;
+}
@@ -0,0 +1,508 @@
+//! This module contains the parser for documentation comments, which assembles the lines lexed by
+//! the [`crate::lexer`] into a tree of [`Documentation`].
+//!
+//! A comment starts with its tags, which are followed by sections separated by blank lines. A line
+//! after a blank line only stays in the current section if it is indented beyond the first line of
+//! that section. Within a section, lines starting with list markers form lists nested by their
+//! indentation, lines indented by at least four spaces beyond the baseline of the section are code,
+//! and any other lines form paragraphs.
+
+use crate::prelude::*;
+
+use crate::ast::Documentation;
+use crate::ast::Element;
+use crate::ast::Inline;
+use crate::ast::List;
+use crate::ast::ListKind;
+use crate::ast::Section;
+use crate::ast::SectionKind;
+use crate::ast::Style;
+use crate::ast::Tag;
+use crate::token::Marker;
+use crate::token::Segment;
+use crate::token::Token;
+use crate::token;
+
+
+
+// =============
+// === Parse ===
+// =============
+
+/// Parse the lines of a documentation comment into its documentation.
+pub fn parse(tokens:token::Stream) -> Documentation {
+    let mut parser = Parser::default();
+    for token in Vec::from(tokens) {
+        parser.push(token);
+    }
+    parser.finish()
+}
+
+/// Assemble the `segments` of some text into inline elements, matching up its formatting markers.
+///
+/// A marker closes the innermost formatting of its style that is still open. Any formatting opened
+/// after that one is closed along with it and then opened again, so that formatting does not have
+/// to be closed in the order it was opened. Formatting that is never closed is reported as
+/// [`Inline::Unclosed`].
+pub fn inline(segments:Vec<Segment>) -> Vec<Inline> {
+    let mut root   = Vec::new();
+    let mut frames = Vec::<Frame>::new();
+    for segment in segments {
+        let inline = match segment {
+            Segment::Text(text)       => Inline::Text(text),
+            Segment::Code(code)       => Inline::Code(code),
+            Segment::Link{title,url}  => Inline::Link{title,url},
+            Segment::Image{name,url}  => Inline::Image{name,url},
+            Segment::Formatter(style) => {
+                match frames.iter().rposition(|frame| frame.style == style) {
+                    Some(ix) => {
+                        let mut reopened = Vec::new();
+                        while frames.len() > ix {
+                            if let Some(frame) = frames.pop() {
+                                reopened.push(frame.style);
+                                let target = frames.last_mut().map_or(&mut root,|f| &mut f.content);
+                                frame.close().into_iter().for_each(|inline| push(target,inline));
+                            }
+                        }
+                        reopened.pop();
+                        frames.extend(reopened.into_iter().rev().map(Frame::reopened));
+                    }
+                    None => frames.push(Frame::new(style)),
+                }
+                continue
+            }
+        };
+        let target = frames.last_mut().map_or(&mut root,|frame| &mut frame.content);
+        push(target,inline);
+    }
+    while let Some(frame) = frames.pop() {
+        let target = frames.last_mut().map_or(&mut root,|frame| &mut frame.content);
+        frame.close_unclosed().into_iter().for_each(|inline| push(target,inline));
+    }
+    root
+}
+
+/// Append `inline` to `target`, joining adjacent text.
+fn push(target:&mut Vec<Inline>, inline:Inline) {
+    match (target.last_mut(),inline) {
+        (Some(Inline::Text(last)),Inline::Text(text)) => last.push_str(&text),
+        (_,inline)                                    => target.push(inline),
+    }
+}
+
+/// The source representation of a line with the provided `marker` and `segments`.
+fn line_repr(marker:Option<Marker>, segments:&[Segment]) -> String {
+    let marker = marker.map(Marker::repr).unwrap_or_default();
+    marker + &segments.iter().map(Segment::repr).collect::<String>()
+}
+
+/// The segments of a line in which the `marker` has no meaning, so it is taken as text.
+fn marker_as_text(marker:Option<Marker>, mut segments:Vec<Segment>) -> Vec<Segment> {
+    if let Some(marker) = marker {
+        segments.insert(0,Segment::text(marker.repr()));
+    }
+    segments
+}
+
+
+
+// =============
+// === Frame ===
+// =============
+
+/// Formatted text whose closing marker has not been found yet.
+#[derive(Clone,Debug)]
+struct Frame {
+    /// The style of the text.
+    style : Style,
+    /// The content of the text so far.
+    content : Vec<Inline>,
+    /// Whether the formatting was opened again after closing another formatting.
+    is_reopened : bool,
+}
+
+impl Frame {
+    /// Constructor.
+    fn new(style:Style) -> Frame {
+        let content     = default();
+        let is_reopened = false;
+        Frame{style,content,is_reopened}
+    }
+
+    /// Construct a frame that continues formatting closed along with another one.
+    fn reopened(style:Style) -> Frame {
+        Frame{is_reopened:true,..Frame::new(style)}
+    }
+
+    /// Close the frame with a marker.
+    ///
+    /// A reopened frame without content does not correspond to any text, so it is dropped.
+    fn close(self) -> Option<Inline> {
+        let is_empty = self.is_reopened && self.content.is_empty();
+        if is_empty { None } else { Some(Inline::formatted(self.style,self.content)) }
+    }
+
+    /// Close the frame at the end of the text, without a marker.
+    fn close_unclosed(self) -> Option<Inline> {
+        let is_empty = self.is_reopened && self.content.is_empty();
+        if is_empty { None } else { Some(Inline::unclosed(self.style,self.content)) }
+    }
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+/// The number of spaces beyond the baseline of a section at which its lines become code.
+const CODE_INDENT : usize = 4;
+
+/// The parser for documentation comments, which consumes the lines of a comment one at a time.
+#[derive(Clone,Debug,Default)]
+pub struct Parser {
+    /// The documentation parsed so far.
+    documentation : Documentation,
+    /// The section being parsed.
+    section : Option<SectionParser>,
+    /// The number of blank lines since the last line of text.
+    blank_lines : usize,
+}
+
+impl Parser {
+    /// Consume the next line of the comment.
+    pub fn push(&mut self, token:Token) {
+        match token {
+            Token::Tag{name,description} => {
+                self.documentation.tags.push(Tag::new(name,description));
+            }
+            Token::BlankLine => {
+                self.blank_lines += 1;
+            }
+            Token::Line{indent,marker,segments} => {
+                self.push_line(indent,marker,segments);
+                self.blank_lines = 0;
+            }
+        }
+    }
+
+    /// Consume a line of text, which either continues the current section or starts a new one.
+    fn push_line(&mut self, indent:usize, marker:Option<Marker>, segments:Vec<Segment>) {
+        let blank_lines = self.blank_lines;
+        match &mut self.section {
+            Some(section) if blank_lines == 0 || indent > section.indent => {
+                section.push_line(indent,marker,segments,blank_lines);
+            }
+            _ => {
+                let has_title = blank_lines >= 2 && self.section.is_some();
+                self.end_section();
+                self.section = Some(SectionParser::new(indent,marker,segments,has_title));
+            }
+        }
+    }
+
+    /// Finish the section being parsed.
+    fn end_section(&mut self) {
+        if let Some(section) = self.section.take() {
+            self.documentation.sections.push(section.finish());
+        }
+    }
+
+    /// Finish parsing, returning the documentation of the comment.
+    pub fn finish(mut self) -> Documentation {
+        self.end_section();
+        self.documentation
+    }
+}
+
+
+
+// =====================
+// === SectionParser ===
+// =====================
+
+/// The parser for a single section of a documentation comment.
+#[derive(Clone,Debug)]
+struct SectionParser {
+    /// The section parsed so far.
+    section : Section,
+    /// The indentation of the first line of the section.
+    indent : usize,
+    /// The indentation of the text in the section, beyond which lines are taken as code.
+    baseline : usize,
+    /// The element that the next line of the section can continue.
+    open : Open,
+}
+
+impl SectionParser {
+    /// Start a section with its first line.
+    ///
+    /// A section marker turns the rest of the line into the title of the section, and moves the
+    /// baseline of the section to the text after the marker. Otherwise, the line is only a title if
+    /// the section `has_title`.
+    fn new
+    (indent:usize, marker:Option<Marker>, segments:Vec<Segment>, has_title:bool) -> SectionParser {
+        let open = Open::Nothing;
+        match marker {
+            Some(marker@Marker::Section(kind)) => {
+                let title    = Some(inline(segments));
+                let section  = Section::new(kind,title);
+                let baseline = indent + marker.repr().chars().count();
+                SectionParser{section,indent,baseline,open}
+            }
+            _ if has_title => {
+                let title   = Some(inline(marker_as_text(marker,segments)));
+                let section = Section::new(SectionKind::Raw,title);
+                SectionParser{section,indent,baseline:indent,open}
+            }
+            _ => {
+                let section    = Section::new(SectionKind::Raw,None);
+                let mut parser = SectionParser{section,indent,baseline:indent,open};
+                parser.push_line(indent,marker,segments,0);
+                parser
+            }
+        }
+    }
+
+    /// Consume a line of the section, which follows `blank_lines` blank lines.
+    fn push_line
+    (&mut self, indent:usize, marker:Option<Marker>, segments:Vec<Segment>, blank_lines:usize) {
+        let is_code = match &self.open {
+            Open::List{..} => blank_lines > 0 && self.is_code_indent(indent),
+            _              => self.is_code_indent(indent),
+        };
+        match marker {
+            Some(Marker::List(kind)) if !is_code => {
+                self.push_list_item(indent,kind,segments,blank_lines);
+            }
+            _ if is_code => self.push_code_line(indent,line_repr(marker,&segments),blank_lines),
+            _ => match &mut self.open {
+                Open::List{indents,segments:item}
+                if blank_lines == 0 && matches!(indents.last(),Some(last) if indent > *last) => {
+                    push_text_line(item,marker_as_text(marker,segments));
+                }
+                Open::Paragraph(paragraph) if blank_lines == 0 => {
+                    push_text_line(paragraph,marker_as_text(marker,segments));
+                }
+                _ => {
+                    self.close();
+                    self.open = Open::Paragraph(marker_as_text(marker,segments));
+                }
+            }
+        }
+    }
+
+    /// Check whether a line with the provided `indent` is indented far enough to be code.
+    fn is_code_indent(&self, indent:usize) -> bool {
+        indent >= self.baseline + CODE_INDENT
+    }
+
+    /// Consume a line of code.
+    ///
+    /// Code keeps its indentation relative to the least indented line of its block, and its blank
+    /// lines.
+    fn push_code_line(&mut self, indent:usize, code:String, blank_lines:usize) {
+        if let Open::Code{indent:code_indent} = &mut self.open {
+            if let Some(Element::Code(lines)) = self.section.elements.last_mut() {
+                if indent < *code_indent {
+                    let offset = " ".repeat(*code_indent - indent);
+                    let lines  = lines.iter_mut().filter(|line| !line.is_empty());
+                    lines.for_each(|line| line.insert_str(0,&offset));
+                    *code_indent = indent;
+                }
+                let offset = " ".repeat(indent - *code_indent);
+                lines.resize(lines.len() + blank_lines,String::new());
+                lines.push(offset + &code);
+                return
+            }
+        }
+        self.close();
+        self.section.elements.push(Element::Code(vec![code]));
+        self.open = Open::Code{indent};
+    }
+
+    /// Consume a line holding a list item with the provided `indent`.
+    ///
+    /// An item indented beyond the last item of the list starts a nested list, while an item that
+    /// is indented less returns to the list of the matching indentation. An item whose kind does
+    /// not match the list it belongs to starts a new list at the same level.
+    fn push_list_item
+    (&mut self, indent:usize, kind:ListKind, segments:Vec<Segment>, blank_lines:usize) {
+        let elements = &mut self.section.elements;
+        match &mut self.open {
+            Open::List{indents,segments:item} if blank_lines == 0 => {
+                let depth = indents.len() - 1;
+                set_item_content(elements,depth,mem::take(item));
+                while indents.len() > 1 && matches!(indents.last(),Some(last) if indent < *last) {
+                    indents.pop();
+                }
+                let last  = indents.last().copied().unwrap_or_default();
+                let depth = indents.len() - 1;
+                if indent > last {
+                    if let Some(parent) = list_at(elements,depth).and_then(|l| l.items.last_mut()) {
+                        parent.lists.push(new_list(kind));
+                    }
+                    indents.push(indent);
+                } else if depth == 0 {
+                    match list_at(elements,0) {
+                        Some(list) if list.kind == kind => list.items.push(default()),
+                        _ => elements.push(Element::List(new_list(kind))),
+                    }
+                } else {
+                    let parent = list_at(elements,depth - 1).and_then(|l| l.items.last_mut());
+                    if let Some(parent) = parent {
+                        match parent.lists.last_mut() {
+                            Some(list) if list.kind == kind => list.items.push(default()),
+                            _ => parent.lists.push(new_list(kind)),
+                        }
+                    }
+                }
+                *item = segments;
+            }
+            _ => {
+                self.close();
+                self.section.elements.push(Element::List(new_list(kind)));
+                self.open = Open::List{indents:vec![indent],segments};
+            }
+        }
+    }
+
+    /// Close the element that is open, so that no further lines can continue it.
+    fn close(&mut self) {
+        match mem::replace(&mut self.open,Open::Nothing) {
+            Open::Paragraph(segments) => {
+                self.section.elements.push(Element::Paragraph(inline(segments)));
+            }
+            Open::List{indents,segments} => {
+                set_item_content(&mut self.section.elements,indents.len() - 1,segments);
+            }
+            Open::Code{..} | Open::Nothing => {}
+        }
+    }
+
+    /// Finish parsing the section.
+    fn finish(mut self) -> Section {
+        self.close();
+        self.section
+    }
+}
+
+/// Construct a list of `kind` with a single empty item.
+fn new_list(kind:ListKind) -> List {
+    let items = vec![default()];
+    List{kind,items}
+}
+
+/// Append the `segments` of a line to the text of a paragraph or list item.
+fn push_text_line(text:&mut Vec<Segment>, segments:Vec<Segment>) {
+    text.push(Segment::text(" "));
+    text.extend(segments);
+}
+
+/// Get the list nested at `depth` within the last list of the section's `elements`, following the
+/// last item on each level.
+fn list_at(elements:&mut [Element], depth:usize) -> Option<&mut List> {
+    let mut list = match elements.last_mut() {
+        Some(Element::List(list)) => list,
+        _                         => return None,
+    };
+    for _ in 0..depth {
+        list = list.items.last_mut()?.lists.last_mut()?;
+    }
+    Some(list)
+}
+
+/// Set the content of the last item in the list at `depth` from its `segments`.
+fn set_item_content(elements:&mut [Element], depth:usize, segments:Vec<Segment>) {
+    if let Some(item) = list_at(elements,depth).and_then(|list| list.items.last_mut()) {
+        item.content = inline(segments);
+    }
+}
+
+
+
+// ============
+// === Open ===
+// ============
+
+/// The element of a section that the next line of the section can continue.
+#[derive(Clone,Debug)]
+enum Open {
+    /// There is no such element.
+    Nothing,
+    /// A paragraph, with the segments of its lines so far.
+    Paragraph(Vec<Segment>),
+    /// A list, with the indentation of each level of nesting down to its last item, and the
+    /// segments of the last item so far.
+    List{indents:Vec<usize>, segments:Vec<Segment>},
+    /// A code block, with the indentation of its first line.
+    Code{indent:usize},
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text:&str) -> Segment {
+        Segment::text(text)
+    }
+
+    fn bold() -> Segment {
+        Segment::Formatter(Style::Bold)
+    }
+
+    fn italic() -> Segment {
+        Segment::Formatter(Style::Italic)
+    }
+
+    #[test]
+    fn inline_formatting() {
+        let segments = vec![text("a "),bold(),text("b"),bold(),text(" c")];
+        let expected = vec![
+            Inline::text("a "),
+            Inline::formatted(Style::Bold,vec![Inline::text("b")]),
+            Inline::text(" c"),
+        ];
+        assert_eq!(inline(segments),expected);
+    }
+
+    #[test]
+    fn inline_interleaved_formatting() {
+        let segments = vec![bold(),text("a "),italic(),text("b"),bold(),text(" c"),italic()];
+        let expected = vec![
+            Inline::formatted(Style::Bold,vec![
+                Inline::text("a "),
+                Inline::formatted(Style::Italic,vec![Inline::text("b")]),
+            ]),
+            Inline::formatted(Style::Italic,vec![Inline::text(" c")]),
+        ];
+        assert_eq!(inline(segments),expected);
+    }
+
+    #[test]
+    fn inline_interleaved_formatting_without_text() {
+        let segments = vec![bold(),italic(),text("a"),bold(),italic()];
+        let expected = vec![
+            Inline::formatted(Style::Bold,vec![
+                Inline::formatted(Style::Italic,vec![Inline::text("a")]),
+            ]),
+        ];
+        assert_eq!(inline(segments),expected);
+    }
+
+    #[test]
+    fn inline_unclosed_formatting() {
+        let segments = vec![text("a "),italic(),text("b "),Segment::code("c")];
+        let expected = vec![
+            Inline::text("a "),
+            Inline::unclosed(Style::Italic,vec![Inline::text("b "),Inline::code("c")]),
+        ];
+        assert_eq!(inline(segments),expected);
+    }
+}
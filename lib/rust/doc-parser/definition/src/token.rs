@@ -0,0 +1,243 @@
+//! This file defines the tokens produced by the lexer for documentation comments.
+//!
+//! Each token describes a whole line of the comment, as the structure of the documentation is
+//! given by the markers and indentation of its lines. The segments of a line are lossless, so that
+//! the parser can still take a line as code once it knows the line belongs to a code block.
+
+use crate::prelude::*;
+
+use crate::ast::ListKind;
+use crate::ast::SectionKind;
+use crate::ast::Style;
+
+use flexer::sink::Checkpoint;
+
+
+
+// =============
+// === Token ===
+// =============
+
+/// A line of a documentation comment.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Token {
+    /// A line holding a tag, with the description that follows it.
+    Tag{name:String, description:Option<String>},
+    /// A line of text, which starts with `marker` after `indent` spaces.
+    Line{indent:usize, marker:Option<Marker>, segments:Vec<Segment>},
+    /// A line that contains only whitespace.
+    BlankLine,
+}
+
+impl Token {
+    /// Construct a token for a tag line.
+    pub fn tag(name:impl Str, description:Option<&str>) -> Token {
+        let description = description.map(Into::into);
+        Token::Tag{name:name.into(),description}
+    }
+
+    /// Construct a token for a line of text.
+    pub fn line(indent:usize, marker:Option<Marker>, segments:Vec<Segment>) -> Token {
+        Token::Line{indent,marker,segments}
+    }
+}
+
+
+
+// ==============
+// === Marker ===
+// ==============
+
+/// A marker at the start of a line, which is followed by a space.
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub enum Marker {
+    /// A marker of a list item.
+    List(ListKind),
+    /// A marker of a section.
+    Section(SectionKind),
+}
+
+impl Marker {
+    /// Get the marker written as `marker`, if any.
+    pub fn from_char(marker:char) -> Option<Marker> {
+        let list    = ListKind::from_marker(marker).map(Marker::List);
+        let section = || SectionKind::from_marker(marker).map(Marker::Section);
+        list.or_else(section)
+    }
+
+    /// The source representation of the marker, including the space that follows it.
+    pub fn repr(self) -> String {
+        let marker = match self {
+            Marker::List(kind)    => kind.marker(),
+            Marker::Section(kind) => kind.marker().unwrap_or_default(),
+        };
+        format!("{} ",marker)
+    }
+}
+
+
+
+// ===============
+// === Segment ===
+// ===============
+
+/// A segment of the text on a line.
+#[allow(missing_docs)]
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Segment {
+    /// Plain text.
+    Text(String),
+    /// Inline code, without its enclosing backticks.
+    Code(String),
+    /// A marker that opens or closes formatted text.
+    Formatter(Style),
+    /// A link, written as `[title](url)`.
+    Link{title:String, url:String},
+    /// An image, written as `![name](url)`.
+    Image{name:String, url:String},
+}
+
+impl Segment {
+    /// Constructor.
+    pub fn text(text:impl Str) -> Segment {
+        Segment::Text(text.into())
+    }
+
+    /// Constructor.
+    pub fn code(code:impl Str) -> Segment {
+        Segment::Code(code.into())
+    }
+
+    /// Constructor.
+    pub fn link(title:impl Str, url:impl Str) -> Segment {
+        Segment::Link{title:title.into(),url:url.into()}
+    }
+
+    /// Constructor.
+    pub fn image(name:impl Str, url:impl Str) -> Segment {
+        Segment::Image{name:name.into(),url:url.into()}
+    }
+
+    /// The source representation of the segment.
+    pub fn repr(&self) -> String {
+        match self {
+            Segment::Text(text)       => text.clone(),
+            Segment::Code(code)       => format!("`{}`",code),
+            Segment::Formatter(style) => style.marker().to_string(),
+            Segment::Link{title,url}  => format!("[{}]({})",title,url),
+            Segment::Image{name,url}  => format!("![{}]({})",name,url),
+        }
+    }
+}
+
+
+
+// ==============
+// === Stream ===
+// ==============
+
+/// A representation of the stream of lines in a documentation comment.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Stream {
+    /// The tokens in the token stream.
+    tokens:Vec<Token>
+}
+
+impl Stream {
+    /// Append the provided `token` to the token stream.
+    pub fn append(&mut self, token:Token) {
+        self.tokens.push(token)
+    }
+
+    /// Get a reference to the tokens in the stream.
+    pub fn tokens(&self) -> &Vec<Token> {
+        &self.tokens
+    }
+}
+
+impl Deref for Stream {
+    type Target = Vec<Token>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tokens
+    }
+}
+
+impl DerefMut for Stream {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tokens
+    }
+}
+
+
+// === Trait Impls ===
+
+impl From<Vec<Token>> for Stream {
+    fn from(tokens:Vec<Token>) -> Self {
+        Stream{tokens}
+    }
+}
+
+impl From<Stream> for Vec<Token> {
+    fn from(stream:Stream) -> Self {
+        stream.tokens
+    }
+}
+
+impl TokenSink for Stream {
+    type Token  = Token;
+    type Output = Stream;
+
+    fn push(&mut self, token:Token) {
+        self.append(token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.tokens.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.tokens.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Stream {
+        mem::take(self)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_from_char() {
+        assert_eq!(Marker::from_char('-'),Some(Marker::List(ListKind::Unordered)));
+        assert_eq!(Marker::from_char('*'),Some(Marker::List(ListKind::Ordered)));
+        assert_eq!(Marker::from_char('!'),Some(Marker::Section(SectionKind::Important)));
+        assert_eq!(Marker::from_char('?'),Some(Marker::Section(SectionKind::Info)));
+        assert_eq!(Marker::from_char('>'),Some(Marker::Section(SectionKind::Example)));
+        assert_eq!(Marker::from_char('#'),None);
+    }
+
+    #[test]
+    fn marker_repr() {
+        assert_eq!(Marker::List(ListKind::Ordered).repr(),"* ");
+        assert_eq!(Marker::Section(SectionKind::Example).repr(),"> ");
+    }
+
+    #[test]
+    fn segment_repr() {
+        assert_eq!(Segment::text("foo bar").repr(),"foo bar");
+        assert_eq!(Segment::code("a + b").repr(),"`a + b`");
+        assert_eq!(Segment::Formatter(Style::Strikethrough).repr(),"~");
+        assert_eq!(Segment::link("Enso","https://enso.org").repr(),"[Enso](https://enso.org)");
+        assert_eq!(Segment::image("Logo","logo.png").repr(),"![Logo](logo.png)");
+    }
+}
@@ -0,0 +1,39 @@
+use std::fs::File;
+use doc_parser_definition::lexer::DocLexer;
+use std::io::prelude::*;
+use flexer::Definition;
+use flexer::generate::CodegenOptions;
+use flexer::generate::specialize_with_report;
+
+
+
+/// Generates the documentation lexer engine and saves the result into the file `src/engine.rs`.
+///
+/// The content of the generated file can be used with the `include!` macro. The size of the engine
+/// is written to `engine_size.txt` in the output directory of the build script.
+fn generate_engine() -> std::io::Result<()> {
+    let definition_path  = "../definition/src/lexer.rs";
+    let output_directory = "src/generated";
+    let _                = std::fs::create_dir(output_directory);
+    let output_path      = "src/generated/engine.rs";
+    let definition_error = format!("The lexer definition should exist at {}.",definition_path);
+    let output_error     = format!("Cannot open output file at {}.",output_path);
+    let mut lexer_def    = File::open(definition_path).expect(definition_error.as_str());
+    let mut contents     = String::new();
+    let mut file         = File::create(output_path).expect(output_error.as_str());
+    let lexer            = DocLexer::define();
+    let options          = CodegenOptions::default();
+    let result           = specialize_with_report(&**lexer,"DocLexer","token::Stream",&options);
+    let (engine,report)  = result.unwrap();
+    let out_directory    = std::env::var("OUT_DIR").expect("Cargo sets the output directory.");
+    let report_path      = std::path::Path::new(&out_directory).join("engine_size.txt");
+    std::fs::write(report_path,format!("{}\n",report)).expect("Unable to write the size report.");
+    lexer_def.read_to_string(&mut contents).expect("Unable to read lexer definition.");
+    file.write_all(contents.as_bytes()).expect("Unable to write lexer definition.");
+    file.write_all(engine.as_bytes()).expect("Unable to write lexer specialization.");
+    Ok(())
+}
+
+fn main() -> std::io::Result<()> {
+    generate_engine()
+}
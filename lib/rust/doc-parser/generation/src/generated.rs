@@ -0,0 +1,3 @@
+//! This module re-exports the generated documentation lexer sources.
+
+pub mod engine;
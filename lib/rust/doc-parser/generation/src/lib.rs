@@ -0,0 +1,58 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This module exports the interface to the parser for Enso documentation comments, which is
+//! shared by the IDE and the generated documentation.
+
+pub mod generated;
+
+pub use doc_parser_definition::ast;
+pub use doc_parser_definition::html;
+
+use crate::generated::engine::DocLexer;
+use crate::html::Html;
+
+use flexer::prelude::Reader;
+use flexer::prelude::reader::decoder::DecoderUTF8;
+
+/// Support libraries for the documentation lexer definition.
+///
+/// This is an intentional re-export in this crate's namespace.
+mod library {
+    pub use doc_parser_definition::library::*;
+}
+
+/// A library of commonly useful functionality.
+mod prelude {
+    pub use doc_parser_definition::prelude::*;
+}
+
+
+
+// ===========================
+// === Doc Parser Rust API ===
+// ===========================
+
+/// Parse the body of a documentation comment into its documentation.
+///
+/// The body of a comment is the text following its `##`, with the margin of the comment removed
+/// from each line after the first. Every input is valid documentation, with mistakes such as
+/// unclosed formatting being kept in the resulting tree.
+pub fn parse(input:&str) -> ast::Documentation {
+    let mut lexer = DocLexer::new();
+    let reader    = Reader::new(input.as_bytes(),DecoderUTF8());
+    let result    = lexer.run(reader);
+    doc_parser_definition::parser::parse(result.tokens)
+}
+
+/// Render the body of a documentation comment as HTML.
+pub fn to_html(input:&str) -> String {
+    parse(input).to_html()
+}
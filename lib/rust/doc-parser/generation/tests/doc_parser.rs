@@ -0,0 +1,363 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for the parser of Enso documentation comments.
+
+use doc_parser::ast::*;
+use doc_parser_definition::token::Marker;
+use doc_parser_definition::token::Segment;
+use doc_parser_definition::token::Token;
+use doc_parser_definition::token;
+use flexer::prelude::Reader;
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use doc_parser::generated::engine::DocLexer;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Assert that `input` lexes to the `expected` lines.
+fn assert_lexes(input:impl AsRef<str>, expected:Vec<Token>) {
+    let mut lexer = DocLexer::new();
+    let reader    = Reader::new(input.as_ref().as_bytes(),DecoderUTF8());
+    let result    = lexer.run(reader);
+    assert_eq!(result.kind,flexer::ResultKind::Success);
+    assert_eq!(result.tokens,token::Stream::from(expected));
+}
+
+/// Assert that `input` parses to documentation with the `expected` sections and no tags.
+fn assert_parses(input:&str, expected:Vec<Section>) {
+    let expected = Documentation{tags:vec![],sections:expected};
+    assert_eq!(doc_parser::parse(input),expected);
+}
+
+/// A section of the provided `kind` and `title`, containing `elements`.
+fn section(kind:SectionKind, title:Option<&str>, elements:Vec<Element>) -> Section {
+    let title = title.map(|title| vec![Inline::text(title)]);
+    Section{kind,title,elements}
+}
+
+/// A paragraph holding just `text`.
+fn paragraph(text:&str) -> Element {
+    Element::Paragraph(vec![Inline::text(text)])
+}
+
+/// A list of `kind` whose items hold just text.
+fn list(kind:ListKind, items:Vec<(&str,Vec<List>)>) -> List {
+    let items = items.into_iter().map(|(text,lists)| {
+        ListItem{content:vec![Inline::text(text)],lists}
+    }).collect();
+    List{kind,items}
+}
+
+
+
+// =============
+// === Lexer ===
+// =============
+
+#[test]
+fn lex_tags() {
+    let input    = " DEPRECATED Use `foo` instead\nADDED\nNot a tag";
+    let expected = vec![
+        Token::tag("DEPRECATED",Some("Use `foo` instead")),
+        Token::tag("ADDED",None),
+        Token::line(0,None,vec![Segment::text("Not a tag")]),
+    ];
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn lex_first_line_indent() {
+    let input    = "  Synopsis.\n  Code.";
+    let expected = vec![
+        Token::line(0,None,vec![Segment::text("Synopsis.")]),
+        Token::line(2,None,vec![Segment::text("Code.")]),
+    ];
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn lex_markers() {
+    let input    = "Text\n- a\n  * b\n! Important\n*bold*";
+    let expected = vec![
+        Token::line(0,None,vec![Segment::text("Text")]),
+        Token::line(0,Some(Marker::List(ListKind::Unordered)),vec![Segment::text("a")]),
+        Token::line(2,Some(Marker::List(ListKind::Ordered)),vec![Segment::text("b")]),
+        Token::line(0,Some(Marker::Section(SectionKind::Important)),vec![
+            Segment::text("Important"),
+        ]),
+        Token::line(0,None,vec![
+            Segment::Formatter(Style::Bold),
+            Segment::text("bold"),
+            Segment::Formatter(Style::Bold),
+        ]),
+    ];
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn lex_blank_lines() {
+    let input    = "\na\n\n   \r\nb\n";
+    let expected = vec![
+        Token::BlankLine,
+        Token::line(0,None,vec![Segment::text("a")]),
+        Token::BlankLine,
+        Token::BlankLine,
+        Token::line(0,None,vec![Segment::text("b")]),
+    ];
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn lex_inline_segments() {
+    let input    = "See `a_b` at [Enso](https://enso.org), ![Logo](logo.png)! [x] ~";
+    let expected = vec![Token::line(0,None,vec![
+        Segment::text("See "),
+        Segment::code("a_b"),
+        Segment::text(" at "),
+        Segment::link("Enso","https://enso.org"),
+        Segment::text(", "),
+        Segment::image("Logo","logo.png"),
+        Segment::text("! [x] "),
+        Segment::Formatter(Style::Strikethrough),
+    ])];
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn lex_unclosed_code() {
+    let input    = "a `b\nc";
+    let expected = vec![
+        Token::line(0,None,vec![Segment::text("a `b")]),
+        Token::line(0,None,vec![Segment::text("c")]),
+    ];
+    assert_lexes(input,expected);
+}
+
+
+
+// ============
+// === Tags ===
+// ============
+
+#[test]
+fn parse_tags() {
+    let input    = "DEPRECATED Use `foo` instead\nUNSTABLE\nSynopsis.";
+    let expected = Documentation{
+        tags : vec![
+            Tag{kind:TagKind::Deprecated,description:Some("Use `foo` instead".into())},
+            Tag{kind:TagKind::Unrecognized("UNSTABLE".into()),description:None},
+        ],
+        sections : vec![section(SectionKind::Raw,None,vec![paragraph("Synopsis.")])],
+    };
+    assert_eq!(doc_parser::parse(input),expected);
+}
+
+
+
+// ================
+// === Sections ===
+// ================
+
+#[test]
+fn parse_synopsis() {
+    let input    = " Adds two numbers\n together.";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Adds two numbers together."),
+    ])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_body() {
+    let input    = "Synopsis.\n\nBody.\n\n\nTitle\nTitled body.";
+    let expected = [
+        section(SectionKind::Raw,None,vec![paragraph("Synopsis.")]),
+        section(SectionKind::Raw,None,vec![paragraph("Body.")]),
+        section(SectionKind::Raw,Some("Title"),vec![paragraph("Titled body.")]),
+    ];
+    let documentation = doc_parser::parse(input);
+    assert_eq!(documentation.synopsis(),Some(&expected[0]));
+    assert_eq!(documentation.body(),&expected[1..]);
+}
+
+#[test]
+fn parse_marked_sections() {
+    let input    = concat!(
+        "Synopsis.\n\n",
+        "! Important\n  Read this.\n\n",
+        "? Info\n\n",
+        "> Example\n  Add.\n\n      1 + 2",
+    );
+    let expected = vec![
+        section(SectionKind::Raw,None,vec![paragraph("Synopsis.")]),
+        section(SectionKind::Important,Some("Important"),vec![paragraph("Read this.")]),
+        section(SectionKind::Info,Some("Info"),vec![]),
+        section(SectionKind::Example,Some("Example"),vec![
+            paragraph("Add."),
+            Element::Code(vec!["1 + 2".into()]),
+        ]),
+    ];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_marker_in_paragraph() {
+    let input    = "Synopsis\n> not a section";
+    let expected = vec![section(SectionKind::Raw,None,vec![paragraph("Synopsis > not a section")])];
+    assert_parses(input,expected);
+}
+
+
+
+// =============
+// === Lists ===
+// =============
+
+#[test]
+fn parse_list() {
+    let input    = "Synopsis:\n- a\n- b\n  continued";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Synopsis:"),
+        Element::List(list(ListKind::Unordered,vec![("a",vec![]),("b continued",vec![])])),
+    ])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_nested_lists() {
+    let input    = "Synopsis:\n  - a\n    * b\n    * c\n      - d\n  - e\n    - f\n    * g";
+    let d        = list(ListKind::Unordered,vec![("d",vec![])]);
+    let bc       = list(ListKind::Ordered,vec![("b",vec![]),("c",vec![d])]);
+    let f        = list(ListKind::Unordered,vec![("f",vec![])]);
+    let g        = list(ListKind::Ordered,vec![("g",vec![])]);
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Synopsis:"),
+        Element::List(list(ListKind::Unordered,vec![("a",vec![bc]),("e",vec![f,g])])),
+    ])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_list_kind_change() {
+    let input    = "- a\n* b";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        Element::List(list(ListKind::Unordered,vec![("a",vec![])])),
+        Element::List(list(ListKind::Ordered,vec![("b",vec![])])),
+    ])];
+    assert_parses(input,expected);
+}
+
+
+
+// ============
+// === Code ===
+// ============
+
+#[test]
+fn parse_code() {
+    let input    = "Synopsis.\n    main =\n        x = `a` * 2\n\n        x\n- item";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Synopsis."),
+        Element::Code(vec![
+            "main =".into(),
+            "    x = `a` * 2".into(),
+            "".into(),
+            "    x".into(),
+        ]),
+        Element::List(list(ListKind::Unordered,vec![("item",vec![])])),
+    ])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_code_with_markers() {
+    let input    = "Synopsis.\n    - not a list\n      > nor a section";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Synopsis."),
+        Element::Code(vec!["- not a list".into(),"  > nor a section".into()]),
+    ])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_code_dedent() {
+    let input    = "Synopsis.\n        x\n\n      y\n    z";
+    let expected = vec![section(SectionKind::Raw,None,vec![
+        paragraph("Synopsis."),
+        Element::Code(vec!["    x".into(),"".into(),"  y".into(),"z".into()]),
+    ])];
+    assert_parses(input,expected);
+}
+
+
+
+// ==================
+// === Formatting ===
+// ==================
+
+#[test]
+fn parse_formatting() {
+    let input    = "*Bold _both* italic_ and ~struck\nacross lines~ [link](url) ![img](src)";
+    let expected = vec![section(SectionKind::Raw,None,vec![Element::Paragraph(vec![
+        Inline::formatted(Style::Bold,vec![
+            Inline::text("Bold "),
+            Inline::formatted(Style::Italic,vec![Inline::text("both")]),
+        ]),
+        Inline::formatted(Style::Italic,vec![Inline::text(" italic")]),
+        Inline::text(" and "),
+        Inline::formatted(Style::Strikethrough,vec![Inline::text("struck across lines")]),
+        Inline::text(" "),
+        Inline::link("link","url"),
+        Inline::text(" "),
+        Inline::image("img","src"),
+    ])])];
+    assert_parses(input,expected);
+}
+
+#[test]
+fn parse_unclosed_formatting() {
+    let input    = "Some *bold\n\nBody.";
+    let expected = vec![
+        section(SectionKind::Raw,None,vec![Element::Paragraph(vec![
+            Inline::text("Some "),
+            Inline::unclosed(Style::Bold,vec![Inline::text("bold")]),
+        ])]),
+        section(SectionKind::Raw,None,vec![paragraph("Body.")]),
+    ];
+    assert_parses(input,expected);
+}
+
+
+
+// ============
+// === HTML ===
+// ============
+
+#[test]
+fn render_html() {
+    let input    = "ADDED 2.0\nAdds _two_ numbers.\n\n> Example\n  Add.\n\n      1 + 2 < 4";
+    let expected = concat!(
+        r#"<div class="doc">"#,
+        r#"<div class="tags"><div class="tag added"><span class="name">ADDED</span>"#,
+        r#"<span class="description">2.0</span></div></div>"#,
+        r#"<div class="synopsis"><div class="section raw">"#,
+        "<p>Adds <i>two</i> numbers.</p>",
+        "</div></div>",
+        r#"<div class="body"><div class="section example"><h2 class="title">Example</h2>"#,
+        "<p>Add.</p><pre><code>1 + 2 &lt; 4</code></pre>",
+        "</div></div>",
+        "</div>",
+    );
+    assert_eq!(doc_parser::to_html(input),expected);
+}
@@ -30,9 +30,11 @@ use flexer::prelude::*;
 use flexer::*;
 use flexer;
 use flexer::automata::pattern::Pattern;
+use flexer::diagnostic::Severity;
 use flexer::group::Registry;
 use flexer::prelude::logger::Disabled;
 use flexer::prelude::reader::BookmarkManager;
+use flexer::sink::Checkpoint;
 
 
 
@@ -91,6 +93,27 @@ impl From<Vec<Token>> for TokenStream {
     }
 }
 
+impl TokenSink for TokenStream {
+    type Token  = Token;
+    type Output = TokenStream;
+
+    fn push(&mut self, token:Token) {
+        self.tokens.push(token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.tokens.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.tokens.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> TokenStream {
+        mem::take(self)
+    }
+}
+
 
 
 // ==================
@@ -139,6 +162,8 @@ impl TestLexer {
     fn on_err_suffix_first_word<R:LazyReader>(&mut self, _reader:&mut R) {
         let ast = Token::Unrecognized(self.current_match.clone());
         self.output.push(ast);
+        let message = format!("Unrecognized input {:?}.",self.current_match);
+        self.report(Severity::Warning,message);
     }
 
     fn on_no_err_suffix_first_word<R:LazyReader>(&mut self, _reader:&mut R) {}
@@ -178,12 +203,21 @@ impl TestLexer {
         self.pop_state();
     }
 
+    fn on_separator<R:LazyReader>(&mut self, reader:&mut R) {
+        // The separator is lexed again in the root state, however often it is rewound here.
+        self.rewind_current_match(reader);
+        self.rewind_current_match(reader);
+        self.less(1,reader);
+        self.pop_state();
+    }
+
     fn rules_in_seen_first_word(lexer:&mut TestLexer) {
         let a_word        = Pattern::char('a').many1();
         let b_word        = Pattern::char('b').many1();
         let space         = Pattern::char(' ');
         let spaced_a_word = &space >> &a_word;
         let spaced_b_word = &space >> &b_word;
+        let separator     = Pattern::all_of(", ");
         let any           = Pattern::any();
         let end           = Pattern::eof();
 
@@ -192,6 +226,7 @@ impl TestLexer {
 
         seen_first_word_group.create_rule(&spaced_a_word,"self.on_spaced_word(reader)");
         seen_first_word_group.create_rule(&spaced_b_word,"self.on_spaced_word(reader)");
+        seen_first_word_group.create_rule(&separator,    "self.on_separator(reader)");
         seen_first_word_group.create_rule(&end,          "self.on_no_err_suffix(reader)");
         seen_first_word_group.create_rule(&any,          "self.on_err_suffix(reader)");
     }
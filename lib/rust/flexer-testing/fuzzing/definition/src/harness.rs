@@ -0,0 +1,150 @@
+//! The driver for differential fuzzing of generated lexers against the reference lexer.
+
+use flexer::prelude::*;
+
+use crate::random::Random;
+use crate::reference::Outcome;
+use crate::reference;
+use crate::spec::Spec;
+use crate::spec;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The number of random inputs that each lexer is checked against.
+pub const INPUTS_PER_LEXER:usize = 256;
+
+/// The value combined with the seed of a lexer to obtain the seed for its inputs.
+///
+/// This keeps the inputs independent of the random choices made while generating the spec.
+const INPUT_SEED_MASK:u64 = 0x5EED_5EED_5EED_5EED;
+
+
+
+// ====================
+// === Disagreement ===
+// ====================
+
+/// An input on which a generated lexer and the reference lexer disagree.
+#[derive(Clone,Debug)]
+pub struct Disagreement {
+    /// The specification of the lexer.
+    pub spec:Spec,
+    /// The randomly generated input on which the disagreement was found.
+    pub input:String,
+    /// The smallest input derived from `input` on which the lexers still disagree.
+    pub minimized:String,
+    /// The outcome of the reference lexer on `minimized`.
+    pub expected:Outcome,
+    /// The outcome of the generated lexer on `minimized`.
+    pub actual:Outcome,
+}
+
+
+// === Trait Impls ===
+
+impl Display for Disagreement {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f,"The generated lexer disagrees with the reference lexer.")?;
+        write!(f,"{}",self.spec)?;
+        writeln!(f,"Input:     {:?}",self.input)?;
+        writeln!(f,"Minimized: {:?}",self.minimized)?;
+        writeln!(f,"Expected:  {}",self.expected)?;
+        write!(f,"Actual:    {}",self.actual)
+    }
+}
+
+
+
+// ===============
+// === Harness ===
+// ===============
+
+/// Check the lexer generated from `spec` against the reference lexer on random inputs.
+///
+/// The `lex` function must run the generated lexer on the provided input. The first disagreement
+/// found is minimized and returned.
+pub fn check(spec:Spec, lex:impl Fn(&str) -> Outcome) -> Result<(),Disagreement> {
+    let mut random = Random::new(spec.seed ^ INPUT_SEED_MASK);
+    let disagrees  = |input:&str| reference::lex(&spec,input) != lex(input);
+    for _ in 0..INPUTS_PER_LEXER {
+        let input = spec::random_input(&mut random);
+        if disagrees(&input) {
+            let minimized = minimize(&input,disagrees);
+            let expected  = reference::lex(&spec,&minimized);
+            let actual    = lex(&minimized);
+            return Err(Disagreement{spec,input,minimized,expected,actual})
+        }
+    }
+    Ok(())
+}
+
+/// Shrink `input` for as long as `is_failing` still holds for the result.
+///
+/// It repeatedly tries to remove chunks of characters, starting with large chunks and moving on to
+/// single characters, and then tries to simplify the remaining characters to `a`.
+pub fn minimize(input:&str, is_failing:impl Fn(&str) -> bool) -> String {
+    let mut chars = input.chars().collect_vec();
+    let mut chunk = chars.len();
+    while chunk > 0 {
+        let mut start   = 0;
+        let mut removed = false;
+        while start + chunk <= chars.len() {
+            let mut candidate = chars.clone();
+            candidate.drain(start..start + chunk);
+            if is_failing(&candidate.iter().collect::<String>()) {
+                chars   = candidate;
+                removed = true;
+            } else {
+                start += 1;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    for ix in 0..chars.len() {
+        let mut candidate = chars.clone();
+        candidate[ix]     = 'a';
+        if candidate != chars && is_failing(&candidate.iter().collect::<String>()) {
+            chars = candidate;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize_to_single_character() {
+        let minimized = minimize("abcbd",|input| input.contains('d'));
+        assert_eq!(minimized,"d");
+    }
+
+    #[test]
+    fn test_minimize_simplifies_characters() {
+        let minimized = minimize("cbc",|input| input.len() == 2);
+        assert_eq!(minimized,"aa");
+    }
+
+    #[test]
+    fn test_reference_is_deterministic() {
+        for seed in 0..8 {
+            let spec  = Spec::generate(seed);
+            let again = Spec::generate(seed);
+            assert_eq!(spec.to_string(),again.to_string());
+            assert_eq!(reference::lex(&spec,"ab cd"),reference::lex(&again,"ab cd"));
+        }
+    }
+}
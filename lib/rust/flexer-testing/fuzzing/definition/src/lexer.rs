@@ -0,0 +1,267 @@
+//! This module contains the definitions of the lexers used for differential fuzzing.
+//!
+//! Every lexer is defined by a [`Spec`], usually generated from a seed, and has the same set of
+//! callbacks available to its rules.
+
+use crate::prelude::*;
+use flexer::*;
+
+use crate::library::spec::Spec;
+use crate::library::token::Token;
+use crate::library::token;
+
+use flexer::group::Registry;
+use flexer::prelude::logger::Disabled;
+use flexer::prelude::reader::BookmarkManager;
+use flexer::span::Span;
+use flexer::State as FlexerState;
+use flexer::UserBookmark;
+use flexer;
+
+
+
+// ====================
+// === Type Aliases ===
+// ====================
+
+type Logger = Disabled;
+type Flexer = flexer::Flexer<State,token::Stream,Logger>;
+
+
+
+// ==============
+// === Lexers ===
+// ==============
+
+/// Define a lexer for each of the provided names, using the associated specification.
+///
+/// It also defines `specialize_all`, which generates the code for all of the lexers at once.
+macro_rules! fuzzing_lexers {
+    ($($name:ident = $spec:expr),* $(,)?) => {
+        $(
+            /// A lexer for the specification that it is defined with.
+            #[derive(Debug)]
+            pub struct $name {
+                lexer    : Flexer,
+                bookmark : UserBookmark,
+            }
+
+            impl $name {
+                /// Construct a new instance of the lexer.
+                pub fn new() -> Self {
+                    let logger    = Logger::new(stringify!($name));
+                    let mut lexer = Flexer::new(logger);
+                    let bookmark  = lexer.define_bookmark("mark");
+                    $name{lexer,bookmark}
+                }
+
+                /// The specification of the lexer.
+                pub fn spec() -> Spec {
+                    $spec
+                }
+
+                /// Emit a token for the current match of `rule`, then enter `group`.
+                ///
+                /// Unlike the other callbacks, this is defined on the lexer rather than in
+                /// [`Callbacks`], so that it calls the `push_state` generated for the lexer, which
+                /// runs the enter hook of the group.
+                pub fn on_match_and_push<R:LazyReader>
+                (&mut self, reader:&mut R, rule:usize, group:usize) {
+                    self.on_match(reader,rule);
+                    self.push_state(group.into());
+                }
+
+                /// Emit a token for the current match of `rule`, then leave the current group.
+                ///
+                /// Like [`Self::on_match_and_push`], this is defined on the lexer, so that the exit
+                /// hook of the group runs.
+                pub fn on_match_and_pop<R:LazyReader>(&mut self, reader:&mut R, rule:usize) {
+                    self.on_match(reader,rule);
+                    self.pop_state();
+                }
+            }
+
+            impl Deref for $name {
+                type Target = Flexer;
+                fn deref(&self) -> &Self::Target {
+                    &self.lexer
+                }
+            }
+
+            impl DerefMut for $name {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    &mut self.lexer
+                }
+            }
+
+            impl Callbacks for $name {
+                fn user_bookmark(&self) -> UserBookmark {
+                    self.bookmark
+                }
+            }
+
+            impl Default for $name {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl flexer::Definition for $name {
+                fn define() -> Self {
+                    let mut lexer = Self::new();
+                    lexer.define_from(stringify!($name),&Self::spec());
+                    lexer
+                }
+
+                fn groups(&self) -> &Registry {
+                    self.lexer.groups()
+                }
+
+                fn set_up(&mut self) {}
+
+                fn tear_down(&mut self) {}
+            }
+        )*
+
+        /// Generate the code for all of the fuzzing lexers.
+        pub fn specialize_all() -> Result<String,GenError> {
+            let mut code = String::new();
+            $(code.push_str(&<$name as flexer::Definition>::define().specialize()?);)*
+            Ok(code)
+        }
+    };
+}
+
+fuzzing_lexers! {
+    FuzzLexer0         = Spec::generate(0),
+    FuzzLexer1         = Spec::generate(1),
+    FuzzLexer2         = Spec::generate(2),
+    FuzzLexer3         = Spec::generate(3),
+    FuzzLexer4         = Spec::generate(4),
+    FuzzLexer5         = Spec::generate(5),
+    FuzzLexer6         = Spec::generate(6),
+    FuzzLexer7         = Spec::generate(7),
+    FuzzLexer8         = Spec::generate(8),
+    FuzzLexer9         = Spec::generate(9),
+    FuzzLexer10        = Spec::generate(10),
+    FuzzLexer11        = Spec::generate(11),
+    FuzzLexer12        = Spec::generate(12),
+    FuzzLexer13        = Spec::generate(13),
+    FuzzLexer14        = Spec::generate(14),
+    FuzzLexer15        = Spec::generate(15),
+    GuardFallbackLexer = Spec::guard_fallback(),
+}
+
+
+
+// =================
+// === Callbacks ===
+// =================
+
+/// The callbacks available to the rules of every fuzzing lexer.
+pub trait Callbacks : DerefMut<Target=Flexer> {
+    /// The bookmark used by the rules that mark and rewind to it.
+    fn user_bookmark(&self) -> UserBookmark;
+
+    /// Emit a token for the current match of `rule`.
+    fn on_match<R:LazyReader>(&mut self, _reader:&mut R, rule:usize) {
+        let text = mem::take(&mut self.current_match);
+        let span = self.current_span;
+        self.output.push(Token::new(rule,text,span));
+    }
+
+    /// Keep only the first `count` characters of the current match, then emit a token for it.
+    fn on_match_and_less<R:LazyReader>(&mut self, reader:&mut R, rule:usize, count:usize) {
+        self.less(count,reader);
+        self.on_match(reader,rule);
+    }
+
+    /// Emit a token for the current match of `rule`, then rewind to the bookmark if it is marked,
+    /// or mark the end of the match with it otherwise.
+    fn on_match_and_mark<R:LazyReader>(&mut self, reader:&mut R, rule:usize) {
+        self.on_match(reader,rule);
+        let bookmark = self.user_bookmark();
+        if !self.rewind_to(bookmark,reader) {
+            self.mark(bookmark,reader);
+        }
+    }
+
+    /// Check whether the number of tokens emitted so far, plus the depth of the group stack, leaves
+    /// `residue` as the remainder of division by `modulus`.
+    fn guard(&self, modulus:usize, residue:usize) -> bool {
+        (self.output.len() + self.state_stack.len()) % modulus == residue
+    }
+
+    /// Emit an empty token for the execution of `hook`, located at the end of the last match.
+    fn on_hook(&mut self, hook:usize) {
+        let end = self.current_span.end;
+        self.output.push(Token::new(hook,"",Span::new(end,end)));
+    }
+
+    /// Emit an empty token for the execution of the end of input `hook`, like
+    /// [`Callbacks::on_hook`].
+    fn on_eof_hook<R:LazyReader>(&mut self, _reader:&mut R, hook:usize) {
+        self.on_hook(hook);
+    }
+}
+
+
+
+// ===================
+// === Lexer State ===
+// ===================
+
+/// The stateful components of a fuzzing lexer.
+#[derive(Debug)]
+pub struct State {
+    /// The name of the lexer type that this state belongs to.
+    name:String,
+    /// The registry for groups in the lexer.
+    lexer_states:group::Registry,
+    /// The bookmarks for this lexer.
+    bookmarks:BookmarkManager,
+}
+
+impl State {
+    /// Define the groups of the lexer named `name` from `spec`.
+    pub fn define_from(&mut self, name:impl Into<String>, spec:&Spec) {
+        self.name         = name.into();
+        self.lexer_states = spec.registry();
+    }
+}
+
+
+// === Trait Impls ===
+
+impl flexer::State for State {
+    fn new(_logger:&impl AnyLogger) -> Self {
+        let name         = default();
+        let lexer_states = default();
+        let bookmarks    = BookmarkManager::new();
+        State{name,lexer_states,bookmarks}
+    }
+
+    fn initial_state(&self) -> group::Identifier {
+        group::Identifier::from(0)
+    }
+
+    fn groups(&self) -> &group::Registry {
+        &self.lexer_states
+    }
+
+    fn groups_mut(&mut self) -> &mut group::Registry {
+        &mut self.lexer_states
+    }
+
+    fn bookmarks(&self) -> &BookmarkManager {
+        &self.bookmarks
+    }
+
+    fn bookmarks_mut(&mut self) -> &mut BookmarkManager {
+        &mut self.bookmarks
+    }
+
+    fn specialize(&self) -> Result<String,GenError> {
+        generate::specialize(self,self.name.as_str(),"token::Stream")
+    }
+}
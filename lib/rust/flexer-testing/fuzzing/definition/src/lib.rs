@@ -0,0 +1,38 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This library defines the lexers used for differential fuzzing of the flexer code generator.
+//!
+//! Each lexer is defined by a [`spec::Spec`] that is generated at random from a seed. The code
+//! generated for these lexers by the flexer is compared against [`reference::lex`], a simple
+//! longest-match interpreter that runs directly on the NFAs for the same group registry. Any
+//! inputs on which the two disagree are minimized and reported by [`harness::check`].
+
+pub mod harness;
+pub mod lexer;
+pub mod random;
+pub mod reference;
+pub mod spec;
+pub mod token;
+
+/// A module that can be re-exported under the same name in the generation crate.
+///
+/// This is necessary to avoid issues with paths getting wonky when the code is generated from the
+/// lexer definitions. In this project, imports should _not_ be made from the crate root _except_
+/// through use of this `library` module.
+pub mod library {
+    pub use crate::spec;
+    pub use crate::token;
+}
+
+/// A collection of functionality for working with the lexer definitions.
+pub mod prelude {
+    pub use flexer::prelude::*;
+    pub use flexer::prelude::logger::*;
+}
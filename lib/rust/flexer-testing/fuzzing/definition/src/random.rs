@@ -0,0 +1,62 @@
+//! A small deterministic source of randomness for generating lexer definitions and inputs.
+//!
+//! The fuzzer must produce exactly the same definitions in the build script that generates the
+//! lexers and in the tests that exercise them, so it relies on a seeded generator rather than on
+//! any source of system entropy.
+
+
+
+// ==============
+// === Random ===
+// ==============
+
+/// A seeded pseudo-random number generator, implementing the
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c) algorithm.
+#[derive(Clone,Copy,Debug)]
+pub struct Random {
+    state:u64
+}
+
+impl Random {
+    /// Create a new generator from the provided `seed`.
+    pub fn new(seed:u64) -> Random {
+        Random{state:seed}
+    }
+
+    /// Get the next pseudo-random value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z  = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Get a value in the range `0..bound`.
+    ///
+    /// # Panics
+    ///
+    /// If `bound` is zero.
+    pub fn below(&mut self, bound:usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Get a value in the range `min..=max`.
+    pub fn between(&mut self, min:usize, max:usize) -> usize {
+        min + self.below(max - min + 1)
+    }
+
+    /// Return `true` with a probability of `numerator / denominator`.
+    pub fn chance(&mut self, numerator:usize, denominator:usize) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    /// Choose one of the provided `items`.
+    ///
+    /// # Panics
+    ///
+    /// If `items` is empty.
+    pub fn choose<T:Copy>(&mut self, items:&[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
@@ -0,0 +1,199 @@
+//! A reference lexer that interprets a [`Spec`] directly, without generating any code.
+//!
+//! It simulates the NFA for the active group on the input, picking the longest match at every
+//! point, and breaking ties in favour of the rule that was defined first. This is deliberately
+//! simple, so that it can serve as the source of truth for the behaviour of generated lexers.
+
+use flexer::prelude::*;
+
+use crate::spec::Action;
+use crate::spec::Spec;
+use crate::token::Token;
+use crate::token;
+
+use flexer::LexingResult;
+use flexer::ResultKind;
+use flexer::automata::nfa::NFA;
+use flexer::automata::symbol::Symbol;
+use flexer::group;
+use flexer::span::Span;
+use std::collections::BTreeSet;
+
+
+
+// ===============
+// === Outcome ===
+// ===============
+
+/// The outcome of running a lexer on some input.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Outcome {
+    /// Whether the lexer consumed the entire input.
+    pub finished:bool,
+    /// The tokens that the lexer produced.
+    pub tokens:token::Stream,
+}
+
+
+// === Trait Impls ===
+
+impl From<LexingResult<token::Stream>> for Outcome {
+    fn from(result:LexingResult<token::Stream>) -> Self {
+        let finished = matches!(result.kind,ResultKind::Success);
+        let tokens   = result.tokens;
+        Outcome{finished,tokens}
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.finished {"finished"} else {"failed"};
+        write!(f,"{} with {}",status,self.tokens)
+    }
+}
+
+
+
+// =================
+// === Reference ===
+// =================
+
+/// Lex `input` according to `spec`.
+///
+/// The input is followed by a single [`Symbol::EOF_CODE`], and lexing finishes successfully once
+/// a rule has matched it. Lexing fails as soon as no rule in the active group matches.
+///
+/// The hooks of a group run right after the rule that entered or left it. Once lexing has
+/// finished, the groups left on the stack are left from the innermost outwards, with the end of
+/// input hook of each group running before its exit hook. The initial group is never left, so only
+/// its end of input hook runs, last.
+///
+/// The lexer has a single bookmark, and rewinding to it discards the tokens emitted since it was
+/// marked, but leaves the stack of groups as it is. Once a rule has matched the end of the input,
+/// lexing finishes unless the rule rewinds to before the last character, as the reader has nothing
+/// left to read either way.
+///
+/// If the guards reject every rule that matches the longest prefix, the rules for the shorter
+/// prefixes are tried instead, from the longest to the shortest, and the first rule whose guard
+/// holds is executed.
+pub fn lex(spec:&Spec, input:&str) -> Outcome {
+    let registry    = spec.registry();
+    let group_count = spec.groups.len();
+    let nfas        = (0..group_count).map(|ix| registry.to_nfa_from(ix.into())).collect_vec();
+    let chars       = input.chars().collect_vec();
+    let bytes       = chars.iter().scan(0,|end,char| {*end += char.len_utf8(); Some(*end)});
+    let bytes       = iter::once(0).chain(bytes).collect_vec();
+    let symbols     = chars.iter().copied().map(Symbol::from);
+    let symbols     = symbols.chain(iter::once(Symbol::EOF_CODE)).collect_vec();
+    let mut stack   = vec![0];
+    let mut tokens  = token::Stream::default();
+    let mut offset  = 0;
+    let mut mark    = None;
+    loop {
+        let group = *stack.last().unwrap();
+        let rules = registry.rules_for(group::Identifier::from(group));
+        let names = (0..rules.len()).map(|ix| registry.group(group.into()).callback_name(ix));
+        let names = names.collect_vec();
+        let spec_rules = rules.iter().map(|rule| spec.rule_for_callback(&rule.callback));
+        let spec_rules = spec_rules.collect::<Option<Vec<_>>>();
+        let spec_rules = spec_rules.expect("Every rule in the registry comes from the spec.");
+        let matches  = prefix_matches(&nfas[group],&names,&symbols[offset..]);
+        let selected = matches.iter().rev().find_map(|(length,rule_ixs)| {
+            let may_match = |ix:&&usize| spec_rules[**ix].may_match(tokens.len(),stack.len());
+            rule_ixs.iter().find(may_match).map(|&ix| (spec_rules[ix],*length))
+        });
+        match selected {
+            None => return Outcome{finished:false,tokens},
+            Some((rule,length)) => {
+                let text_length = (offset + length).min(chars.len()) - offset;
+                let length      = match rule.action {
+                    Action::Less(count) if count < text_length => count,
+                    _                                          => length,
+                };
+                let end    = (offset + length).min(chars.len());
+                let text   = chars[offset..end].iter().collect::<String>();
+                let span   = Span::new(bytes[offset],bytes[end]);
+                tokens.push(Token::new(rule.id,text,span));
+                let at_end = Span::new(span.end,span.end);
+                let at_eof = offset + length == symbols.len();
+                offset    += length;
+                let hook = match rule.action {
+                    Action::Emit | Action::Less(_) => None,
+                    Action::Push(group) => {
+                        stack.push(group);
+                        spec.groups[group].on_enter
+                    },
+                    Action::Pop => match stack.len() {
+                        1 => None,
+                        _ => spec.groups[stack.pop().unwrap()].on_exit,
+                    },
+                    Action::Mark => {
+                        if let Some((position,checkpoint)) = mark.take() {
+                            offset = position;
+                            tokens.rollback(checkpoint);
+                        } else {
+                            mark = Some((offset,tokens.checkpoint()));
+                        }
+                        None
+                    },
+                };
+                if let Some(hook) = hook {
+                    tokens.push(Token::new(hook,"",at_end));
+                }
+                if at_eof && offset >= chars.len() {
+                    while let Some(&group) = stack.last() {
+                        let left = if stack.len() > 1 {stack.pop()} else {None};
+                        let exit = left.and_then(|group| spec.groups[group].on_exit);
+                        for hook in spec.groups[group].on_eof.into_iter().chain(exit) {
+                            tokens.push(Token::new(hook,"",at_end));
+                        }
+                        if left.is_none() {
+                            break
+                        }
+                    }
+                    return Outcome{finished:true,tokens}
+                }
+            }
+        }
+    }
+}
+
+/// Find the prefixes of `input` matched by `nfa`, from the shortest to the longest, along with the
+/// indices of the rules that match each of them in order of priority.
+///
+/// The accepting states of `nfa` are named according to `names`.
+fn prefix_matches(nfa:&NFA, names:&[String], input:&[Symbol]) -> Vec<(usize,Vec<usize>)> {
+    let accepted = |states:&BTreeSet<usize>| {
+        let state_names = states.iter().filter_map(|&ix| nfa.states[ix].name.as_ref());
+        let rule_ixs    = state_names.filter_map(|name| names.iter().position(|n| n == name));
+        rule_ixs.sorted().collect_vec()
+    };
+    let mut states  = epsilon_closure(nfa,iter::once(0).collect());
+    let mut matches = Vec::new();
+    for (length,symbol) in input.iter().enumerate() {
+        let targets = states.iter().flat_map(|&ix| nfa.states[ix].links.iter());
+        let targets = targets.filter(|link| link.symbols.contains(symbol));
+        states      = epsilon_closure(nfa,targets.map(|link| link.target_state.id).collect());
+        if states.is_empty() {
+            break
+        }
+        let rule_ixs = accepted(&states);
+        if !rule_ixs.is_empty() {
+            matches.push((length + 1,rule_ixs));
+        }
+    }
+    matches
+}
+
+/// Extend `states` with all states reachable from them through epsilon links in `nfa`.
+fn epsilon_closure(nfa:&NFA, mut states:BTreeSet<usize>) -> BTreeSet<usize> {
+    let mut pending = states.iter().copied().collect_vec();
+    while let Some(state) = pending.pop() {
+        for target in &nfa.states[state].epsilon_links {
+            if states.insert(target.id) {
+                pending.push(target.id);
+            }
+        }
+    }
+    states
+}
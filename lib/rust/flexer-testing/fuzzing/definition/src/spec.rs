@@ -0,0 +1,473 @@
+//! Randomly generated specifications for lexers.
+//!
+//! A specification describes a set of groups, each with a set of rules, in a form that can both be
+//! installed into a [`Registry`] for code generation, and be interpreted directly by the reference
+//! lexer.
+
+use flexer::prelude::*;
+
+use crate::random::Random;
+
+use flexer::automata::pattern::Pattern;
+use flexer::automata::symbol::Symbol;
+use flexer::group::Registry;
+use flexer::group;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The characters that appear in generated patterns.
+const PATTERN_CHARS:&[char] = &['a','b','c',' '];
+
+/// The characters that appear in generated inputs.
+///
+/// This includes characters that can only be matched by wildcard patterns, one of which takes up
+/// several bytes in UTF-8.
+const INPUT_CHARS:&[char] = &['a','b','c',' ','d','é'];
+
+/// The maximum nesting depth of generated patterns.
+const MAX_PATTERN_DEPTH:usize = 3;
+
+/// The maximum length of generated inputs.
+const MAX_INPUT_LENGTH:usize = 12;
+
+/// The seed for the inputs of [`Spec::guard_fallback`].
+const GUARD_FALLBACK_SEED:u64 = 0xFA11_BAC4;
+
+
+
+// ============
+// === Spec ===
+// ============
+
+/// The specification of a lexer.
+///
+/// The first group is the initial state of the lexer.
+#[derive(Clone,Debug)]
+pub struct Spec {
+    /// The seed from which the specification was generated, which also seeds the random inputs
+    /// that the lexer is checked against.
+    pub seed:u64,
+    /// The groups of the lexer, in the order in which they are defined.
+    pub groups:Vec<GroupSpec>,
+}
+
+impl Spec {
+    /// Generate a random specification from the provided `seed`.
+    ///
+    /// The same seed always results in the same specification.
+    pub fn generate(seed:u64) -> Spec {
+        let mut random   = Random::new(seed);
+        let group_count  = random.between(1,3);
+        let mut next_id  = 0;
+        let mut groups   = Vec::with_capacity(group_count);
+        for index in 0..group_count {
+            let has_parent = index > 0 && random.chance(1,3);
+            let parent     = if has_parent {Some(random.below(index))} else {None};
+            let mut rules  = Vec::new();
+            for _ in 0..random.between(1,4) {
+                let pattern = Self::non_empty_pattern(&mut random);
+                let action  = Action::generate(&mut random,index,group_count);
+                let guarded = random.chance(1,3);
+                let guard   = if guarded {Some(Guard::generate(&mut random))} else {None};
+                rules.push(RuleSpec::new(next_id,pattern,action).with_guard(guard));
+                next_id += 1;
+            }
+            if random.chance(3,4) {
+                let action = Action::generate(&mut random,index,group_count);
+                rules.push(RuleSpec::new(next_id,Pattern::eof(),action));
+                next_id += 1;
+            }
+            if random.chance(1,2) {
+                let action = Action::generate(&mut random,index,group_count);
+                rules.push(RuleSpec::new(next_id,wildcard(),action));
+                next_id += 1;
+            }
+            let mut hook = || if random.chance(1,3) {
+                next_id += 1;
+                Some(next_id - 1)
+            } else {
+                None
+            };
+            let on_enter = hook();
+            let on_exit  = hook();
+            let on_eof   = hook();
+            let name     = if index == 0 {"ROOT".into()} else {format!("GROUP_{}",index)};
+            groups.push(GroupSpec{name,parent,rules,on_enter,on_exit,on_eof});
+        }
+        Spec{seed,groups}
+    }
+
+    /// The specification of a lexer whose guards reject the two longest matches of `abc`, leaving
+    /// the rule for `a` to match.
+    ///
+    /// The lexer has to fall back through both of the rejected matches to reach the one for `a`.
+    pub fn guard_fallback() -> Spec {
+        let seed   = GUARD_FALLBACK_SEED;
+        let never  = Some(Guard{modulus:1,residue:1});
+        let a      = Pattern::char('a');
+        let ab     = &a >> Pattern::char('b');
+        let abc    = &ab >> Pattern::char('c');
+        let rules  = vec![
+            RuleSpec::new(0,a,Action::Emit),
+            RuleSpec::new(1,ab,Action::Emit).with_guard(never),
+            RuleSpec::new(2,abc,Action::Emit).with_guard(never),
+            RuleSpec::new(3,wildcard(),Action::Emit),
+            RuleSpec::new(4,Pattern::eof(),Action::Emit),
+        ];
+        let name   = "ROOT".into();
+        let parent = None;
+        let group  = GroupSpec{name,parent,rules,on_enter:None,on_exit:None,on_eof:None};
+        Spec{seed,groups:vec![group]}
+    }
+
+    /// Build the group registry described by this specification.
+    pub fn registry(&self) -> Registry {
+        let mut registry = Registry::default();
+        for group in &self.groups {
+            let parent = group.parent.map(group::Identifier::from);
+            let id     = registry.define_group(group.name.as_str(),parent);
+            for rule in &group.rules {
+                match rule.guard {
+                    None        => registry.create_rule(id,&rule.pattern,rule.callback()),
+                    Some(guard) => {
+                        registry.create_guarded_rule(id,&rule.pattern,guard.code(),rule.callback())
+                    }
+                }
+            }
+            let group_handle = registry.group_mut(id);
+            if let Some(hook) = group.on_enter {
+                group_handle.set_on_enter(&hook_callback(hook));
+            }
+            if let Some(hook) = group.on_exit {
+                group_handle.set_on_exit(&hook_callback(hook));
+            }
+            if let Some(hook) = group.on_eof {
+                group_handle.set_on_eof(&eof_hook_callback(hook));
+            }
+        }
+        registry
+    }
+
+    /// Find the rule whose callback is `callback`.
+    pub fn rule_for_callback(&self, callback:&str) -> Option<&RuleSpec> {
+        let mut rules = self.groups.iter().flat_map(|group| group.rules.iter());
+        rules.find(|rule| rule.callback() == callback)
+    }
+
+    /// Generate a random pattern that does not match the empty input.
+    ///
+    /// Rules that match the empty input never consume anything, and hence can never make progress.
+    fn non_empty_pattern(random:&mut Random) -> Pattern {
+        loop {
+            let pattern = random_pattern(random,MAX_PATTERN_DEPTH);
+            if Pattern::always().is_subset_of(&pattern).is_err() {
+                break pattern
+            }
+        }
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Spec {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f,"Specification with seed {}:",self.seed)?;
+        for (index,group) in self.groups.iter().enumerate() {
+            write!(f,"  Group {} ({})",index,group.name)?;
+            match group.parent {
+                Some(parent) => writeln!(f,", parent {}:",parent)?,
+                None         => writeln!(f,":")?,
+            }
+            for rule in &group.rules {
+                let pattern = show_pattern(&rule.pattern);
+                write!(f,"    Rule {}: {}",rule.id,pattern)?;
+                if let Some(guard) = rule.guard {
+                    write!(f," {}",guard)?;
+                }
+                writeln!(f," => {}",rule.action)?;
+            }
+            let hooks = &[("enter",group.on_enter),("exit",group.on_exit),("eof",group.on_eof)];
+            for (kind,hook) in hooks {
+                if let Some(hook) = hook {
+                    writeln!(f,"    Hook {}: on {}",hook,kind)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+
+
+// =================
+// === GroupSpec ===
+// =================
+
+/// The specification for a single group in the lexer.
+#[derive(Clone,Debug)]
+pub struct GroupSpec {
+    /// The name of the group.
+    pub name:String,
+    /// The index of the group from which this group inherits rules.
+    pub parent:Option<usize>,
+    /// The rules defined directly in this group.
+    pub rules:Vec<RuleSpec>,
+    /// The identifier of the hook that runs when the lexer enters this group.
+    pub on_enter:Option<usize>,
+    /// The identifier of the hook that runs when the lexer leaves this group.
+    pub on_exit:Option<usize>,
+    /// The identifier of the hook that runs when the input ends while this group is on the stack.
+    pub on_eof:Option<usize>,
+}
+
+
+
+// ================
+// === RuleSpec ===
+// ================
+
+/// The specification for a single rule in the lexer.
+#[derive(Clone,Debug)]
+pub struct RuleSpec {
+    /// The identifier of the rule, unique across the whole specification.
+    pub id:usize,
+    /// The pattern matched by the rule.
+    pub pattern:Pattern,
+    /// The action taken when the rule matches.
+    pub action:Action,
+    /// The condition under which the rule may match, if any.
+    pub guard:Option<Guard>,
+}
+
+impl RuleSpec {
+    /// Construct a new rule specification.
+    pub fn new(id:usize, pattern:Pattern, action:Action) -> RuleSpec {
+        let guard = None;
+        RuleSpec{id,pattern,action,guard}
+    }
+
+    /// Set the guard of the rule.
+    pub fn with_guard(mut self, guard:Option<Guard>) -> RuleSpec {
+        self.guard = guard;
+        self
+    }
+
+    /// Check whether the rule may match when `tokens` tokens have been emitted, and the group stack
+    /// is `depth` groups deep.
+    pub fn may_match(&self, tokens:usize, depth:usize) -> bool {
+        self.guard.map_or(true,|guard| guard.holds(tokens,depth))
+    }
+
+    /// The code for the callback of this rule in the generated lexer.
+    pub fn callback(&self) -> String {
+        match self.action {
+            Action::Emit        => format!("self.on_match(reader,{})",self.id),
+            Action::Push(group) => format!("self.on_match_and_push(reader,{},{})",self.id,group),
+            Action::Pop         => format!("self.on_match_and_pop(reader,{})",self.id),
+            Action::Less(count) => format!("self.on_match_and_less(reader,{},{})",self.id,count),
+            Action::Mark        => format!("self.on_match_and_mark(reader,{})",self.id),
+        }
+    }
+}
+
+
+/// The code for the enter or exit hook identified by `id` in the generated lexer.
+///
+/// Hooks share identifiers with rules, and emit an empty token when they run.
+pub fn hook_callback(id:usize) -> String {
+    format!("self.on_hook({})",id)
+}
+
+/// The code for the end of input hook identified by `id` in the generated lexer, which is passed
+/// the reader unlike the other hooks.
+pub fn eof_hook_callback(id:usize) -> String {
+    format!("self.on_eof_hook(reader,{})",id)
+}
+
+
+// =============
+// === Guard ===
+// =============
+
+/// A condition on the state of the lexer, under which a guarded rule may match.
+///
+/// It holds when the number of tokens emitted so far, plus the depth of the group stack, leaves
+/// `residue` as the remainder of division by `modulus`.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Guard {
+    /// The divisor of the lexer state.
+    pub modulus:usize,
+    /// The remainder for which the guard holds.
+    pub residue:usize,
+}
+
+impl Guard {
+    /// Generate a random guard.
+    fn generate(random:&mut Random) -> Guard {
+        let modulus = random.between(2,3);
+        let residue = random.below(modulus);
+        Guard{modulus,residue}
+    }
+
+    /// Check whether the guard holds when `tokens` tokens have been emitted, and the group stack is
+    /// `depth` groups deep.
+    pub fn holds(self, tokens:usize, depth:usize) -> bool {
+        (tokens + depth) % self.modulus == self.residue
+    }
+
+    /// The code for the guard in the generated lexer.
+    pub fn code(self) -> String {
+        format!("self.guard({},{})",self.modulus,self.residue)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Guard {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"if (tokens + depth) % {} == {}",self.modulus,self.residue)
+    }
+}
+
+
+
+// ==============
+// === Action ===
+// ==============
+
+/// The action taken when a rule matches.
+///
+/// All actions emit a token for the match.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Action {
+    /// Only emit the token.
+    Emit,
+    /// Emit the token and then enter the group with the provided index.
+    Push(usize),
+    /// Emit the token and then leave the current group, unless it is the initial one.
+    Pop,
+    /// Give back all but the provided number of characters of the match to the input, and then
+    /// emit the token for the rest of it.
+    Less(usize),
+    /// Emit the token, and then rewind to the bookmark of the lexer if it is marked, or mark the
+    /// end of the match with it otherwise.
+    ///
+    /// Rewinding discards the tokens emitted since the bookmark was marked, including this one,
+    /// and unmarks the bookmark. As every rewind goes to a later mark than the one before, lexing
+    /// always terminates.
+    Mark,
+}
+
+impl Action {
+    /// Generate a random action for a rule in the group with index `group` out of `group_count`.
+    fn generate(random:&mut Random, group:usize, group_count:usize) -> Action {
+        if group_count > 1 && random.chance(1,5) {
+            Action::Push(random.below(group_count))
+        } else if group > 0 && random.chance(1,4) {
+            Action::Pop
+        } else {
+            match random.below(6) {
+                0 => Action::Less(random.between(1,2)),
+                1 => Action::Mark,
+                _ => Action::Emit,
+            }
+        }
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Action {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Emit        => write!(f,"emit"),
+            Action::Push(group) => write!(f,"emit, push {}",group),
+            Action::Pop         => write!(f,"emit, pop"),
+            Action::Less(count) => write!(f,"keep {}, emit",count),
+            Action::Mark        => write!(f,"emit, mark or rewind"),
+        }
+    }
+}
+
+
+
+// ================
+// === Patterns ===
+// ================
+
+/// A pattern matching any single character.
+///
+/// Unlike [`Pattern::any`], it does not match the end of the input.
+pub fn wildcard() -> Pattern {
+    Pattern::symbols(Symbol::from(0)..=Symbol::from(char::MAX))
+}
+
+/// Generate a random input for a lexer.
+pub fn random_input(random:&mut Random) -> String {
+    let length = random.between(0,MAX_INPUT_LENGTH);
+    (0..length).map(|_| random.choose(INPUT_CHARS)).collect()
+}
+
+/// Generate a random pattern that is nested at most `depth` levels deep.
+fn random_pattern(random:&mut Random, depth:usize) -> Pattern {
+    let choice    = if depth == 0 {random.below(4)} else {random.below(10)};
+    let parts     = |random:&mut Random| {
+        let count = random.between(2,3);
+        (0..count).map(|_| random_pattern(random,depth-1)).collect_vec()
+    };
+    match choice {
+        0 | 1 => Pattern::char(random.choose(PATTERN_CHARS)),
+        2     => Pattern::range('a'..=random.choose(&['b','c'])),
+        3     => if random.chance(1,2) {
+            wildcard()
+        } else {
+            Pattern::not(random.choose(PATTERN_CHARS))
+        },
+        4 | 5 => parts(random).into_iter().fold1(|l,r| l >> r).unwrap(),
+        6 | 7 => parts(random).into_iter().fold1(|l,r| l | r).unwrap(),
+        8     => random_pattern(random,depth-1).many1(),
+        _     => if random.chance(1,2) {
+            random_pattern(random,depth-1).many()
+        } else {
+            random_pattern(random,depth-1).opt()
+        }
+    }
+}
+
+/// Show `pattern` using a compact regex-like notation.
+pub fn show_pattern(pattern:&Pattern) -> String {
+    match pattern {
+        Pattern::Range(range) => show_range(*range.start(),*range.end()),
+        Pattern::Or(patterns) => format!("({})",patterns.iter().map(show_pattern).join("|")),
+        Pattern::Seq(patterns) => patterns.iter().map(show_pattern).join(""),
+        Pattern::Many(body)    => format!("({})*",show_pattern(body)),
+        Pattern::Literals(literals) => {
+            let show = |literal:&Vec<Symbol>| literal.iter().map(|s| show_range(*s,*s)).join("");
+            format!("({})",literals.iter().map(show).join("|"))
+        }
+        Pattern::Always        => "()".into(),
+    }
+}
+
+/// Show the range of symbols from `start` to `end` inclusive.
+fn show_range(start:Symbol, end:Symbol) -> String {
+    let show = |symbol:Symbol| match std::char::from_u32(symbol.value) {
+        _ if symbol == Symbol::EOF_CODE => "<EOF>".to_string(),
+        Some(char)                      => char.escape_debug().to_string(),
+        None                            => format!("<{:#X}>",symbol.value),
+    };
+    if start == end {
+        show(start)
+    } else if start == Symbol::from(0) && end.value >= char::MAX as u32 {
+        ".".into()
+    } else {
+        format!("[{}-{}]",show(start),show(end))
+    }
+}
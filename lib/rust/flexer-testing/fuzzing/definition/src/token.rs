@@ -0,0 +1,102 @@
+//! The tokens produced by the fuzzing lexers.
+
+use flexer::prelude::*;
+
+use flexer::sink::Checkpoint;
+use flexer::span::Span;
+
+
+
+// =============
+// === Token ===
+// =============
+
+/// A token, recording which rule matched which part of the input.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Token {
+    /// The identifier of the rule that produced the token, as given in the [`crate::spec::Spec`].
+    pub rule:usize,
+    /// The text that the rule matched.
+    pub text:String,
+    /// The location of the text in the input.
+    pub span:Span,
+}
+
+impl Token {
+    /// Construct a new token.
+    pub fn new(rule:usize, text:impl Into<String>, span:Span) -> Token {
+        let text = text.into();
+        Token{rule,text,span}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Token {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{}:{:?}@{}",self.rule,self.text,self.span)
+    }
+}
+
+
+
+// ==============
+// === Stream ===
+// ==============
+
+/// A stream of tokens.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct Stream {
+    tokens:Vec<Token>
+}
+
+impl Stream {
+    /// Append the provided `token` to the stream.
+    pub fn push(&mut self, token:Token) {
+        self.tokens.push(token);
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Deref for Stream {
+    type Target = Vec<Token>;
+    fn deref(&self) -> &Self::Target {
+        &self.tokens
+    }
+}
+
+impl From<Vec<Token>> for Stream {
+    fn from(tokens:Vec<Token>) -> Self {
+        Stream{tokens}
+    }
+}
+
+impl Display for Stream {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"[{}]",self.tokens.iter().join(", "))
+    }
+}
+
+impl TokenSink for Stream {
+    type Token  = Token;
+    type Output = Stream;
+
+    fn push(&mut self, token:Token) {
+        self.tokens.push(token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.tokens.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.tokens.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Stream {
+        mem::take(self)
+    }
+}
@@ -0,0 +1,3 @@
+//! This module re-exports the generated lexer sources.
+
+pub mod engine;
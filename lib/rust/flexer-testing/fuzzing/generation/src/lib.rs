@@ -0,0 +1,29 @@
+//! This library exposes the specialized versions of the fuzzing lexers.
+//!
+//! As with the other flexer-based lexers, the engines have to live in a separate crate from their
+//! definitions, as they are generated from those definitions by `build.rs` during compilation. The
+//! output is stored in a new file `engine.rs` and exported by `generated.rs`.
+
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+pub mod generated;
+
+/// Support libraries for the lexer definitions.
+///
+/// This is an intentional re-export in this crate's namespace.
+mod library {
+    pub use flexer_fuzz_definition::library::*;
+}
+
+/// A library of commonly useful functionality.
+mod prelude {
+    pub use flexer_fuzz_definition::prelude::*;
+}
@@ -0,0 +1,81 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file checks each of the generated fuzzing lexers against the reference lexer.
+
+use flexer::*;
+use flexer_fuzz_generation::generated::engine::*;
+
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer::prelude::Reader;
+use flexer_fuzz_definition::harness;
+use flexer_fuzz_definition::reference::Outcome;
+use flexer_fuzz_definition::reference;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Define a test for each of the provided lexers, checking it against the reference lexer.
+macro_rules! differential_tests {
+    ($($test:ident => $lexer:ident),* $(,)?) => {$(
+        #[test]
+        fn $test() {
+            let lex = |input:&str| {
+                let reader    = Reader::new(input.as_bytes(),DecoderUTF8());
+                let mut lexer = $lexer::define();
+                Outcome::from(lexer.run(reader))
+            };
+            if let Err(disagreement) = harness::check($lexer::spec(),lex) {
+                panic!("{}",disagreement)
+            }
+        }
+    )*};
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+differential_tests! {
+    test_lexer_0        => FuzzLexer0,
+    test_lexer_1        => FuzzLexer1,
+    test_lexer_2        => FuzzLexer2,
+    test_lexer_3        => FuzzLexer3,
+    test_lexer_4        => FuzzLexer4,
+    test_lexer_5        => FuzzLexer5,
+    test_lexer_6        => FuzzLexer6,
+    test_lexer_7        => FuzzLexer7,
+    test_lexer_8        => FuzzLexer8,
+    test_lexer_9        => FuzzLexer9,
+    test_lexer_10       => FuzzLexer10,
+    test_lexer_11       => FuzzLexer11,
+    test_lexer_12       => FuzzLexer12,
+    test_lexer_13       => FuzzLexer13,
+    test_lexer_14       => FuzzLexer14,
+    test_lexer_15       => FuzzLexer15,
+    test_guard_fallback => GuardFallbackLexer,
+}
+
+#[test]
+fn test_fallback_past_rejected_matches() {
+    let reader    = Reader::new("abc".as_bytes(),DecoderUTF8());
+    let mut lexer = GuardFallbackLexer::define();
+    let actual    = Outcome::from(lexer.run(reader));
+    let expected  = reference::lex(&GuardFallbackLexer::spec(),"abc");
+    let texts     = actual.tokens.iter().map(|token| token.text.as_str()).collect::<Vec<_>>();
+    assert!(actual.finished);
+    assert_eq!(texts,vec!["a","b","c",""]);
+    assert_eq!(actual,expected);
+}
@@ -108,3 +108,74 @@ fn test_end_invalid() {
     let result = run_test_on(input);
     assert_eq!(result, expected_output);
 }
+
+#[test]
+fn test_invalid_character() {
+    let input      = b"aa \xED\xA0\x80";
+    let reader     = Reader::new(&input[..], DecoderUTF8());
+    let mut lexer  = TestLexer::new();
+    let run_result = lexer.run(reader);
+    match run_result.kind {
+        flexer::ResultKind::Error(error) => {
+            let kind = flexer::ErrorKind::Reader(reader::Error::InvalidChar);
+            assert_eq!(error.kind, kind);
+            assert_eq!(error.position, 3);
+            assert_eq!(error.state_stack.last(), Some(&error.group));
+        },
+        kind => panic!("Expected an error, got {:?}.", kind),
+    }
+    let expected_output = TokenStream::from(vec![Token::word("aa"), Token::unrecognized(" ")]);
+    assert_eq!(run_result.tokens, expected_output);
+}
+
+#[test]
+fn test_unknown_bookmark() {
+    let mut other  = TestLexer::new();
+    let bookmark   = other.define_bookmark("mark");
+    let mut reader = Reader::new("aa".as_bytes(), DecoderUTF8());
+    let mut lexer  = TestLexer::new();
+    assert!(!lexer.rewind_to(bookmark, &mut reader));
+    match lexer.result_kind() {
+        flexer::ResultKind::Error(error) => {
+            assert_eq!(error.kind, flexer::ErrorKind::UnknownBookmark(0));
+            assert!(error.to_string().starts_with("The lexer used unknown bookmark 0 at byte 0"));
+        },
+        kind => panic!("Expected an error, got {:?}.", kind),
+    }
+}
+
+#[test]
+fn test_result_progress() {
+    let input      = "aaaaaa c bbbbbb";
+    let reader     = Reader::new(input.as_bytes(), DecoderUTF8());
+    let mut lexer  = TestLexer::new();
+    let run_result = lexer.run(reader);
+    assert_eq!(run_result.kind, flexer::ResultKind::Success);
+    assert_eq!(run_result.position, input.len());
+    assert_eq!(run_result.consumed, input.chars().count());
+    assert_eq!(run_result.state_stack, vec![String::from("ROOT")]);
+    let messages = run_result.diagnostics.iter().map(|d| d.message.as_str()).collect_vec();
+    let expected = vec!["Unrecognized input \" \".","Unrecognized input \"c\".",
+        "Unrecognized input \" \"."];
+    assert_eq!(messages, expected);
+    assert_eq!(run_result.diagnostics[1].span, flexer::span::Span::new(7,8));
+    assert!(!run_result.has_errors());
+}
+
+#[test]
+fn test_rewind_current_match_repeatedly() {
+    let input      = "aa, bb";
+    let reader     = Reader::new(input.as_bytes(), DecoderUTF8());
+    let mut lexer  = TestLexer::new();
+    let run_result = lexer.run(reader);
+    assert_eq!(run_result.kind, flexer::ResultKind::Success);
+    assert_eq!(run_result.position, input.len());
+    assert_eq!(run_result.consumed, input.chars().count());
+    let expected_output = TokenStream::from(vec![
+        Token::word("aa"),
+        Token::unrecognized(","),
+        Token::unrecognized(" "),
+        Token::word("bb"),
+    ]);
+    assert_eq!(run_result.tokens, expected_output);
+}
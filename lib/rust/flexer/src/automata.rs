@@ -3,6 +3,7 @@
 
 pub mod alphabet;
 pub mod dfa;
+pub mod matcher;
 pub mod nfa;
 pub mod pattern;
 pub mod state;
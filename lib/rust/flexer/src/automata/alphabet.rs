@@ -3,6 +3,7 @@
 use crate::prelude::*;
 
 use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
 
 use std::collections::BTreeSet;
 use std::ops::RangeInclusive;
@@ -45,23 +46,45 @@ use std::ops::RangeInclusive;
 /// seg: 1   2     3  4      5
 /// ```
 ///
-/// This type tracks these divisions explicitly for an input alphabet defined for all automata in
-/// this library as `0u32..=u32::max_value()`.
+/// This type tracks these divisions explicitly for an input alphabet that covers all of the
+/// symbols of type `S`, such as `0u32..=u32::max_value()` for the [`Symbol`]s read by lexers.
 #[derive(Clone,Debug,PartialEq,Eq)]
 #[allow(missing_docs)]
-pub struct Segmentation {
-    pub divisions:BTreeSet<Symbol>
+pub struct Segmentation<S=Symbol> {
+    pub divisions:BTreeSet<S>
 }
 
-impl Segmentation {
+impl<S:Symbolic> Segmentation<S> {
     /// Inserts a range of symbols into the alphabet.
-    pub fn insert(&mut self, range:RangeInclusive<Symbol>) {
-        self.divisions.insert(Symbol::from(range.start()));
-        if range.end().value != Symbol::EOF_CODE.value {
-            self.divisions.insert(Symbol{value:range.end().value + 1});
+    pub fn insert(&mut self, range:RangeInclusive<S>) {
+        self.divisions.insert(*range.start());
+        if let Some(next) = range.end().next() {
+            self.divisions.insert(next);
         }
     }
 
+    /// Obtains the divisions in the alphabet segmentation as a vector.
+    pub fn divisions_as_vec(&self) -> Vec<Division<S>> {
+        self.divisions.iter().copied().enumerate().map(From::from).collect()
+    }
+
+    /// Obtains the index of the division that contains `symbol`.
+    pub fn division_index(&self, symbol:S) -> usize {
+        self.divisions.range(..=symbol).count().saturating_sub(1)
+    }
+
+    /// Obtains a single symbol from each division, in order.
+    ///
+    /// As all symbols in a division are treated identically by an automaton over this alphabet,
+    /// any of them can stand for the whole division. It is chosen by [`Symbolic::representative`].
+    pub fn representatives(&self) -> Vec<S> {
+        let starts = self.divisions.iter();
+        let ends   = self.divisions.iter().skip(1).copied().map(Some).chain(iter::once(None));
+        starts.zip(ends).map(|(start,end)| S::representative(*start,end)).collect()
+    }
+}
+
+impl Segmentation {
     /// Creates a [`Segmentation`] from an input set of divisions.
     pub fn from_divisions(divisions:&[u32]) -> Self {
         let mut dict = Self::default();
@@ -70,19 +93,14 @@ impl Segmentation {
         }
         dict
     }
-
-    /// Obtains the divisions in the alphabet segmentation as a vector.
-    pub fn divisions_as_vec(&self) -> Vec<Division> {
-        self.divisions.iter().copied().enumerate().map(From::from).collect()
-    }
 }
 
 
 // === Trait Impls ===
 
-impl Default for Segmentation {
+impl<S:Symbolic> Default for Segmentation<S> {
     fn default() -> Self {
-        let mut divisions: BTreeSet<Symbol> = default();
+        let mut divisions:BTreeSet<S> = default();
         // The existence of the default (0) member in the set is assumed by the implementation of
         // the NFA -> DFA conversion.
         divisions.insert(default());
@@ -98,16 +116,16 @@ impl Default for Segmentation {
 
 /// A division of the alphabet used by the lexer.
 #[derive(Copy,Clone,Debug,PartialEq,Eq)]
-pub struct Division {
+pub struct Division<S=Symbol> {
     /// The position of the division.
     pub position : usize,
     /// The symbol at which it divides the alphabet.
-    pub symbol : Symbol,
+    pub symbol : S,
 }
 
-impl Division {
+impl<S> Division<S> {
     /// Create a new division.
-    pub fn new(position:usize, symbol:Symbol) -> Division {
+    pub fn new(position:usize, symbol:S) -> Division<S> {
         Division{position,symbol}
     }
 }
@@ -115,14 +133,14 @@ impl Division {
 
 // === Trait Impls ===
 
-impl Into<(usize,Symbol)> for Division {
-    fn into(self) -> (usize, Symbol) {
+impl<S> Into<(usize,S)> for Division<S> {
+    fn into(self) -> (usize, S) {
         (self.position,self.symbol)
     }
 }
 
-impl From<(usize,Symbol)> for Division {
-    fn from((position, symbol): (usize, Symbol)) -> Self {
+impl<S> From<(usize,S)> for Division<S> {
+    fn from((position, symbol): (usize, S)) -> Self {
         Division::new(position,symbol)
     }
 }
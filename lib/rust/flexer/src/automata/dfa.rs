@@ -2,8 +2,14 @@
 
 use crate::automata::alphabet;
 use crate::automata::state;
+use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
 use crate::data::matrix::Matrix;
 
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::collections::hash_map::Entry;
+
 
 
 // =====================================
@@ -21,10 +27,10 @@ use crate::data::matrix::Matrix;
 ///  │ 0 │ ----> │ 1 │ ----> │ 2 │ ----> │ 3 │
 ///  └───┘       └───┘       └───┘       └───┘
 /// ```
-#[derive(Clone,Debug,Default,Eq,PartialEq)]
-pub struct DFA {
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct DFA<S=Symbol> {
     /// A set of disjoint intervals over the allowable input alphabet.
-    pub alphabet_segmentation:alphabet::Segmentation,
+    pub alphabet_segmentation:alphabet::Segmentation<S>,
     /// The transition matrix for the DFA.
     ///
     /// It represents a function of type `(state, symbol) -> state`, returning the identifier for
@@ -45,7 +51,7 @@ pub struct DFA {
     pub callbacks:Vec<Option<RuleExecutable>>,
 }
 
-impl DFA {
+impl<S:Symbolic> DFA<S> {
     /// Check whether the DFA has a rule for the target state.
     ///
     /// This method should only be used in generated code, where its invariants are already checked.
@@ -56,11 +62,80 @@ impl DFA {
     pub fn has_rule_for(&self, target_state:state::Identifier) -> bool {
         self.callbacks.get(target_state.id).unwrap().is_some()
     }
+
+    /// Check whether `state` is an accepting state of the DFA.
+    ///
+    /// The invalid state never accepts.
+    pub fn is_accepting(&self, state:state::Identifier) -> bool {
+        state != state::Identifier::INVALID && self.has_rule_for(state)
+    }
+
+    /// Get the state that the DFA transitions to from `state` when reading `symbol`.
+    ///
+    /// Once in the invalid state, the DFA remains there.
+    pub fn next_state(&self, state:state::Identifier, symbol:S) -> state::Identifier {
+        if state == state::Identifier::INVALID {
+            state
+        } else {
+            self.links[(state.id,self.alphabet_segmentation.division_index(symbol))]
+        }
+    }
+
+    /// Find a shortest input after which the acceptance of `self` and `other` satisfies
+    /// `condition`.
+    ///
+    /// The `condition` is given whether `self` and `other` (in that order) accept the input. This
+    /// performs a breadth-first search of the product automaton of both DFAs, running over the
+    /// union of their alphabet segmentations, and returns `None` if no such input exists.
+    pub fn shortest_input_where
+    ( &self
+    , other     : &DFA<S>
+    , condition : impl Fn(bool,bool) -> bool
+    ) -> Option<Vec<S>> {
+        let mut alphabet = self.alphabet_segmentation.clone();
+        alphabet.divisions.extend(other.alphabet_segmentation.divisions.iter());
+        let symbols     = alphabet.representatives();
+        let start       = (state::Identifier::new(0),state::Identifier::new(0));
+        let mut parents = HashMap::new();
+        let mut queue   = VecDeque::new();
+        parents.insert(start,None);
+        queue.push_back(start);
+        while let Some(pair) = queue.pop_front() {
+            let (lhs,rhs) = pair;
+            if condition(self.is_accepting(lhs),other.is_accepting(rhs)) {
+                let mut input   = Vec::new();
+                let mut current = pair;
+                while let Some(&Some((parent,symbol))) = parents.get(&current) {
+                    input.push(symbol);
+                    current = parent;
+                }
+                input.reverse();
+                return Some(input)
+            }
+            for &symbol in &symbols {
+                let next = (self.next_state(lhs,symbol),other.next_state(rhs,symbol));
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some((pair,symbol)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
 }
 
 
 // === Trait Impls ===
 
+impl<S:Symbolic> Default for DFA<S> {
+    fn default() -> Self {
+        let alphabet_segmentation = Default::default();
+        let links                 = Default::default();
+        let callbacks             = Default::default();
+        DFA{alphabet_segmentation,links,callbacks}
+    }
+}
+
 impl From<Vec<Vec<usize>>> for Matrix<state::Identifier> {
     fn from(input:Vec<Vec<usize>>) -> Self {
         let rows        = input.len();
@@ -86,19 +161,52 @@ impl From<Vec<Vec<usize>>> for Matrix<state::Identifier> {
 /// It contains the rust code that is intended to be executed after encountering a
 /// [`pattern`](super::pattern::Pattern) that causes the associated state transition. This pattern
 /// is declared in [`Rule.pattern`](crate::group::rule::Rule::pattern).
-#[derive(Clone,Debug,PartialEq,Eq)]
+///
+/// When the rule is [guarded](crate::group::rule::Rule::guard), the callback also records the
+/// rule to try instead if the guard rejects the match.
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
 pub struct RuleExecutable {
     /// A description of the priority with which the callback is constructed during codegen.
     pub priority:usize,
     /// The rust code that will be executed when running this callback.
     pub code:String,
+    /// The condition that must hold for this callback to run.
+    pub guard:Option<String>,
+    /// The callback to try if `guard` does not hold.
+    pub alternative:Option<Box<RuleExecutable>>,
 }
 
 impl RuleExecutable {
     /// Creates a new rule executable with the provided `priority` and `code`.
     pub fn new(priority:usize, code_str:impl Into<String>) -> RuleExecutable {
-        let code = code_str.into();
-        RuleExecutable{priority,code}
+        let code        = code_str.into();
+        let guard       = None;
+        let alternative = None;
+        RuleExecutable{priority,code,guard,alternative}
+    }
+
+    /// Creates a new rule executable that only runs when `guard` holds, trying `alternative`
+    /// otherwise.
+    pub fn guarded
+    ( priority    : usize
+    , code_str    : impl Into<String>
+    , guard       : impl Into<String>
+    , alternative : Option<RuleExecutable>
+    ) -> RuleExecutable {
+        let code        = code_str.into();
+        let guard       = Some(guard.into());
+        let alternative = alternative.map(Box::new);
+        RuleExecutable{priority,code,guard,alternative}
+    }
+
+    /// Check whether some callback in the chain of alternatives starting at `self` is certain to
+    /// run.
+    pub fn is_unconditional(&self) -> bool {
+        match (&self.guard,&self.alternative) {
+            (None,_)             => true,
+            (Some(_),Some(next)) => next.is_unconditional(),
+            (Some(_),None)       => false,
+        }
     }
 }
 
@@ -123,7 +231,7 @@ pub mod tests {
             links:Matrix::from(vec![vec![INVALID,1,INVALID], vec![INVALID,INVALID,INVALID]]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:2, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(2,"group_0_rule_0")),
             ],
         }
     }
@@ -135,7 +243,7 @@ pub mod tests {
             links:Matrix::from(vec![vec![INVALID,1,INVALID], vec![INVALID,INVALID,INVALID]]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:2, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(2,"group_0_rule_0")),
             ],
         }
     }
@@ -151,8 +259,8 @@ pub mod tests {
             ]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:3, code:"group_0_rule_0".into()}),
-                Some(RuleExecutable {priority:3, code:"group_0_rule_0".into()}),
+                Some(RuleExecutable::new(3,"group_0_rule_0")),
+                Some(RuleExecutable::new(3,"group_0_rule_0")),
             ],
         }
     }
@@ -169,9 +277,9 @@ pub mod tests {
             ]),
             callbacks:vec![
                 None,
-                Some(RuleExecutable {priority:4, code:"group_0_rule_1".into()}),
-                Some(RuleExecutable {priority:4, code:"group_0_rule_0".into()}),
-                Some(RuleExecutable {priority:4, code:"group_0_rule_1".into()}),
+                Some(RuleExecutable::new(4,"group_0_rule_1")),
+                Some(RuleExecutable::new(4,"group_0_rule_0")),
+                Some(RuleExecutable::new(4,"group_0_rule_1")),
             ],
         }
     }
@@ -0,0 +1,141 @@
+//! A matcher that finds which of a list of patterns matches the longest prefix of an input.
+//!
+//! Unlike a generated lexer, the matcher interprets its DFA at runtime, so it can be built for
+//! patterns over any type of [`Symbolic`] symbols, such as the kinds of tokens in a token stream.
+
+use crate::automata::dfa::DFA;
+use crate::automata::nfa::NFA;
+use crate::automata::pattern::Pattern;
+use crate::automata::state;
+use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
+
+
+
+// ===============
+// === Matcher ===
+// ===============
+
+/// A matcher for a list of patterns.
+#[derive(Clone,Debug)]
+pub struct Matcher<S=Symbol> {
+    /// The DFA recognising all of the patterns, whose accepting states are named after the index
+    /// of the pattern that they accept.
+    dfa:DFA<S>,
+}
+
+impl<S:Symbolic> Matcher<S> {
+    /// Create a matcher for `patterns`.
+    ///
+    /// When several patterns match the same input, the one that comes first in `patterns` wins.
+    pub fn new(patterns:&[Pattern<S>]) -> Matcher<S> {
+        let mut nfa = NFA::default();
+        let start   = nfa.new_state();
+        for (index,pattern) in patterns.iter().enumerate() {
+            let end = nfa.new_pattern(start,&pattern.normalize());
+            nfa.states[end.id].name = Some(index.to_string());
+        }
+        let dfa = DFA::from(&nfa);
+        Matcher{dfa}
+    }
+
+    /// Find the pattern that matches the longest prefix of `input`.
+    pub fn longest_match(&self, input:impl IntoIterator<Item=S>) -> Option<Match> {
+        let mut state  = state::Identifier::new(0);
+        let mut result = self.match_in(state,0);
+        for (index,symbol) in input.into_iter().enumerate() {
+            state = self.dfa.next_state(state,symbol);
+            if state == state::Identifier::INVALID {
+                break
+            }
+            result = self.match_in(state,index + 1).or(result);
+        }
+        result
+    }
+
+    /// Find the pattern that matches all of `input`.
+    pub fn full_match(&self, input:impl IntoIterator<Item=S>) -> Option<usize> {
+        let mut state  = state::Identifier::new(0);
+        let mut length = 0;
+        for symbol in input {
+            state   = self.dfa.next_state(state,symbol);
+            length += 1;
+        }
+        self.match_in(state,length).map(|result| result.pattern)
+    }
+
+    /// The match of the given `length` that ends in `state`, if `state` is accepting.
+    fn match_in(&self, state:state::Identifier, length:usize) -> Option<Match> {
+        if self.dfa.is_accepting(state) {
+            let callback = self.dfa.callbacks[state.id].as_ref()?;
+            let pattern  = callback.code.parse().ok()?;
+            Some(Match{pattern,length})
+        } else {
+            None
+        }
+    }
+}
+
+
+
+// =============
+// === Match ===
+// =============
+
+/// A prefix of an input matched by one of the patterns of a [`Matcher`].
+#[derive(Clone,Copy,Debug,Eq,Hash,PartialEq)]
+pub struct Match {
+    /// The index of the pattern that matched.
+    pub pattern:usize,
+    /// The number of symbols that the pattern matched.
+    pub length:usize,
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_match() {
+        let keywords = Pattern::literals(&["if","in","infix"]);
+        let name     = Pattern::range('a'..='z').many1();
+        let matcher  = Matcher::new(&[keywords,name]);
+        let symbols  = |input:&str| input.chars().map(Symbol::from).collect::<Vec<_>>();
+        assert_eq!(matcher.longest_match(symbols("in x")),Some(Match{pattern:0,length:2}));
+        assert_eq!(matcher.longest_match(symbols("info")),Some(Match{pattern:1,length:4}));
+        assert_eq!(matcher.longest_match(symbols("infix")),Some(Match{pattern:0,length:5}));
+        assert_eq!(matcher.longest_match(symbols("!")),None);
+        assert_eq!(matcher.full_match(symbols("info")),Some(1));
+        assert_eq!(matcher.full_match(symbols("in x")),None);
+    }
+
+    #[test]
+    fn test_char_symbols() {
+        let surrogates = Pattern::symbols('\u{D000}'..='\u{E000}');
+        let matcher    = Matcher::new(&[Pattern::symbol('a').many(),surrogates]);
+        assert_eq!(matcher.full_match("".chars()),Some(0));
+        assert_eq!(matcher.full_match("aaa".chars()),Some(0));
+        assert_eq!(matcher.full_match("\u{D7FF}".chars()),Some(1));
+        assert_eq!(matcher.full_match("\u{E000}".chars()),Some(1));
+        assert_eq!(matcher.full_match("\u{E001}".chars()),None);
+        assert_eq!(Symbolic::next('\u{D7FF}'),Some('\u{E000}'));
+        assert_eq!(Symbolic::next(char::MAX),None);
+    }
+
+    #[test]
+    fn test_byte_symbols() {
+        let header  = Pattern::sequences(vec![b"GIF87a".to_vec(),b"GIF89a".to_vec()]);
+        let high    = Pattern::symbols(0x80..=0xFF).many1();
+        let matcher = Matcher::new(&[header,high]);
+        assert_eq!(matcher.longest_match(b"GIF89a...".iter().copied()).map(|m| m.length),Some(6));
+        assert_eq!(matcher.full_match(vec![0x80,0xFF]),Some(1));
+        assert_eq!(matcher.full_match(vec![0x7F]),None);
+        assert!(Pattern::symbols(0..=0x7F).is_subset_of(&Pattern::symbols(0..=0xFF)).is_ok());
+    }
+}
@@ -8,6 +8,7 @@ use crate::automata::state::State;
 use crate::automata::state::Transition;
 use crate::automata::state;
 use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
 use crate::data::matrix::Matrix;
 
 use itertools::Itertools;
@@ -42,15 +43,15 @@ type StateSetId = BTreeSet<state::Identifier>;
 ///  │ 0 │ ----> │ 1 │ -> │ 2 │ ----> │ 3 │ -> │ 3 │ ----> │ 3 │
 ///  └───┘       └───┘ ε  └───┘       └───┘ ε  └───┘       └───┘
 /// ```
-#[derive(Clone,Debug,Default,PartialEq,Eq)]
-pub struct NFA {
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct NFA<S=Symbol> {
     /// A set of disjoint intervals over the input alphabet.
-    pub alphabet_segmentation:alphabet::Segmentation,
+    pub alphabet_segmentation:alphabet::Segmentation<S>,
     /// A set of named NFA states, with (epsilon) transitions.
-    pub states:Vec<State>,
+    pub states:Vec<State<S>>,
 }
 
-impl NFA {
+impl<S:Symbolic> NFA<S> {
     /// Adds a new state to the NFA and returns its identifier.
     pub fn new_state(&mut self) -> state::Identifier {
         let id = self.states.len();
@@ -74,7 +75,7 @@ impl NFA {
     ( &mut self
     , source       : state::Identifier
     , target_state : state::Identifier
-    , symbols      : &RangeInclusive<Symbol>
+    , symbols      : &RangeInclusive<S>
     ) {
         self.alphabet_segmentation.insert(symbols.clone());
         self.states[source.id].links.push(Transition{symbols:symbols.clone(),target_state});
@@ -83,7 +84,11 @@ impl NFA {
     /// Transforms a pattern to an NFA using the algorithm described
     /// [here](https://www.youtube.com/watch?v=RYNN-tb9WxI).
     /// The asymptotic complexity is linear in number of symbols.
-    pub fn new_pattern(&mut self, source:state::Identifier, pattern:&Pattern) -> state::Identifier {
+    pub fn new_pattern
+    ( &mut self
+    , source  : state::Identifier
+    , pattern : &Pattern<S>
+    ) -> state::Identifier {
         let current = self.new_state();
         self.connect(source,current);
         match pattern {
@@ -113,6 +118,21 @@ impl NFA {
                 }
                 end
             },
+            Pattern::Literals(literals) => {
+                let end      = self.new_state();
+                let mut trie = HashMap::<(state::Identifier,S),state::Identifier>::new();
+                for literal in literals {
+                    let last = literal.iter().fold(current,|state,&symbol| {
+                        *trie.entry((state,symbol)).or_insert_with(|| {
+                            let next = self.new_state();
+                            self.connect_via(state,next,&(symbol..=symbol));
+                            next
+                        })
+                    });
+                    self.connect(last,end);
+                }
+                end
+            },
             Pattern::Always => current,
         }
     }
@@ -120,8 +140,8 @@ impl NFA {
     /// Merges states that are connected by epsilon links, using an algorithm based on the one shown
     /// [here](https://www.youtube.com/watch?v=taClnxU-nao).
     fn eps_matrix(&self) -> Vec<StateSetId> {
-        fn fill_eps_matrix
-        ( nfa      : &NFA
+        fn fill_eps_matrix<S:Symbolic>
+        ( nfa      : &NFA<S>
         , states   : &mut Vec<StateSetId>
         , visited  : &mut Vec<bool>
         , state    : state::Identifier
@@ -164,12 +184,33 @@ impl NFA {
 
 // === Trait Impls ===
 
-impl From<&NFA> for DFA {
+impl<S:Symbolic> Default for NFA<S> {
+    fn default() -> Self {
+        let alphabet_segmentation = default();
+        let states                = default();
+        NFA{alphabet_segmentation,states}
+    }
+}
+
+impl<S:Symbolic> From<&Pattern<S>> for NFA<S> {
+    /// Creates an NFA that accepts exactly the inputs matched by `pattern`.
+    ///
+    /// The accepting state is named `"pattern"`, so that it is marked as accepting in the DFA.
+    fn from(pattern:&Pattern<S>) -> Self {
+        let mut nfa = NFA::default();
+        let start   = nfa.new_state();
+        let end     = nfa.new_pattern(start,pattern);
+        nfa.states[end.id].name = Some("pattern".into());
+        nfa
+    }
+}
+
+impl<S:Symbolic> From<&NFA<S>> for DFA<S> {
 
     /// Transforms an NFA into a DFA, based on the algorithm described
     /// [here](https://www.youtube.com/watch?v=taClnxU-nao).
     /// The asymptotic complexity is quadratic in number of states.
-    fn from(nfa:&NFA) -> Self {
+    fn from(nfa:&NFA<S>) -> Self {
         let     nfa_mat     = nfa.nfa_matrix();
         let     eps_mat     = nfa.eps_matrix();
         let mut dfa_mat     = Matrix::new(0,nfa.alphabet_segmentation.divisions.len());
@@ -209,10 +250,17 @@ impl From<&NFA> for DFA {
         let     priority  = dfa_eps_ixs.len();
         for (dfa_ix, epss) in dfa_eps_ixs.into_iter().enumerate() {
             let has_name = |&key:&state::Identifier| nfa.states[key.id].name.is_some();
-            if let Some(eps) = epss.into_iter().find(has_name) {
-                let code          = nfa.states[eps.id].name.as_ref().cloned().unwrap();
-                callbacks[dfa_ix] = Some(RuleExecutable {code,priority});
-            }
+            let named    = epss.into_iter().filter(has_name).collect_vec();
+            // The rules are tried in order of definition, and a rule without a guard always runs,
+            // so the alternatives are built from the back and those after it are dropped.
+            callbacks[dfa_ix] = named.into_iter().rev().fold(None,|alternative,eps| {
+                let state = &nfa.states[eps.id];
+                let code  = state.name.as_ref().cloned().unwrap();
+                match &state.guard {
+                    None        => Some(RuleExecutable::new(priority,code)),
+                    Some(guard) => Some(RuleExecutable::guarded(priority,code,guard,alternative)),
+                }
+            });
         }
 
         let alphabet_segmentation = nfa.alphabet_segmentation.clone();
@@ -323,6 +371,16 @@ pub mod tests {
         assert_eq!(DFA::from(&letter_and_spaces()),dfa::tests::letter_and_spaces());
     }
 
+    #[test]
+    fn test_literals_share_prefixes() {
+        let keywords    = ["import","in","if","infix","int"];
+        let alternative = keywords.iter().map(|kw| Pattern::all_of(kw)).fold1(|l,r| l | r).unwrap();
+        let trie        = NFA::from(&Pattern::literals(&keywords));
+        let links       = trie.states.iter().map(|state| state.links.len()).sum::<usize>();
+        assert_eq!(links,"import".len() + "nfix".len() + "t".len() + "f".len());
+        assert!(trie.states.len() < NFA::from(&alternative).states.len());
+    }
+
     #[bench]
     fn bench_to_dfa_newline(bencher:&mut Bencher) {
         bencher.iter(|| DFA::from(&newline()))
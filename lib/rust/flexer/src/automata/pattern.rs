@@ -3,10 +3,14 @@
 #[macro_use]
 mod macros;
 
+use crate::automata::dfa::DFA;
+use crate::automata::nfa::NFA;
 use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
 
 use core::iter;
 use itertools::Itertools;
+use std::fmt;
 use std::ops::BitOr;
 use std::ops::RangeInclusive;
 use std::ops::Shr;
@@ -20,37 +24,32 @@ use Pattern::*;
 // =============
 
 /// A representation of a simple regular pattern.
-#[derive(Clone,Debug)]
-pub enum Pattern {
+///
+/// The patterns used by lexers run over [`Symbol`]s, but a pattern can run over any type of
+/// [`Symbolic`] symbols, such as the kinds of tokens in a token stream.
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
+pub enum Pattern<S=Symbol> {
     /// The pattern that triggers on any symbol from the given range.
-    Range(RangeInclusive<Symbol>),
+    Range(RangeInclusive<S>),
     /// The pattern that triggers on any given pattern from a sequence.
-    Or(Vec<Pattern>),
+    Or(Vec<Pattern<S>>),
     /// The pattern that triggers when a sequence of patterns is encountered.
-    Seq(Vec<Pattern>),
+    Seq(Vec<Pattern<S>>),
     /// The pattern that triggers on 0..N repetitions of given pattern.
-    Many(Box<Pattern>),
+    Many(Box<Pattern<S>>),
+    /// The pattern that triggers on any sequence of symbols from a sorted list without duplicates.
+    Literals(Vec<Vec<S>>),
     /// The pattern that always triggers.
     Always,
 }
 
-impl Pattern {
-
-    /// A pattern that never triggers.
-    pub fn never() -> Self {
-        Pattern::symbol(Symbol::INVALID_SYMBOL)
-    }
+impl<S:Symbolic> Pattern<S> {
 
     /// A pattern that always triggers
     pub fn always() -> Self {
         Pattern::Always
     }
 
-    /// A pattern that triggers on any character.
-    pub fn any() -> Self {
-        Pattern::symbols(Symbol::from(0)..=Symbol::from(u32::max_value()))
-    }
-
     /// A pattern that triggers on 0..N repetitions of the pattern described by `self`.
     pub fn many(&self) -> Self {
         Many(Box::new(self.clone()))
@@ -66,21 +65,159 @@ impl Pattern {
         self.clone() | Self::always()
     }
 
-    /// A pattern that triggers on the given character.
-    pub fn char(character:char) -> Self {
-        Self::symbol(Symbol::from(character))
-    }
-
     /// A pattern that triggers on the given symbol.
-    pub fn symbol(symbol:Symbol) -> Self {
+    pub fn symbol(symbol:S) -> Self {
         Pattern::symbols(symbol..=symbol)
     }
 
     /// A pattern that triggers on any of the provided `symbols`.
-    pub fn symbols(symbols:RangeInclusive<Symbol>) -> Self {
+    pub fn symbols(symbols:RangeInclusive<S>) -> Self {
         Pattern::Range(symbols)
     }
 
+    /// The pattern that triggers on any of the sequences of symbols in `sequences`.
+    ///
+    /// Unlike an alternative of sequences of [`Pattern::symbol`] patterns, it is compiled to a trie
+    /// in which the sequences share their common prefixes, which keeps the NFA small for keyword
+    /// tables.
+    pub fn sequences(sequences:impl IntoIterator<Item=Vec<S>>) -> Self {
+        let mut sequences = sequences.into_iter().collect_vec();
+        sequences.sort();
+        sequences.dedup();
+        Literals(sequences)
+    }
+
+    /// The pattern that triggers on `num` repetitions of `pat`.
+    pub fn repeat(pat:Pattern<S>, num:usize) -> Self {
+        (0..num).fold(Self::always(),|p,_| p >> pat.clone())
+    }
+
+    /// Simplify the pattern without changing the inputs that it matches.
+    ///
+    /// Nested alternatives are flattened. Among them, the ranges of symbols that overlap or are
+    /// adjacent are merged, and the literal sequences of symbols are gathered into a single
+    /// [`Pattern::Literals`].
+    pub fn normalize(&self) -> Self {
+        match self {
+            Or(patterns)  => Self::normalize_alternatives(patterns),
+            Seq(patterns) => Seq(patterns.iter().map(Self::normalize).collect()),
+            Many(body)    => body.normalize().many(),
+            _             => self.clone(),
+        }
+    }
+
+    /// Normalize the alternatives of an [`Pattern::Or`].
+    fn normalize_alternatives(patterns:&[Pattern<S>]) -> Self {
+        let mut ranges       = Vec::new();
+        let mut literals     = Vec::new();
+        let mut alternatives = Vec::new();
+        let flattened        = patterns.iter().map(Self::normalize).flat_map(|pattern| {
+            match pattern {
+                Or(nested) => nested,
+                pattern    => vec![pattern],
+            }
+        });
+        for pattern in flattened {
+            match pattern {
+                Range(range)      => ranges.push(range),
+                Literals(literal) => literals.extend(literal),
+                pattern           => match pattern.as_literal() {
+                    Some(literal) => literals.push(literal),
+                    None          => alternatives.push(pattern),
+                }
+            }
+        }
+        let mut result = Self::merge_ranges(ranges).into_iter().map(Range).collect_vec();
+        if !literals.is_empty() {
+            result.push(Self::sequences(literals));
+        }
+        result.extend(alternatives);
+        if result.len() == 1 {result.pop().unwrap()} else {Or(result)}
+    }
+
+    /// Sort `ranges` and merge the ones that overlap or are adjacent.
+    fn merge_ranges(mut ranges:Vec<RangeInclusive<S>>) -> Vec<RangeInclusive<S>> {
+        ranges.sort_by_key(|range| (*range.start(),*range.end()));
+        let separate   = |last:&RangeInclusive<S>, range:&RangeInclusive<S>| {
+            matches!(last.end().next(),Some(next) if *range.start() > next)
+        };
+        let mut merged = Vec::<RangeInclusive<S>>::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if !separate(last,&range) => {
+                    let end = *last.end().max(range.end());
+                    *last   = *last.start()..=end;
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// The sequence of symbols matched by `self`, if it is a sequence of single symbols.
+    fn as_literal(&self) -> Option<Vec<S>> {
+        match self {
+            Seq(patterns) => patterns.iter().map(Self::as_symbol).collect(),
+            _             => None,
+        }
+    }
+
+    /// The symbol matched by `self`, if it is a range of a single symbol.
+    fn as_symbol(&self) -> Option<S> {
+        match self {
+            Range(range) if range.start() == range.end() => Some(*range.start()),
+            _                                            => None,
+        }
+    }
+
+    /// Check whether `self` and `other` match exactly the same inputs.
+    ///
+    /// On failure, returns a shortest input that is matched by only one of the two patterns.
+    pub fn equivalent(&self, other:&Pattern<S>) -> Result<(),Counterexample<S>> {
+        self.find_counterexample(other,|lhs,rhs| lhs != rhs)
+    }
+
+    /// Check whether every input matched by `self` is also matched by `other`.
+    ///
+    /// On failure, returns a shortest input that is matched by `self` but not by `other`.
+    pub fn is_subset_of(&self, other:&Pattern<S>) -> Result<(),Counterexample<S>> {
+        self.find_counterexample(other,|lhs,rhs| lhs && !rhs)
+    }
+
+    /// Search the product of the DFAs for `self` and `other` for a shortest input on which their
+    /// acceptance satisfies `condition`.
+    fn find_counterexample
+    ( &self
+    , other     : &Pattern<S>
+    , condition : impl Fn(bool,bool) -> bool
+    ) -> Result<(),Counterexample<S>> {
+        let lhs = DFA::from(&NFA::from(self));
+        let rhs = DFA::from(&NFA::from(other));
+        match lhs.shortest_input_where(&rhs,condition) {
+            Some(symbols) => Err(Counterexample{symbols}),
+            None          => Ok(()),
+        }
+    }
+}
+
+/// Patterns over the characters read by lexers.
+impl Pattern {
+
+    /// A pattern that never triggers.
+    pub fn never() -> Self {
+        Pattern::symbol(Symbol::INVALID_SYMBOL)
+    }
+
+    /// A pattern that triggers on any character.
+    pub fn any() -> Self {
+        Pattern::symbols(Symbol::from(0)..=Symbol::from(u32::max_value()))
+    }
+
+    /// A pattern that triggers on the given character.
+    pub fn char(character:char) -> Self {
+        Self::symbol(Symbol::from(character))
+    }
+
     /// A pattern that triggers at the end of the file.
     pub fn eof() -> Self {
         Self::symbol(Symbol::EOF_CODE)
@@ -101,6 +238,13 @@ impl Pattern {
         }
     }
 
+    /// The pattern that triggers on any of the strings in `literals`.
+    ///
+    /// Like [`Pattern::sequences`], the strings share their common prefixes in the NFA.
+    pub fn literals(literals:&[&str]) -> Self {
+        Self::sequences(literals.iter().map(|literal| literal.chars().map(Symbol::from).collect()))
+    }
+
     /// The pattern that triggers on any characters contained in `chars`.
     pub fn any_of(chars:&str) -> Self {
         chars.chars().fold(Self::never(),|pat,char| pat | Self::char(char))
@@ -127,11 +271,6 @@ impl Pattern {
         Self::none_of(&char.to_string())
     }
 
-    /// The pattern that triggers on `num` repetitions of `pat`.
-    pub fn repeat(pat:Pattern, num:usize) -> Self {
-        (0..num).fold(Self::always(),|p,_| p >> pat.clone())
-    }
-
     /// Pattern that triggers on `min`..`max` repetitions of `pat`.
     pub fn repeat_between(pat:Pattern, min:usize, max:usize) -> Self {
         (min..max).fold(Self::never(),|p,n| p | Self::repeat(pat.clone(),n))
@@ -141,9 +280,9 @@ impl Pattern {
 
 // === Trait Impls ====
 
-impl BitOr<Pattern> for Pattern {
-    type Output = Pattern;
-    fn bitor(self, rhs:Pattern) -> Self::Output {
+impl<S:Symbolic> BitOr<Pattern<S>> for Pattern<S> {
+    type Output = Pattern<S>;
+    fn bitor(self, rhs:Pattern<S>) -> Self::Output {
         match (self, rhs) {
             (Or(mut lhs), Or(    rhs)) => {lhs.extend(rhs)   ; Or(lhs)},
             (Or(mut lhs), rhs        ) => {lhs.push(rhs)     ; Or(lhs)},
@@ -152,11 +291,11 @@ impl BitOr<Pattern> for Pattern {
         }
     }
 }
-gen_ref_versions!(Pattern,BitOr,bitor);
+gen_ref_versions!(Pattern<S>,BitOr,bitor,<S:Symbolic>);
 
-impl Shr<Pattern> for Pattern {
-    type Output = Pattern;
-    fn shr(self, rhs:Pattern) -> Self::Output {
+impl<S:Symbolic> Shr<Pattern<S>> for Pattern<S> {
+    type Output = Pattern<S>;
+    fn shr(self, rhs:Pattern<S>) -> Self::Output {
         match (self, rhs) {
             (Seq(mut lhs), Seq(rhs)    ) => {lhs.extend(rhs)   ; Seq(lhs)},
             (Seq(mut lhs), rhs         ) => {lhs.push(rhs)     ; Seq(lhs)},
@@ -165,9 +304,47 @@ impl Shr<Pattern> for Pattern {
         }
     }
 }
-gen_ref_versions!(Pattern,Shr,shr);
+gen_ref_versions!(Pattern<S>,Shr,shr,<S:Symbolic>);
+
+
+
+// ======================
+// === Counterexample ===
+// ======================
+
+/// An input witnessing that two patterns do not match the same inputs.
+///
+/// It is produced by [`Pattern::equivalent`] and [`Pattern::is_subset_of`].
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Counterexample<S=Symbol> {
+    /// The symbols making up the input.
+    pub symbols:Vec<S>,
+}
+
+impl Counterexample {
+    /// Get the input as a string, or `None` if it contains symbols that are not characters, such
+    /// as [`Symbol::EOF_CODE`].
+    pub fn as_string(&self) -> Option<String> {
+        self.symbols.iter().map(|symbol| std::char::from_u32(symbol.value)).collect()
+    }
+}
 
 
+// === Trait Impls ===
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        for symbol in &self.symbols {
+            match std::char::from_u32(symbol.value) {
+                Some(char) => write!(f,"{}",char.escape_debug())?,
+                None if *symbol == Symbol::EOF_CODE => write!(f,"<EOF>")?,
+                None => write!(f,"<{:#X}>",symbol.value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 
 // =================
 // === Utilities ===
@@ -192,3 +369,87 @@ macro_rules! l {
         Pattern::all_of($lit)
     }
 }
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn counterexample(result:Result<(),Counterexample>) -> String {
+        result.expect_err("The patterns should differ.").as_string().unwrap()
+    }
+
+    #[test]
+    fn test_equivalent_patterns() {
+        let digit = Pattern::range('0'..='9');
+        assert!(digit.many1().equivalent(&(digit.many() >> &digit)).is_ok());
+        assert!(l!("ab").many().equivalent(&(l!("ab") | Pattern::always()).many()).is_ok());
+        assert!((c!('a') | c!('b') | c!('c')).equivalent(&Pattern::range('a'..='c')).is_ok());
+    }
+
+    #[test]
+    fn test_not_equivalent_patterns() {
+        let digit = Pattern::range('0'..='9');
+        assert_eq!(counterexample(digit.many().equivalent(&digit.many1())),"");
+        let a_to_y = Pattern::range('a'..='y');
+        let a_to_z = Pattern::range('a'..='z');
+        assert_eq!(counterexample(a_to_y.equivalent(&a_to_z)),"z");
+        assert_eq!(counterexample(l!("abc").equivalent(&l!("abd"))),"abc");
+    }
+
+    #[test]
+    fn test_subset_patterns() {
+        let lower = Pattern::range('a'..='z');
+        let alpha = &lower | Pattern::range('A'..='Z');
+        assert!(lower.many1().is_subset_of(&alpha.many()).is_ok());
+        assert!(l!("ab").is_subset_of(&alpha.many()).is_ok());
+        assert_eq!(counterexample(alpha.many1().is_subset_of(&lower.many())),"A");
+        assert_eq!(counterexample(lower.many().is_subset_of(&lower.many1())),"");
+    }
+
+    #[test]
+    fn test_literals() {
+        let keywords    = ["if","in","import","then","type"];
+        let alternative = keywords.iter().map(|kw| Pattern::all_of(kw)).fold1(|l,r| l | r).unwrap();
+        let literals    = Pattern::literals(&keywords);
+        assert!(literals.equivalent(&alternative).is_ok());
+        assert!(Pattern::literals(&["","a"]).equivalent(&c!('a').opt()).is_ok());
+        assert!(Pattern::literals(&[]).is_subset_of(&Pattern::never()).is_ok());
+        assert_eq!(counterexample(literals.equivalent(&Pattern::literals(&["if","in"]))),"then");
+    }
+
+    #[test]
+    fn test_normalize_merges_ranges() {
+        let overlapping = Pattern::range('d'..='f') | Pattern::range('e'..='g');
+        let pattern     = Pattern::any_of("cabxy") | overlapping;
+        let merged      = vec![Pattern::range('a'..='g'),Pattern::range('x'..='y')];
+        assert_eq!(pattern.normalize(),Or(merged) | Pattern::never());
+        assert!(pattern.normalize().equivalent(&pattern).is_ok());
+        assert_eq!((c!('a') | c!('b')).many().normalize(),Pattern::range('a'..='b').many());
+    }
+
+    #[test]
+    fn test_normalize_gathers_literals() {
+        let pattern  = l!("==") | c!('=') | (l!(">=") | Pattern::literals(&["!=","=="]));
+        let expected = Or(vec![c!('='),Pattern::literals(&["!=","==",">="])]);
+        assert_eq!(pattern.normalize(),expected);
+        assert!(pattern.normalize().equivalent(&pattern).is_ok());
+        let pattern = l!("ab") | l!("a").many();
+        assert_eq!(pattern.normalize(),Or(vec![Pattern::literals(&["ab"]),c!('a').many()]));
+    }
+
+    #[test]
+    fn test_counterexample_display() {
+        let eof = Pattern::eof();
+        let err = eof.is_subset_of(&Pattern::never()).unwrap_err();
+        assert_eq!(err.as_string(),None);
+        assert_eq!(err.to_string(),"<EOF>");
+        let err = Pattern::char('\n').is_subset_of(&Pattern::never()).unwrap_err();
+        assert_eq!(err.to_string(),"\\n");
+    }
+}
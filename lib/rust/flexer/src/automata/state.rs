@@ -2,6 +2,7 @@
 
 use crate::automata::alphabet;
 use crate::automata::symbol::Symbol;
+use crate::automata::symbol::Symbolic;
 
 use crate::prelude::*;
 
@@ -13,22 +14,24 @@ use crate::prelude::*;
 
 /// A named state for a [`super::nfa::NFA`].
 #[derive(Clone,Debug,Default,PartialEq,Eq)]
-pub struct State {
+pub struct State<S=Symbol> {
     /// A set of transitions that can trigger without consuming a symbol (ε-transitions).
     pub epsilon_links:Vec<Identifier>,
     /// The set of transitions that trigger while consuming a specific symbol.
     ///
     /// When triggered, the automaton will transition to the [`Transition::target_state`].
-    pub links:Vec<Transition>,
+    pub links:Vec<Transition<S>>,
     /// The name of the state.
     ///
     /// This is used to auto-generate a call to the rust method of the same name.
     pub name:Option<String>,
     /// The function to call when evaluating the state.
-    pub callback:String
+    pub callback:String,
+    /// The condition under which the rule that ends in this state may match, if any.
+    pub guard:Option<String>,
 }
 
-impl State {
+impl<S:Symbolic> State<S> {
     /// Updater for field `name`. Returns updated state.
     pub fn named(mut self, name:&str) -> Self {
         self.name = Some(name.to_owned());
@@ -36,7 +39,7 @@ impl State {
     }
 
     /// Returns transition (next state) for each symbol in alphabet.
-    pub fn targets(&self, alphabet:&alphabet::Segmentation) -> Vec<Identifier> {
+    pub fn targets(&self, alphabet:&alphabet::Segmentation<S>) -> Vec<Identifier> {
         let mut targets = vec![];
         let mut index   = 0;
         let mut links   = self.links.clone();
@@ -58,7 +61,7 @@ impl State {
 
 // === Trait Impls ====
 
-impl From<Vec<usize>> for State {
+impl<S:Symbolic> From<Vec<usize>> for State<S> {
     /// Creates a state with epsilon links.
     fn from(vec:Vec<usize>) -> Self {
         let epsilon_links = vec.iter().cloned().map(|id| Identifier{id}).collect();
@@ -128,9 +131,9 @@ impl From<usize> for Identifier {
 
 /// A transition between states in a finite automaton that must consume a symbol to trigger.
 #[derive(Clone,Debug,PartialEq,Eq)]
-pub struct Transition {
+pub struct Transition<S=Symbol> {
     /// The range of symbols on which this transition will trigger.
-    pub symbols:RangeInclusive<Symbol>,
+    pub symbols:RangeInclusive<S>,
     /// The state that is entered after the transition has triggered.
     pub target_state:Identifier,
 }
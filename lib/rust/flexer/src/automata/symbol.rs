@@ -1,4 +1,47 @@
-//! Defines a Symbol that is operated on by the finite automata.
+//! Defines the symbols that are operated on by the finite automata.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+
+
+// ================
+// === Symbolic ===
+// ================
+
+/// A type of the symbols read by finite automata.
+///
+/// Transitions trigger on ranges of symbols, so the symbols have to be totally ordered, and it has
+/// to be possible to step from a symbol to the one directly after it. The [`Default`] symbol must
+/// be the least one.
+pub trait Symbolic : Copy + Debug + Default + Eq + Hash + Ord {
+    /// The symbol directly after `self`, or `None` if `self` is the greatest symbol.
+    fn next(self) -> Option<Self>;
+
+    /// Choose a symbol to stand for all of the symbols from `start` up to, but excluding, `end`,
+    /// or up to the greatest symbol if `end` is `None`.
+    ///
+    /// The automata treat all of these symbols identically, so by default it is `start`.
+    fn representative(start:Self, _end:Option<Self>) -> Self {
+        start
+    }
+}
+
+impl Symbolic for char {
+    fn next(self) -> Option<Self> {
+        match self {
+            '\u{D7FF}' => Some('\u{E000}'),
+            char       => std::char::from_u32(char as u32 + 1),
+        }
+    }
+}
+
+impl Symbolic for u8 {
+    fn next(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+}
+
 
 
 
@@ -33,6 +76,25 @@ impl Default for Symbol {
     }
 }
 
+impl Symbolic for Symbol {
+    fn next(self) -> Option<Self> {
+        self.value.checked_add(1).map(Symbol::from)
+    }
+
+    /// Symbols that are valid `char`s are preferred, so that inputs built from the representatives
+    /// can be displayed as text.
+    fn representative(start:Self, end:Option<Self>) -> Self {
+        let surrogates       = 0xD800..=0xDFFF;
+        let after_surrogates = surrogates.end() + 1;
+        let covers_after     = !matches!(end,Some(end) if end.value <= after_surrogates);
+        if surrogates.contains(&start.value) && covers_after {
+            Symbol::from(after_surrogates)
+        } else {
+            start
+        }
+    }
+}
+
 impl From<u32> for Symbol {
     fn from(value:u32) -> Symbol {
         Symbol{value}
@@ -0,0 +1,71 @@
+//! This module provides diagnostics, which the callbacks of a lexer report to explain problems with
+//! its input without stopping the lexer.
+
+use crate::prelude::*;
+
+use crate::span::Span;
+
+
+
+// ================
+// === Severity ===
+// ================
+
+/// How serious the problem described by a diagnostic is.
+#[derive(Clone,Copy,Debug,Eq,Hash,Ord,PartialEq,PartialOrd)]
+pub enum Severity {
+    /// The diagnostic is purely informative.
+    Info,
+    /// The input is valid, but probably not what was intended.
+    Warning,
+    /// The input is invalid.
+    Error,
+}
+
+
+// === Trait Impls ===
+
+impl Display for Severity {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info    => "info",
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        };
+        write!(f,"{}",name)
+    }
+}
+
+
+
+// ==================
+// === Diagnostic ===
+// ==================
+
+/// A problem with the input of a lexer, reported by one of its callbacks.
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity:Severity,
+    /// A description of the problem.
+    pub message:String,
+    /// The location of the problem in the input.
+    pub span:Span,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic for the text at `span`.
+    pub fn new(severity:Severity, message:impl Into<String>, span:Span) -> Diagnostic {
+        let message = message.into();
+        Diagnostic{severity,message,span}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for Diagnostic {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{} at {}: {}",self.severity,self.span,self.message)
+    }
+}
@@ -9,12 +9,16 @@ use crate::automata::dfa::DFA;
 use crate::automata::dfa::RuleExecutable;
 use crate::automata::state::Identifier;
 use crate::automata::state::State;
+use crate::data::matrix::Matrix;
 use crate::group::Group;
+use crate::group::rule::Rule;
 use crate::group;
+use crate::textmate;
 
 use enso_macro_utils::repr;
 use proc_macro2::Literal;
-use std::hash::BuildHasher;
+use proc_macro2::TokenStream;
+use std::collections::BTreeMap;
 use std::result::Result;
 use std::fmt;
 
@@ -36,17 +40,111 @@ pub fn specialize
 , state_type_name  : impl Str
 , output_type_name : impl Str
 ) -> Result<String,GenError> {
-    let group_registry = definition.groups();
-    let mut body_items = Vec::new();
-    body_items.push(run_function(output_type_name)?);
-    body_items.push(run_current_state_function());
-    body_items.push(step(group_registry));
+    let options  = CodegenOptions::default();
+    let (code,_) = specialize_with_report(definition,state_type_name,output_type_name,&options)?;
+    Ok(code)
+}
+
+/// Generate specialized code for the provided lexer `definition` according to `options`, like
+/// [`specialize`], reporting how much code was saved by generating identical functions only once.
+///
+/// Groups with identical rules share a single automaton, and the functions for the states of all
+/// automata, as well as those for the rules, are only generated once for every distinct body.
+///
+/// The generated code is the same for the same definition, byte for byte. It starts with a header
+/// containing the [hash of the definition](definition_hash), and puts every function on a line of
+/// its own, after comments relating it to the groups and rules that it implements.
+pub fn specialize_with_report
+( definition       : &impl flexer::State
+, state_type_name  : impl Str
+, output_type_name : impl Str
+, options          : &CodegenOptions
+) -> Result<(String,SizeReport),GenError> {
+    specialize_groups(definition.groups(),state_type_name,output_type_name,options)
+}
+
+/// Generate specialized code for a lexer with the groups in `group_registry`, like
+/// [`specialize_with_report`].
+///
+/// This allows generating code for lexers whose groups are not defined by a [`flexer::State`],
+/// such as those loaded from a [`crate::spec::Spec`].
+pub fn specialize_groups
+( group_registry   : &group::Registry
+, state_type_name  : impl Str
+, output_type_name : impl Str
+, options          : &CodegenOptions
+) -> Result<(String,SizeReport),GenError> {
+    let mut functions  = FunctionTable::default();
+    let mut automata   = Vec::<(Vec<&Rule>,Automaton)>::new();
+    let mut dispatches = Vec::with_capacity(group_registry.all().len());
+    let mut hooks      = Vec::new();
+    let type_names     = (state_type_name.as_ref(),output_type_name.as_ref());
+    let hash           = definition_hash(group_registry,type_names.0,type_names.1,options);
     for group in group_registry.all().iter() {
-        body_items.extend(automaton_for_group(group,group_registry)?)
+        let rules    = group_registry.rules_for(group.id);
+        let existing = automata.iter().find(|(other_rules,_)| same_code(other_rules,&rules));
+        let automaton = match existing {
+            Some((_,automaton)) => {
+                let comment = format!("Dispatches on the states of group `{}`.",group.name);
+                functions.add_comments(&automaton.dispatch,vec![comment]);
+                functions.report.size_before      += automaton.size;
+                functions.report.functions_before += automaton.functions;
+                automaton.clone()
+            }
+            None => {
+                let automaton = automaton_for_group(group,group_registry,&mut functions)?;
+                automata.push((rules,automaton.clone()));
+                automaton
+            }
+        };
+        dispatches.push(automaton.dispatch);
+        hooks.extend(hooks_for_group(group)?);
     }
+    let mut body_items = vec![
+        run_function(output_type_name)?,
+        run_current_state_function(options),
+        step(&dispatches),
+    ];
+    body_items.extend(state_stack_functions());
+    body_items.extend(vec![
+        run_eof_hooks_function(),
+        hook_dispatch(group_registry,HookKind::Enter)?,
+        hook_dispatch(group_registry,HookKind::Exit)?,
+        hook_dispatch(group_registry,HookKind::Eof)?,
+    ]);
+    body_items.extend(hooks);
+    let report = functions.report;
+    body_items.extend(functions.items.into_iter().map(ImplItem::Method));
     let result = wrap_in_impl_for(state_type_name,body_items)?;
-    let code   = show_code(&result);
-    Ok(code)
+    let header = format!("// This code is generated by the flexer. Do not edit it by hand.\n\
+        // The hash of the lexer definition is {:016x}.\n",hash);
+    let code   = header + &show_impl(&result);
+    Ok((code,report))
+}
+
+/// Checks whether the automata for groups with `rules` and `other_rules` are the same, which is the
+/// case if their rules [generate the same code](Rule::generates_same_code) in the same order.
+fn same_code(rules:&[&Rule], other_rules:&[&Rule]) -> bool {
+    let mut pairs = rules.iter().zip(other_rules);
+    rules.len() == other_rules.len() && pairs.all(|(rule,other)| rule.generates_same_code(other))
+}
+
+/// A hash of everything that the code generated for the lexer depends on.
+///
+/// It is stable across builds and platforms, so the header of the generated code only changes
+/// along with the definition.
+pub fn definition_hash
+( group_registry   : &group::Registry
+, state_type_name  : &str
+, output_type_name : &str
+, options          : &CodegenOptions
+) -> u64 {
+    let definition = format!("{}\n{}\n{:?}\n{:?}",state_type_name,output_type_name,options,
+        group_registry);
+    // The FNV-1a hash, as the hashers of the standard library may change between releases.
+    definition.bytes().fold(0xcbf2_9ce4_8422_2325,|hash,byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
 }
 
 
@@ -72,19 +170,15 @@ pub fn wrap_in_impl_for
 pub fn run_function(output_type_name:impl Str) -> Result<ImplItem,GenError> {
     let output_type_name = str_to_path(output_type_name)?;
     let tree:ImplItem    = parse_quote! {
-        pub fn run<R:LazyReader>(&mut self, mut reader:R) -> LexingResult<#output_type_name> {
+        pub fn run<R:LazyReader>
+        (&mut self, mut reader:R) -> LexingResult<<#output_type_name as TokenSink>::Output> {
             self.set_up();
             reader.advance_char(&mut self.bookmarks);
             while self.run_current_state(&mut reader) == StageStatus::ExitSuccess {}
-            let result = match self.status {
-                StageStatus::ExitFinished => LexingResult::success(
-                    mem::take(&mut self.output)
-                ),
-                StageStatus::ExitFail => LexingResult::failure(
-                    mem::take(&mut self.output)
-                ),
-                _ => LexingResult::partial(mem::take(&mut self.output))
-            };
+            if self.status == StageStatus::ExitFinished {
+                self.run_eof_hooks(&mut reader);
+            }
+            let result = self.lexing_result();
             self.tear_down();
             result
         }
@@ -93,7 +187,19 @@ pub fn run_function(output_type_name:impl Str) -> Result<ImplItem,GenError> {
 }
 
 /// Generate the function responsible for executing the lexer in its current state.
-pub fn run_current_state_function() -> ImplItem {
+///
+/// The generated code never panics. Errors from the reader instead stop the lexer with a
+/// [`crate::LexingError`]. It only logs its progress if `options` ask for a trace.
+pub fn run_current_state_function(options:&CodegenOptions) -> ImplItem {
+    let log_step = options.log(quote! {
+        self.logger.debug(||format!("Current character is {:?}.",reader.character().char));
+        self.logger.debug(||format!("Continuing in {:?}.",next_state));
+    });
+    let log_result = options.log(quote! {
+        self.logger.info(||format!("Result is {:?}.",reader.result()));
+    });
+    let log_finished = options.log(quote! {self.logger.info("Input finished.");});
+    let log_eof      = options.log(quote! {self.logger.info("Reached EOF.");});
     let tree:ImplItem = parse_quote! {
         fn run_current_state<R:LazyReader>(&mut self, reader:&mut R) -> StageStatus {
             self.status = StageStatus::Initial;
@@ -101,12 +207,11 @@ pub fn run_current_state_function() -> ImplItem {
 
             // Runs until reaching a state that no longer says to continue.
             while let Some(next_state) = self.status.continue_as() {
-                self.logger.debug(||format!("Current character is {:?}.",reader.character().char));
-                self.logger.debug(||format!("Continuing in {:?}.",next_state));
+                #log_step
                 self.status = self.step(next_state,reader);
 
-                if finished && reader.finished(self.bookmarks()) {
-                    self.logger.info("Input finished.");
+                if finished && reader.character().is_eof() && reader.finished(self.bookmarks()) {
+                    #log_finished
                     self.status = StageStatus::ExitFinished
                 }
                 finished = reader.character().is_eof();
@@ -115,21 +220,14 @@ pub fn run_current_state_function() -> ImplItem {
                     match reader.character().char {
                         Ok(char) => {
                             reader.append_result(char);
-                            self.logger.info(||format!("Result is {:?}.",reader.result()));
+                            #log_result
                         },
                         Err(flexer::prelude::reader::Error::EOF) => {
-                            self.logger.info("Reached EOF.");
+                            #log_eof
                         },
-                        Err(flexer::prelude::reader::Error::EndOfGroup) => {
-                            let current_state = self.current_state();
-                            let group_name    = self.groups().group(current_state).name.as_str();
-                            let err           = format!("Missing rules for state {}.", group_name);
-                            self.logger.error(err.as_str());
-                            panic!(err)
-                        }
-                        Err(_) => {
-                            self.logger.error("Unexpected error!");
-                            panic!("Unexpected error!")
+                        Err(error) => {
+                            self.status = self.fail(flexer::ErrorKind::Reader(error),reader);
+                            break
                         }
                     }
                     reader.advance_char(&mut self.bookmarks);
@@ -142,81 +240,271 @@ pub fn run_current_state_function() -> ImplItem {
     tree
 }
 
-/// Generate the `step` function for the lexer.
+/// Generate the `step` function for the lexer, where `dispatches` names the dispatch function for
+/// each of the groups.
 ///
 /// This function is responsible for dispatching based on the current state, consuming a character,
 /// and returning the state to transition to.
-pub fn step(groups:&group::Registry) -> ImplItem {
-    let arms = groups.all().iter().map(|g| step_match_arm(g.id.into())).collect_vec();
+pub fn step(dispatches:&[Ident]) -> ImplItem {
+    let arms = dispatches.iter().enumerate().map(|(ix,name)| step_match_arm(ix,name)).collect_vec();
     parse_quote! {
         fn step<R:LazyReader>(&mut self, next_state:SubStateId, reader:&mut R) -> StageStatus {
             let current_state:usize = self.current_state().into();
             match current_state {
                 #(#arms)*
-                _ => unreachable_panic!("Unreachable state reached in lexer."),
+                _ => self.fail(flexer::ErrorKind::UnknownGroup(current_state.into()),reader),
             }
         }
     }
 }
 
-/// Generate a match arm for the step function.
+/// Generate a match arm for the step function, calling the dispatch function `func_name`.
 ///
 /// There is one match arm per lexer state.
-pub fn step_match_arm(number:usize) -> Arm {
-    let literal           = Literal::usize_unsuffixed(number);
-    let function_name_str = format!("dispatch_in_state_{}",number);
-    let func_name:Ident   = parse_str(function_name_str.as_str()).unwrap();
+pub fn step_match_arm(number:usize, func_name:&Ident) -> Arm {
+    let literal = Literal::usize_unsuffixed(number);
     let arm:Arm = parse_quote! {
         #literal => self.#func_name(next_state,reader),
     };
     arm
 }
 
+/// Generate the functions that enter and leave the states of the lexer, running the hooks of the
+/// groups involved.
+///
+/// They shadow the functions of the same names on [`flexer::Flexer`], which only change the stack
+/// of states, so that the callbacks of the lexer run the hooks without having to ask for them. The
+/// enter hook of a group runs right after the group has been entered, and the exit hook right after
+/// it has been left. When several states are left at once, they are left from the innermost
+/// outwards, and the exit hook of each runs before the next one is left.
+pub fn state_stack_functions() -> Vec<ImplItem> {
+    vec![
+        parse_quote! {
+            pub fn push_state(&mut self, state:flexer::group::Identifier) {
+                (**self).push_state(state);
+                self.run_enter_hook(state.into());
+            }
+        },
+        parse_quote! {
+            pub fn pop_state(&mut self) -> Option<flexer::group::Identifier> {
+                let result = (**self).pop_state();
+                if let Some(state) = result {
+                    self.run_exit_hook(state.into());
+                }
+                result
+            }
+        },
+        parse_quote! {
+            pub fn pop_states_until
+            (&mut self, state:flexer::group::Identifier) -> flexer::group::Identifier {
+                while self.current_state() != state && self.current_state() != self.initial_state() {
+                    self.pop_state();
+                }
+                self.current_state()
+            }
+        },
+        parse_quote! {
+            pub fn pop_states_including
+            (&mut self, state:flexer::group::Identifier) -> flexer::group::Identifier {
+                self.pop_states_until(state);
+                self.pop_state();
+                self.current_state()
+            }
+        },
+    ]
+}
+
+/// Generate the function that executes the end of input hooks.
+///
+/// Once the input has ended, the lexer leaves all of its states, from the innermost outwards. The
+/// end of input hook of each group runs first, and the group is then left, along with any group
+/// that the hook has entered, unless the hook has left it already. Leaving the groups runs their
+/// exit hooks as usual. The end of input hook of the initial state runs last, and the lexer stays
+/// in that state.
+pub fn run_eof_hooks_function() -> ImplItem {
+    parse_quote! {
+        fn run_eof_hooks<R:LazyReader>(&mut self, reader:&mut R) {
+            loop {
+                let depth = self.state_stack.len();
+                self.run_eof_hook(self.current_state().into(),reader);
+                if depth == 1 {
+                    break
+                }
+                while self.state_stack.len() >= depth {
+                    self.pop_state();
+                }
+            }
+        }
+    }
+}
+
+/// Generate the function that dispatches to the hooks of the provided `kind` based on the group.
+///
+/// Only the end of input hooks are passed the reader, as the others run whenever the callbacks of
+/// the lexer enter or leave a state.
+pub fn hook_dispatch(groups:&group::Registry, kind:HookKind) -> Result<ImplItem,GenError> {
+    let dispatch_name:Ident = str_to_ident(format!("run_{}_hook",kind.name()))?;
+    let mut arms            = Vec::new();
+    for group in groups.all().iter().filter(|group| kind.code_for(group).is_some()) {
+        let literal         = Literal::usize_unsuffixed(group.id.into());
+        let hook_name:Ident = str_to_ident(group.hook_name(kind.hook_suffix()))?;
+        let arm:Arm         = match kind.takes_reader() {
+            true  => parse_quote! {#literal => self.#hook_name(reader),},
+            false => parse_quote! {#literal => self.#hook_name(),},
+        };
+        arms.push(arm);
+    }
+    let dispatch:ImplItem = match (kind.takes_reader(),arms.is_empty()) {
+        (true,true) => parse_quote! {
+            fn #dispatch_name<R:LazyReader>(&mut self, _group:usize, _reader:&mut R) {}
+        },
+        (true,false) => parse_quote! {
+            fn #dispatch_name<R:LazyReader>(&mut self, group:usize, reader:&mut R) {
+                match group {
+                    #(#arms)*
+                    _ => (),
+                }
+            }
+        },
+        (false,true) => parse_quote! {
+            fn #dispatch_name(&mut self, _group:usize) {}
+        },
+        (false,false) => parse_quote! {
+            fn #dispatch_name(&mut self, group:usize) {
+                match group {
+                    #(#arms)*
+                    _ => (),
+                }
+            }
+        },
+    };
+    Ok(dispatch)
+}
+
 
 // === Generation for a Specific Lexer State ===
 
-/// Generate the functions that implement the lexer automaton for a given lexer state.
+/// Generate the functions that implement the lexer automaton for a given lexer state, adding them
+/// to `functions`.
+///
+/// The rules of the automaton are renamed to the functions that implement them, so that rules with
+/// the same callback share a function.
 pub fn automaton_for_group
-( group    : &Group
-, registry : &group::Registry
-) -> Result<Vec<ImplItem>,GenError> {
-    let nfa       = registry.to_nfa_from(group.id);
-    let mut rules = Vec::with_capacity(nfa.states.len());
-    for state in nfa.states.iter() {
-        if state.name.is_some() {
-            rules.push(rule_for_state(state)?);
+( group     : &Group
+, registry  : &group::Registry
+, functions : &mut FunctionTable
+) -> Result<Automaton,GenError> {
+    let before       = functions.report;
+    let mut nfa      = registry.to_nfa_from(group.id);
+    let rules        = registry.rules_for(group.id);
+    let mut accepted = HashMap::<String,Vec<String>>::new();
+    for state in nfa.states.iter_mut() {
+        if let Some(name) = &state.name {
+            let rule_ix     = (0..rules.len()).find(|&ix| group.callback_name(ix) == *name);
+            let rule_ix     = rule_ix.expect("The state should be named after one of the rules.");
+            let description = describe_rule(group,rule_ix,rules[rule_ix]);
+            let mut rule    = rule_for_state(state)?;
+            add_comment(&mut rule,format!("Implements {}.",description));
+            let name = functions.add(rule).to_string();
+            accepted.entry(name.clone()).or_default().push(description);
+            state.name = Some(name);
+        }
+    }
+    let dfa          = FallbackDFA::from(&DFA::from(&nfa));
+    let transitions  = transitions_for_dfa(&dfa,group.id.into(),functions)?;
+    for (ix,transition) in transitions.iter().enumerate() {
+        let comments = describe_state(&dfa,ix,group,&accepted);
+        functions.add_comments(transition,comments);
+    }
+    let mut dispatch = dispatch_in_state(&dfa.dfa,group.id.into(),&transitions)?;
+    add_comment(&mut dispatch,format!("Dispatches on the states of group `{}`.",group.name));
+    let dispatch     = functions.add(dispatch);
+    let size         = functions.report.size_before - before.size_before;
+    let count        = functions.report.functions_before - before.functions_before;
+    Ok(Automaton{dispatch,size,functions:count})
+}
+
+/// Generate the functions that implement the hooks for a given lexer state.
+pub fn hooks_for_group(group:&Group) -> Result<Vec<ImplItem>,GenError> {
+    let mut hooks = Vec::new();
+    for kind in &[HookKind::Enter,HookKind::Exit,HookKind::Eof] {
+        if let Some(code) = kind.code_for(group) {
+            let name     = group.hook_name(kind.hook_suffix());
+            let mut hook = match kind.takes_reader() {
+                true  => callback_function(&name,code)?,
+                false => hook_function(&name,code)?,
+            };
+            let comment  = format!("The `{}` hook of group `{}`: `{}`.",kind.hook_suffix(),
+                group.name,code);
+            add_comment(&mut hook,comment);
+            hooks.push(ImplItem::Method(hook));
+        }
+    }
+    Ok(hooks)
+}
+
+/// Describe the rule with index `rule_ix` among the rules of `group`, for the comments on the
+/// generated code.
+pub fn describe_rule(group:&Group, rule_ix:usize, rule:&Rule) -> String {
+    let regex = match textmate::regex(&rule.pattern) {
+        regex if regex.is_empty() => "(?:)".into(),
+        regex                     => regex,
+    };
+    let guard = rule.guard.as_ref().map(|guard| format!(" if `{}`",guard)).unwrap_or_default();
+    format!("rule {} of group `{}`: `{}`{} => `{}`",rule_ix,group.name,regex,guard,rule.callback)
+}
+
+/// Describe the state `state_ix` of the automaton for `group`, where `accepted` describes the
+/// rules implemented by each rule function.
+fn describe_state
+( dfa      : &FallbackDFA
+, state_ix : usize
+, group    : &Group
+, accepted : &HashMap<String,Vec<String>>
+) -> Vec<String> {
+    let state        = format!("State {} of the automaton for group `{}`.",state_ix,group.name);
+    let mut comments = vec![state];
+    let mut describe = |rule:Option<&RuleExecutable>, verb:&str| {
+        let mut rule = rule;
+        while let Some(rule_exec) = rule {
+            let descriptions = accepted.get(&rule_exec.code).into_iter().flatten();
+            comments.extend(descriptions.map(|description| format!("{} {}.",verb,description)));
+            rule = rule_exec.alternative.as_deref();
         }
+    };
+    describe(dfa.dfa.callbacks[state_ix].as_ref(),"Accepts");
+    for fallback in &dfa.fallbacks[state_ix] {
+        describe(Some(fallback),"Falls back to");
     }
-    let mut dfa             = DFA::from(&nfa);
-    let dispatch_for_dfa    = dispatch_in_state(&dfa,group.id.into())?;
-    let mut dfa_transitions = transitions_for_dfa(&mut dfa,group.id.into())?;
-    dfa_transitions.push(dispatch_for_dfa);
-    dfa_transitions.extend(rules);
-    Ok(dfa_transitions)
+    comments
 }
 
-/// Generate a set of transition functions for the provided `dfa`, with identifier `id`.
-pub fn transitions_for_dfa(dfa:&mut DFA, id:usize) -> Result<Vec<ImplItem>,GenError> {
-    let mut state_has_overlapping_rules:HashMap<usize,bool> = HashMap::new();
-    state_has_overlapping_rules.insert(0,false);
-    let state_names:Vec<_> = dfa.links.row_indices().map(|ix| (ix, name_for_step(id, ix))).collect();
-    let mut transitions    = Vec::with_capacity(state_names.len());
+/// Generate a set of transition functions for the provided `dfa`, with identifier `id`, adding
+/// them to `functions`.
+///
+/// Returns the names of the functions for each of the states of `dfa`.
+pub fn transitions_for_dfa
+( dfa       : &FallbackDFA
+, id        : usize
+, functions : &mut FunctionTable
+) -> Result<Vec<Ident>,GenError> {
+    let state_names     = dfa.dfa.links.row_indices().map(|ix| (ix,name_for_step(id,ix)));
+    let state_names     = state_names.collect_vec();
+    let mut transitions = Vec::with_capacity(state_names.len());
     for (ix,name) in state_names.into_iter() {
-        transitions.push(transition_for_dfa(dfa,name,ix,&mut state_has_overlapping_rules)?)
+        transitions.push(functions.add(transition_for_dfa(dfa,name,ix)?))
     }
     Ok(transitions)
 }
 
-/// Generate a specific transition function for
-#[allow(clippy::implicit_hasher)]
-pub fn transition_for_dfa<S:BuildHasher>
-( dfa             : &mut DFA
+/// Generate a specific transition function for the state `state_ix` of the `dfa`.
+pub fn transition_for_dfa
+( dfa             : &FallbackDFA
 , transition_name : Ident
 , state_ix        : usize
-, has_overlaps    : &mut HashMap<usize,bool,S>
-) -> Result<ImplItem,GenError> {
-    let match_expr:Expr   = match_for_transition(dfa,state_ix,has_overlaps)?;
-    let function:ImplItem = parse_quote! {
+) -> Result<ImplItemMethod,GenError> {
+    let match_expr:Expr         = match_for_transition(dfa,state_ix)?;
+    let function:ImplItemMethod = parse_quote! {
         fn #transition_name<R:LazyReader>(&mut self, reader:&mut R) -> StageStatus {
             #match_expr
         }
@@ -225,33 +513,27 @@ pub fn transition_for_dfa<S:BuildHasher>
 }
 
 /// Generate the pattern match for a given transition function.
-pub fn match_for_transition<S:BuildHasher>
-( dfa          : &mut DFA
-, state_ix     : usize
-, has_overlaps : &mut HashMap<usize,bool,S>
-) -> Result<Expr,GenError> {
-    let overlaps          = *has_overlaps.get(&state_ix).unwrap_or(&false);
-    let state             = dfa.callbacks.get(state_ix).expect("Internal error.").clone();
-    let mut trigger_state = dfa.links[(state_ix,0)];
+pub fn match_for_transition(dfa:&FallbackDFA, state_ix:usize) -> Result<Expr,GenError> {
+    let links             = &dfa.dfa.links;
+    let mut trigger_state = links[(state_ix,0)];
     let mut range_start   = u32::min_value();
-    let divisions:Vec<_>  = dfa.alphabet_segmentation.divisions_as_vec();
+    let divisions:Vec<_>  = dfa.dfa.alphabet_segmentation.divisions_as_vec();
     let mut branches      = Vec::with_capacity(divisions.len());
     for division in divisions.into_iter() {
         let ix                = division.position;
         let sym               = division.symbol;
-        let new_trigger_state = dfa.links[(state_ix,ix)];
+        let new_trigger_state = links[(state_ix,ix)];
         if new_trigger_state != trigger_state {
             let range_end             = if sym.value != 0 { sym.value - 1 } else { sym.value };
             let current_trigger_state = trigger_state;
             let current_range_start   = range_start;
             trigger_state             = new_trigger_state;
             range_start               = sym.value;
-            let body =
-                branch_body(dfa,current_trigger_state,&state,has_overlaps,overlaps)?;
+            let body = branch_body(dfa,state_ix,current_trigger_state)?;
             branches.push(Branch::new(Some(current_range_start..=range_end),body))
         } else {}
     }
-    let catch_all_branch_body = branch_body(dfa,trigger_state,&state,has_overlaps,overlaps)?;
+    let catch_all_branch_body = branch_body(dfa,state_ix,trigger_state)?;
     let catch_all_branch      = Branch::new(None,catch_all_branch_body);
     branches.push(catch_all_branch);
     let arms:Vec<Arm> = branches.into_iter().map(Into::into).collect();
@@ -264,85 +546,89 @@ pub fn match_for_transition<S:BuildHasher>
     Ok(Expr::Match(match_expr))
 }
 
-/// Generate the branch body for a transition in the DFA.
-pub fn branch_body<S:BuildHasher>
-( dfa           : &mut DFA
-, target_state  : Identifier
-, maybe_state   : &Option<RuleExecutable>
-, has_overlaps  : &mut HashMap<usize,bool,S>
-, rules_overlap : bool
+/// Generate the branch body for the transition from `source_state` to `target_state` in the DFA.
+///
+/// When the transition leaves a state that has a rule of its own for one that may fall back to it,
+/// the position is recorded in the bookmark for the match of that rule, so that the lexer can
+/// rewind to it and execute the fallback rule if it fails to match anything longer.
+pub fn branch_body
+( dfa          : &FallbackDFA
+, source_state : usize
+, target_state : Identifier
 ) -> Result<Block,GenError> {
+    let own_rule = &dfa.dfa.callbacks[source_state];
     if target_state == Identifier::INVALID {
-        match maybe_state {
-            None => {
-                Ok(parse_quote! {{
-                    StageStatus::ExitFail
-                }})
-            },
-            Some(rule_exec) => {
-                let rule:Expr = match parse_str(rule_exec.code.as_str()) {
-                    Ok(rule) => rule,
-                    Err(_)   => return Err(GenError::BadExpression(rule_exec.code.clone()))
-                };
-                if rules_overlap {
-                    Ok(parse_quote! {{
-                        let rule_bookmark    = self.bookmarks.rule_bookmark;
-                        let matched_bookmark = self.bookmarks.matched_bookmark;
-                        self.bookmarks.rewind(rule_bookmark,reader);
-                        self.current_match = reader.pop_result();
-                        self.#rule(reader);
-                        self.bookmarks.bookmark(matched_bookmark,reader);
-                        StageStatus::ExitSuccess
-                    }})
-                } else {
-                    Ok(parse_quote! {{
-                        let matched_bookmark = self.bookmarks.matched_bookmark;
-                        self.current_match   = reader.pop_result();
-                        self.#rule(reader);
-                        self.bookmarks.bookmark(matched_bookmark,reader);
-                        StageStatus::ExitSuccess
-                    }})
-                }
-            }
+        let mut fallback:Expr = parse_quote! { StageStatus::ExitFail };
+        for rule_exec in dfa.fallbacks[source_state].iter().rev() {
+            fallback = select_rule(rule_exec,&dfa.rewind_to_match(rule_exec),fallback)?;
         }
+        let body = match own_rule {
+            Some(rule_exec) => select_rule(rule_exec,&[],fallback)?,
+            None            => fallback,
+        };
+        Ok(parse_quote! {{
+            #body
+        }})
     } else {
-        let target_state_has_no_rule = match maybe_state {
-            Some(state) => if !dfa.has_rule_for(target_state) {
-                dfa.callbacks[target_state.id] = Some(state.clone());
-                has_overlaps.insert(target_state.id,true);
-                true
-            } else {
-                false
-            },
-            None => false
+        let fallbacks = &dfa.fallbacks[target_state.id];
+        let mark      = match own_rule {
+            Some(rule_exec) if fallbacks.contains(rule_exec) => dfa.mark_match(rule_exec),
+            _                                                => vec![],
         };
-
-        let state_id = Literal::usize_unsuffixed(target_state.id);
-        let ret:Expr = parse_quote! {
+        let state_id  = Literal::usize_unsuffixed(target_state.id);
+        Ok(parse_quote! {{
+            #(#mark)*
             StageStatus::ContinueWith(#state_id.into())
-        };
+        }})
+    }
+}
 
-        if target_state_has_no_rule && !rules_overlap {
-            Ok(parse_quote! {{
-                let rule_bookmark = self.bookmarks.rule_bookmark;
-                self.bookmarks.bookmark(rule_bookmark,reader);
-                #ret
-            }})
-        } else {
-            Ok(parse_quote! {{
-                #ret
-            }})
+/// Generate the code that executes the first rule in the chain starting at `rule_exec` whose
+/// guard holds, evaluating `otherwise` if the guards of all of them reject the match.
+///
+/// The statements in `rewind` are executed before the rule, to return the reader to the end of the
+/// match of the rule.
+pub fn select_rule
+(rule_exec:&RuleExecutable, rewind:&[Stmt], otherwise:Expr) -> Result<Expr,GenError> {
+    let rule:Expr = match parse_str(rule_exec.code.as_str()) {
+        Ok(rule) => rule,
+        Err(_)   => return Err(GenError::BadExpression(rule_exec.code.clone()))
+    };
+    let run:Expr = parse_quote! {{
+        #(#rewind)*
+        let matched_bookmark = self.bookmarks.matched_bookmark;
+        self.take_match(reader);
+        self.#rule(reader);
+        self.bookmarks.bookmark(matched_bookmark,reader);
+        StageStatus::ExitSuccess
+    }};
+    match &rule_exec.guard {
+        None        => Ok(run),
+        Some(guard) => {
+            let guard:Expr = match parse_str(guard.as_str()) {
+                Ok(guard) => guard,
+                Err(_)    => return Err(GenError::BadExpression(guard.clone()))
+            };
+            let otherwise = match &rule_exec.alternative {
+                Some(alternative) => select_rule(alternative,rewind,otherwise)?,
+                None              => otherwise,
+            };
+            Ok(parse_quote! {
+                if #guard #run else { #otherwise }
+            })
         }
     }
 }
 
-/// Generate the dispatch function for a given lexer state.
+/// Generate the dispatch function for a given lexer state, where `transitions` names the function
+/// for each state of the `dfa`.
 ///
 /// This dispatch function is responsible for dispatching based on the sub-state of any given lexer
 /// state, and is the main part of implementing the actual lexer transitions.
-pub fn dispatch_in_state(dfa:&DFA, id:usize) -> Result<ImplItem,GenError> {
+pub fn dispatch_in_state
+(dfa:&DFA, id:usize, transitions:&[Ident]) -> Result<ImplItemMethod,GenError> {
     let dispatch_name:Ident = str_to_ident(format!("dispatch_in_state_{}",id))?;
-    let state_names  = dfa.links.row_indices().map(|ix| (ix, name_for_step(id,ix))).collect_vec();
+    let state_names  = dfa.links.row_indices().zip(transitions).collect_vec();
     let mut branches = Vec::with_capacity(state_names.len());
     for (ix,name) in state_names.into_iter() {
         let literal = Literal::usize_unsuffixed(ix);
@@ -355,10 +641,10 @@ pub fn dispatch_in_state(dfa:&DFA, id:usize) -> Result<ImplItem,GenError> {
     let pattern_match:ExprMatch = parse_quote! {
         match new_state_index.into() {
             #(#branches)*
-            _ => unreachable_panic!("Unreachable state reached in lexer.")
+            _ => self.fail(flexer::ErrorKind::UnknownState(new_state_index),reader)
         }
     };
-    let func:ImplItem = parse_quote! {
+    let func:ImplItemMethod = parse_quote! {
         fn #dispatch_name<R:LazyReader>
         ( &mut self
         , new_state_index:SubStateId
@@ -378,27 +664,51 @@ pub fn name_for_step(in_state:usize, to_state:usize) -> Ident {
 }
 
 /// Generate an executable rule function for a given lexer state.
-pub fn rule_for_state(state:&State) -> Result<ImplItem,GenError> {
+pub fn rule_for_state(state:&State) -> Result<ImplItemMethod,GenError> {
     match &state.name {
-        None => unreachable_panic!("Rule for state requested, but state has none."),
-        Some(name) => {
-            let rule_name = str_to_ident(name)?;
-            let code:Expr = match parse_str(state.callback.as_str()) {
-                Ok(expr) => expr,
-                Err(_)   => return Err(GenError::BadExpression(state.callback.clone()))
-            };
-            if !has_reader_arg(&code) {
-                return Err(GenError::BadCallbackArgument)
-            }
+        None       => unreachable_panic!("Rule for state requested, but state has none."),
+        Some(name) => callback_function(name,&state.callback),
+    }
+}
 
-            let tree:ImplItem = parse_quote! {
-                fn #rule_name<R:LazyReader>(&mut self, reader:&mut R) {
-                    #code
-                }
-            };
-            Ok(tree)
+/// Generate a function named `name` that executes the callback `code`.
+pub fn callback_function(name:&str, code:&str) -> Result<ImplItemMethod,GenError> {
+    let function_name = str_to_ident(name)?;
+    let code:Expr     = match parse_str(code) {
+        Ok(expr) => expr,
+        Err(_)   => return Err(GenError::BadExpression(code.into()))
+    };
+    if !has_reader_arg(&code) {
+        return Err(GenError::BadCallbackArgument)
+    }
+
+    let tree:ImplItemMethod = parse_quote! {
+        fn #function_name<R:LazyReader>(&mut self, reader:&mut R) {
+            #code
         }
+    };
+    Ok(tree)
+}
+
+/// Generate a function named `name` that executes the code of an enter or exit hook.
+///
+/// Unlike callbacks, these hooks are not passed the reader.
+pub fn hook_function(name:&str, code:&str) -> Result<ImplItemMethod,GenError> {
+    let function_name = str_to_ident(name)?;
+    let code:Expr     = match parse_str(code) {
+        Ok(expr) => expr,
+        Err(_)   => return Err(GenError::BadExpression(code.into()))
+    };
+    if has_reader_arg(&code) {
+        return Err(GenError::BadHookArgument)
     }
+
+    let tree:ImplItemMethod = parse_quote! {
+        fn #function_name(&mut self) {
+            #code
+        }
+    };
+    Ok(tree)
 }
 
 /// Checks if the given `expr` is a  call with a single argument "reader" being passed.
@@ -433,6 +743,330 @@ pub fn has_reader_arg(expr:&Expr) -> bool {
 
 
 
+// ================
+// === HookKind ===
+// ================
+
+/// The kinds of hook that a group can define.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum HookKind {
+    /// Executed when the lexer enters the group.
+    Enter,
+    /// Executed when the lexer leaves the group.
+    Exit,
+    /// Executed when the input ends while the group is on the stack.
+    Eof,
+}
+
+impl HookKind {
+    /// The name of this kind of hook, as used in the generated code.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookKind::Enter => "enter",
+            HookKind::Exit  => "exit",
+            HookKind::Eof   => "eof",
+        }
+    }
+
+    /// The suffix of the name of the generated function for this kind of hook.
+    pub fn hook_suffix(self) -> &'static str {
+        match self {
+            HookKind::Enter => "on_enter",
+            HookKind::Exit  => "on_exit",
+            HookKind::Eof   => "on_eof",
+        }
+    }
+
+    /// Whether this kind of hook is passed the reader.
+    pub fn takes_reader(self) -> bool {
+        self == HookKind::Eof
+    }
+
+    /// The code for this kind of hook in `group`, if the group defines it.
+    pub fn code_for(self, group:&Group) -> Option<&str> {
+        let code = match self {
+            HookKind::Enter => &group.on_enter,
+            HookKind::Exit  => &group.on_exit,
+            HookKind::Eof   => &group.on_eof,
+        };
+        code.as_deref()
+    }
+}
+
+
+
+// ===================
+// === FallbackDFA ===
+// ===================
+
+/// A DFA in which every state knows the rules to execute when the lexer cannot continue from it.
+///
+/// A state without a rule of its own falls back to the rule of the last state with a rule that the
+/// lexer passed through. As this depends on the path taken through the DFA, any such state that
+/// can be reached with different fallback rules is split into one copy per fallback rule.
+///
+/// A state whose rules are all guarded falls back in the same way when every guard rejects the
+/// match. As the guards of the fallback rule may reject the shorter match as well, each state has
+/// a chain of fallback rules, with one rule for every shorter match up to the last one that is
+/// certain to run. A rule only occurs in the chain once, for its longest match, as its guard holds
+/// for all of its matches or for none of them.
+#[derive(Clone,Debug)]
+pub struct FallbackDFA {
+    /// The automaton, with the rules of its states unchanged.
+    pub dfa:DFA,
+    /// The fallback rules for each state of `dfa`, from the longest match to the shortest.
+    pub fallbacks:Vec<Vec<RuleExecutable>>,
+    /// The guarded rules among the fallbacks, indexed by the fallback bookmark for their match.
+    pub guarded_fallbacks:Vec<RuleExecutable>,
+}
+
+impl FallbackDFA {
+    /// Generate the code that marks the end of the match for the fallback rule `rule_exec`.
+    ///
+    /// Only one fallback rule is certain to run, so the unguarded rules share the rule bookmark,
+    /// while each guarded rule has a fallback bookmark of its own.
+    pub fn mark_match(&self, rule_exec:&RuleExecutable) -> Vec<Stmt> {
+        let block:Block = match self.fallback_slot(rule_exec) {
+            None => parse_quote! {{
+                let rule_bookmark = self.bookmarks.rule_bookmark;
+                self.bookmarks.bookmark(rule_bookmark,reader);
+            }},
+            Some(slot) => parse_quote! {{
+                self.mark_fallback(#slot,reader);
+            }},
+        };
+        block.stmts
+    }
+
+    /// Generate the code that rewinds the reader to the end of the match for the fallback rule
+    /// `rule_exec`.
+    pub fn rewind_to_match(&self, rule_exec:&RuleExecutable) -> Vec<Stmt> {
+        let block:Block = match self.fallback_slot(rule_exec) {
+            None => parse_quote! {{
+                let rule_bookmark = self.bookmarks.rule_bookmark;
+                self.bookmarks.rewind(rule_bookmark,reader);
+            }},
+            Some(slot) => parse_quote! {{
+                self.rewind_to_fallback(#slot,reader);
+            }},
+        };
+        block.stmts
+    }
+
+    /// The fallback bookmark for the match of `rule_exec`, if it is a guarded rule.
+    fn fallback_slot(&self, rule_exec:&RuleExecutable) -> Option<Literal> {
+        let slot = self.guarded_fallbacks.iter().position(|rule| rule == rule_exec)?;
+        Some(Literal::usize_unsuffixed(slot))
+    }
+}
+
+
+// === Trait Impls ===
+
+impl From<&DFA> for FallbackDFA {
+    fn from(dfa:&DFA) -> Self {
+        let columns      = dfa.alphabet_segmentation.divisions.len();
+        let rule         = |state:usize| dfa.callbacks[state].clone();
+        let without      = |chain:&[RuleExecutable], rule:&RuleExecutable| {
+            chain.iter().filter(|other| *other != rule).cloned().collect_vec()
+        };
+        let mut states   = vec![(0,vec![])];
+        let mut ids      = HashMap::new();
+        let mut links    = Matrix::new(0,columns);
+        let mut state_ix = 0;
+        ids.insert(states[0].clone(),0);
+        while state_ix < states.len() {
+            links.new_row();
+            let (state,fallbacks) = states[state_ix].clone();
+            let inherited         = match rule(state) {
+                Some(rule) if rule.is_unconditional() => vec![rule],
+                Some(rule) => iter::once(rule.clone()).chain(without(&fallbacks,&rule)).collect(),
+                None       => fallbacks,
+            };
+            for column in 0..columns {
+                let target = dfa.links[(state,column)];
+                if target != Identifier::INVALID {
+                    let target_fallbacks = match rule(target.id) {
+                        Some(rule) if rule.is_unconditional() => vec![],
+                        Some(rule)                            => without(&inherited,&rule),
+                        None                                  => inherited.clone(),
+                    };
+                    let target_key = (target.id,target_fallbacks);
+                    let target_ix  = *ids.entry(target_key.clone()).or_insert_with(|| {
+                        states.push(target_key);
+                        states.len() - 1
+                    });
+                    links[(state_ix,column)] = Identifier::new(target_ix);
+                }
+            }
+            state_ix += 1;
+        }
+        let alphabet_segmentation = dfa.alphabet_segmentation.clone();
+        let callbacks             = states.iter().map(|(state,_)| rule(*state)).collect();
+        let fallbacks:Vec<_>      = states.into_iter().map(|(_,fallbacks)| fallbacks).collect();
+        let guarded               = fallbacks.iter().flatten().filter(|rule| rule.guard.is_some());
+        let guarded_fallbacks     = guarded.cloned().unique().collect();
+        let dfa                   = DFA{alphabet_segmentation,links,callbacks};
+        FallbackDFA{dfa,fallbacks,guarded_fallbacks}
+    }
+}
+
+
+
+// ======================
+// === CodegenOptions ===
+// ======================
+
+/// Options that control the code generated for a lexer.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct CodegenOptions {
+    /// Whether the generated code logs its progress through the input.
+    ///
+    /// The logging calls cost time for every character, even with a disabled logger, so they are
+    /// left out unless the `trace` feature of the flexer is enabled.
+    pub trace:bool,
+}
+
+impl CodegenOptions {
+    /// The options for generating code that logs its progress, for debugging lexer definitions.
+    pub fn trace() -> CodegenOptions {
+        CodegenOptions{trace:true}
+    }
+
+    /// The options for generating code that does not log anything.
+    pub fn release() -> CodegenOptions {
+        CodegenOptions{trace:false}
+    }
+
+    /// Generate the logging `statements` if the code should log its progress, and nothing
+    /// otherwise.
+    pub fn log(&self, statements:TokenStream) -> TokenStream {
+        if self.trace { statements } else { TokenStream::new() }
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        if cfg!(feature="trace") { CodegenOptions::trace() } else { CodegenOptions::release() }
+    }
+}
+
+
+
+// =====================
+// === FunctionTable ===
+// =====================
+
+/// The functions generated for the automata of a lexer, each of which is only generated once for
+/// every distinct signature and body.
+#[derive(Clone,Debug,Default)]
+pub struct FunctionTable {
+    /// The distinct functions, in the order in which they were added.
+    items:Vec<ImplItemMethod>,
+    /// The index in `items` of the function for each distinct signature and body.
+    ///
+    /// It is only used for lookups, but is ordered to keep the generated code independent of the
+    /// order of iteration over hashed collections.
+    names:BTreeMap<String,usize>,
+    /// The size of the functions added to the table.
+    report:SizeReport,
+}
+
+impl FunctionTable {
+    /// Add `function` to the table, returning the name under which it can be called.
+    ///
+    /// If a function that only differs from `function` in its name and comments has already been
+    /// added, the name of that function is returned instead, and the comments of `function` are
+    /// added to it.
+    pub fn add(&mut self, function:ImplItemMethod) -> Ident {
+        let mut anonymous   = function.clone();
+        anonymous.sig.ident = Ident::new("function",anonymous.sig.ident.span());
+        take_comments(&mut anonymous);
+        let key             = show_code(&anonymous);
+        self.report.functions_before += 1;
+        self.report.size_before      += key.len();
+        match self.names.get(&key) {
+            Some(&ix) => {
+                let name = self.items[ix].sig.ident.clone();
+                self.add_comments(&name,take_comments(&mut function.clone()));
+                name
+            }
+            None => {
+                let name = function.sig.ident.clone();
+                self.report.functions_after += 1;
+                self.report.size_after      += key.len();
+                self.names.insert(key,self.items.len());
+                self.items.push(function);
+                name
+            }
+        }
+    }
+
+    /// Add the `comments` that are not there yet to the function called `name`.
+    pub fn add_comments(&mut self, name:&Ident, comments:Vec<String>) {
+        if let Some(function) = self.items.iter_mut().find(|item| item.sig.ident == *name) {
+            let existing = take_comments(function);
+            let new      = comments.into_iter().filter(|comment| !existing.contains(comment));
+            for comment in existing.iter().cloned().chain(new.collect_vec()) {
+                add_comment(function,comment);
+            }
+        }
+    }
+}
+
+
+// === Automaton ===
+
+/// The automaton generated for a group.
+#[derive(Clone,Debug)]
+pub struct Automaton {
+    /// The name of the function that dispatches on the states of the automaton.
+    pub dispatch:Ident,
+    /// The size of the functions that implement the automaton, including those shared with other
+    /// automata.
+    pub size:usize,
+    /// The number of functions that implement the automaton, including those shared with other
+    /// automata.
+    pub functions:usize,
+}
+
+
+
+// ==================
+// === SizeReport ===
+// ==================
+
+/// The size of the functions generated for the automata of a lexer, before and after generating
+/// identical functions only once.
+///
+/// Sizes are measured in bytes of unformatted code.
+#[derive(Clone,Copy,Debug,Default,Eq,PartialEq)]
+pub struct SizeReport {
+    /// The number of functions that would be generated without any sharing.
+    pub functions_before:usize,
+    /// The number of functions that are actually generated.
+    pub functions_after:usize,
+    /// The size of the functions that would be generated without any sharing.
+    pub size_before:usize,
+    /// The size of the functions that are actually generated.
+    pub size_after:usize,
+}
+
+
+// === Trait Impls ===
+
+impl Display for SizeReport {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{} functions ({} bytes) reduced to {} functions ({} bytes).",
+            self.functions_before,self.size_before,self.functions_after,self.size_after)
+    }
+}
+
+
+
 // ================
 // === GenError ===
 // ================
@@ -442,6 +1076,8 @@ pub fn has_reader_arg(expr:&Expr) -> bool {
 pub enum GenError {
     /// The callback function does not take a single argument `reader`.
     BadCallbackArgument,
+    /// The enter or exit hook takes the argument `reader`.
+    BadHookArgument,
     /// The provided string is not a valid rust identifier.
     BadIdentifier(String),
     /// The provided expression isn't a valid rust expression.
@@ -461,6 +1097,9 @@ impl Display for GenError {
             GenError::BadCallbackArgument => write!(f,
                 "Bad argument to a callback function. It must take a single argument `reader`."
             ),
+            GenError::BadHookArgument => write!(f,
+                "Bad argument to an enter or exit hook. It must not take the argument `reader`."
+            ),
             GenError::BadIdentifier(str) => write!(f,"`{}` is not a valid rust identifier.",str),
             GenError::BadExpression(str) => write!(f,"`{}` is not a valid rust expression.",str),
             GenError::BadLiteral(str)    => write!(f,"`{}` is not a valid rust literal.",str),
@@ -538,4 +1177,176 @@ pub fn show_code(tokens:&impl ToTokens) -> String {
     repr(tokens)
 }
 
+/// Convert the `impl` block into a string, with each of its items on a line of its own.
+///
+/// The doc comments added to the items with [`add_comment`] are shown as line comments before
+/// them.
+pub fn show_impl(tree:&ItemImpl) -> String {
+    let attributes = tree.attrs.iter().map(show_code).join(" ");
+    let mut code   = format!("{}\nimpl {} {{\n",attributes,show_code(&tree.self_ty));
+    for item in &tree.items {
+        let mut item = item.clone();
+        if let ImplItem::Method(method) = &mut item {
+            for comment in take_comments(method) {
+                code.push_str(&format!("    // {}\n",comment));
+            }
+        }
+        code.push_str(&format!("    {}\n",show_code(&item)));
+    }
+    code.push_str("}\n");
+    code
+}
+
+/// Add the `comment` to the generated `function`, as a doc comment.
+pub fn add_comment(function:&mut ImplItemMethod, comment:impl Into<String>) {
+    let comment = comment.into();
+    function.attrs.push(parse_quote!(#[doc=#comment]));
+}
+
+/// Remove the doc comments from the generated `function`, returning their lines.
+fn take_comments(function:&mut ImplItemMethod) -> Vec<String> {
+    let (docs,attrs):(Vec<_>,Vec<_>) = function.attrs.drain(..).partition(|attribute| {
+        attribute.path.is_ident("doc")
+    });
+    function.attrs = attrs;
+    let docs = docs.into_iter().filter_map(|attribute| match attribute.parse_meta() {
+        Ok(Meta::NameValue(MetaNameValue{lit:Lit::Str(doc),..})) => Some(doc.value()),
+        _                                                       => None,
+    });
+    docs.flat_map(|doc| doc.lines().map(String::from).collect_vec()).collect()
+}
+
+
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use crate::automata::pattern::Pattern;
+
+    #[test]
+    fn test_logging_is_optional() {
+        let release = show_code(&run_current_state_function(&CodegenOptions::release()));
+        let trace   = show_code(&run_current_state_function(&CodegenOptions::trace()));
+        assert!(!release.contains("logger"));
+        assert!(trace.contains("logger"));
+    }
 
+    /// A registry with two groups that have the same rules, and a third group that inherits them.
+    fn registry() -> group::Registry {
+        let mut registry = group::Registry::default();
+        let first        = registry.define_group("FIRST",None);
+        let second       = registry.define_group("SECOND",None);
+        let third        = registry.define_group("THIRD",Some(first));
+        for &group in &[first,second] {
+            registry.create_rule(group,&Pattern::char('a').many1(),"self.on_a(reader)");
+            registry.create_rule(group,&Pattern::any(),"self.on_other(reader)");
+        }
+        registry.create_rule(third,&Pattern::char('b'),"self.on_b(reader)");
+        registry
+    }
+
+    #[test]
+    fn test_identical_automata_share_functions() {
+        let registry      = registry();
+        let mut functions = FunctionTable::default();
+        let groups        = registry.all();
+        let first         = automaton_for_group(&groups[0],&registry,&mut functions).unwrap();
+        let report        = functions.report;
+        let second        = automaton_for_group(&groups[1],&registry,&mut functions).unwrap();
+        assert_eq!(first.dispatch,second.dispatch);
+        assert_eq!(functions.report.functions_after,report.functions_after);
+        assert_eq!(functions.report.size_after,report.size_after);
+        assert_eq!(functions.report.functions_before,2 * report.functions_before);
+    }
+
+    #[test]
+    fn test_inherited_rules_share_functions() {
+        let registry      = registry();
+        let mut functions = FunctionTable::default();
+        let groups        = registry.all();
+        automaton_for_group(&groups[0],&registry,&mut functions).unwrap();
+        let names = functions.items.iter().map(|item| item.sig.ident.to_string()).collect_vec();
+        automaton_for_group(&groups[2],&registry,&mut functions).unwrap();
+        let new_items = &functions.items[names.len()..];
+        let new_names = new_items.iter().map(|item| item.sig.ident.to_string()).collect_vec();
+        assert!(names.contains(&"group_0_rule_0".into()));
+        assert!(new_names.contains(&"group_2_rule_0".into()));
+        assert!(!new_names.contains(&"group_2_rule_1".into()));
+        assert!(!new_names.contains(&"group_2_rule_2".into()));
+    }
+
+    #[test]
+    fn test_annotations_do_not_prevent_sharing() {
+        let mut registry   = group::Registry::default();
+        let plain          = registry.define_group("PLAIN",None);
+        let rule           = Rule::new(Pattern::char('a').many1(),"self.on_a(reader)");
+        registry.add_rule(plain,rule.clone());
+        let options        = CodegenOptions::release();
+        let (_,plain_only) = specialize_groups(&registry,"Lexer","Output",&options).unwrap();
+        let annotated      = registry.define_group("ANNOTATED",None);
+        let annotation     = group::rule::Annotation::scoped("keyword.enso").exiting();
+        registry.add_rule(annotated,rule.annotated(annotation));
+        let (code,report)  = specialize_groups(&registry,"Lexer","Output",&options).unwrap();
+        let dispatches     = code.lines().filter(|line| line.starts_with("    // Dispatches on "));
+        assert_eq!(dispatches.collect_vec(),vec![
+            "    // Dispatches on the states of group `PLAIN`.",
+            "    // Dispatches on the states of group `ANNOTATED`.",
+        ]);
+        assert_eq!(report.functions_after,plain_only.functions_after);
+        assert!(report.functions_before > plain_only.functions_before);
+    }
+
+    fn specialize(registry:&group::Registry) -> String {
+        let options = CodegenOptions::release();
+        specialize_groups(registry,"Lexer","Output",&options).unwrap().0
+    }
+
+    #[test]
+    fn test_deterministic_code() {
+        let code    = specialize(&registry());
+        let options = CodegenOptions::release();
+        let hash    = definition_hash(&registry(),"Lexer","Output",&options);
+        assert_eq!(code,specialize(&registry()));
+        assert!(code.starts_with("// This code is generated by the flexer."));
+        assert!(code.contains(&format!("The hash of the lexer definition is {:016x}.",hash)));
+        let mut changed = registry();
+        let first       = changed.all()[0].id;
+        changed.create_rule(first,&Pattern::char('c'),"self.on_c(reader)");
+        assert_ne!(definition_hash(&changed,"Lexer","Output",&options),hash);
+        assert_ne!(definition_hash(&registry(),"Lexer","Output",&CodegenOptions::trace()),hash);
+    }
+
+    #[test]
+    fn test_code_comments() {
+        let code  = specialize(&registry());
+        let lines = code.lines().collect_vec();
+        let rule  = "rule 0 of group `FIRST`: `aa*` => `self.on_a(reader)`";
+        let index = |line:&str| lines.iter().position(|l| *l == line);
+        let rule_function = index(&format!("    // Implements {}.",rule)).unwrap();
+        assert_eq!(lines[rule_function + 1],
+            "    // Implements rule 1 of group `THIRD`: `aa*` => `self.on_a(reader)`.");
+        assert!(lines[rule_function + 2].starts_with("    fn group_0_rule_0 "));
+        assert!(lines.contains(&"    // State 1 of the automaton for group `FIRST`."));
+        assert!(lines.contains(&format!("    // Accepts {}.",rule).as_str()));
+        let dispatch = index("    // Dispatches on the states of group `FIRST`.").unwrap();
+        assert_eq!(lines[dispatch + 1],"    // Dispatches on the states of group `SECOND`.");
+        let third = "    // Implements rule 0 of group `THIRD`: `b` => `self.on_b(reader)`.";
+        assert!(lines.contains(&third));
+        let mut with_fallback = registry();
+        let first             = with_fallback.all()[0].id;
+        let bcd               = Pattern::char('b') >> Pattern::char('c') >> Pattern::char('d');
+        with_fallback.create_rule(first,&bcd,"self.on_bcd(reader)");
+        let code     = specialize(&with_fallback);
+        let fallback = code.lines().find(|line| line.starts_with("    // Falls back to "));
+        let fallback = fallback.unwrap();
+        assert!(fallback.starts_with("    // Falls back to rule 1 of group `FIRST`: "));
+        assert!(fallback.ends_with(" => `self.on_other(reader)`."));
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::automata::nfa::NFA;
 use crate::automata::pattern::Pattern;
+use crate::group::rule::Annotation;
 use crate::group::rule::Rule;
 
 use itertools::Itertools;
@@ -57,6 +58,21 @@ impl Registry {
         group.create_rule(pattern,callback.as_ref());
     }
 
+    /// Creates a rule that matches `pattern` for the group identified by `group_id`, but only when
+    /// `guard` holds.
+    ///
+    /// Panics if `group_id` refers to a nonexistent group.
+    pub fn create_guarded_rule
+    ( &mut self
+    , group    : Identifier
+    , pattern  : &Pattern
+    , guard    : impl AsRef<str>
+    , callback : impl AsRef<str>
+    ) {
+        let group = self.group_mut(group);
+        group.create_guarded_rule(pattern,guard.as_ref(),callback.as_ref());
+    }
+
     /// Associates the provided `rule` with the group identified by `group_id`.
     ///
     /// Panics if `group_id` refers to a nonexistent group.
@@ -106,14 +122,16 @@ impl Registry {
         let group     = self.group(group);
         let mut nfa   = NFA::default();
         let start     = nfa.new_state();
-        let build     = |rule:&Rule| nfa.new_pattern(start,&rule.pattern);
+        let build     = |rule:&Rule| nfa.new_pattern(start,&rule.pattern.normalize());
         let rules     = self.rules_for(group.id);
         let callbacks = rules.iter().map(|r| r.callback.clone()).collect_vec();
+        let guards    = rules.iter().map(|r| r.guard.clone()).collect_vec();
         let states    = rules.into_iter().map(build).collect_vec();
         let end       = nfa.new_state();
         for (ix,state) in states.into_iter().enumerate() {
             nfa.states[state.id].name     = Some(group.callback_name(ix));
             nfa.states[state.id].callback = callbacks.get(ix).unwrap().clone();
+            nfa.states[state.id].guard    = guards.get(ix).unwrap().clone();
             nfa.connect(state,end);
         }
         nfa
@@ -139,7 +157,7 @@ impl Registry {
 
 /// An identifier for a group.
 #[allow(missing_docs)]
-#[derive(Copy,Clone,Debug,Default,Eq,PartialEq)]
+#[derive(Copy,Clone,Debug,Default,Eq,Hash,PartialEq)]
 pub struct Identifier(usize);
 
 
@@ -176,6 +194,8 @@ impl Into<usize> for Identifier {
 ///
 /// - A set of [`Rule`s](Rule), each containing a regex pattern and associated callback.
 /// - Inherited rules from a parent group, if such a group exists.
+/// - Optional hooks, executed when the lexer enters or leaves the group, and when the input ends
+///   while the group is on the stack.
 ///
 /// Internally, the flexer maintains a stack of groups, where only one group can be active at any
 /// given time. Rules are matched _in order_, and hence overlaps are handled by the order in which
@@ -198,14 +218,23 @@ pub struct Group {
     pub parent_index:Option<Identifier>,
     /// A set of flexer rules.
     pub rules:Vec<Rule>,
+    /// The code to execute when the lexer enters this group.
+    pub on_enter:Option<String>,
+    /// The code to execute when the lexer leaves this group.
+    pub on_exit:Option<String>,
+    /// The code to execute when the input ends while this group is on the stack.
+    pub on_eof:Option<String>,
 }
 
 impl Group {
 
     /// Creates a new group.
     pub fn new(id:Identifier, name:impl Into<String>, parent_index:Option<Identifier>) -> Self {
-        let rules = Vec::new();
-        Group{id,name:name.into(),parent_index,rules}
+        let rules    = Vec::new();
+        let on_enter = None;
+        let on_exit  = None;
+        let on_eof   = None;
+        Group{id,name:name.into(),parent_index,rules,on_enter,on_exit,on_eof}
     }
 
     /// Adds a new rule to the current group.
@@ -220,10 +249,45 @@ impl Group {
         self.rules.push(rule)
     }
 
+    /// Creates a new rule that only matches when `guard` holds.
+    pub fn create_guarded_rule(&mut self, pattern:&Pattern, guard:&str, code:&str) {
+        let pattern_clone = pattern.clone();
+        let rule          = Rule::guarded(pattern_clone,guard,code);
+        self.rules.push(rule)
+    }
+
+    /// Creates a new rule with an `annotation` describing it to tools such as the exporter of
+    /// [TextMate grammars](crate::textmate).
+    pub fn create_annotated_rule(&mut self, pattern:&Pattern, code:&str, annotation:Annotation) {
+        let pattern_clone = pattern.clone();
+        let rule          = Rule::new(pattern_clone,code).annotated(annotation);
+        self.rules.push(rule)
+    }
+
+    /// Sets the code to execute when the lexer enters this group.
+    pub fn set_on_enter(&mut self, code:&str) {
+        self.on_enter = Some(code.into())
+    }
+
+    /// Sets the code to execute when the lexer leaves this group.
+    pub fn set_on_exit(&mut self, code:&str) {
+        self.on_exit = Some(code.into())
+    }
+
+    /// Sets the code to execute when the input ends while this group is on the stack.
+    pub fn set_on_eof(&mut self, code:&str) {
+        self.on_eof = Some(code.into())
+    }
+
     /// The canonical name for a given rule.
     pub fn callback_name(&self, rule_ix:usize) -> String {
         format!("group_{}_rule_{}",self.id.0,rule_ix)
     }
+
+    /// The canonical name for the hook of this group with the provided `kind`.
+    pub fn hook_name(&self, kind:&str) -> String {
+        format!("group_{}_{}",self.id.0,kind)
+    }
 }
 
 // === Trait Impls ===
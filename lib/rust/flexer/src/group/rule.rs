@@ -4,6 +4,7 @@
 //! callback.
 
 use crate::automata::pattern::Pattern;
+use crate::group;
 
 
 
@@ -12,7 +13,7 @@ use crate::automata::pattern::Pattern;
 // ==========
 
 /// A flexer rule.
-#[derive(Clone,Debug)]
+#[derive(Clone,Debug,Eq,Hash,PartialEq)]
 pub struct Rule {
     /// The pattern that triggers the callback.
     pub pattern:Pattern,
@@ -24,11 +25,84 @@ pub struct Rule {
     /// a [`crate::Flexer`] instance. To this end, the code you provide as a string must be valid in
     /// that context.
     pub callback:String,
+
+    /// An optional boolean expression over `self` that must hold for the rule to match.
+    ///
+    /// The guard is evaluated once [`Rule::pattern`] has matched, before the match is taken from
+    /// the reader. If it does not hold, the lexer falls back to the next rule that matches the same
+    /// input, as if this rule did not exist.
+    pub guard:Option<String>,
+
+    /// A description of the rule for tools other than the code generator, such as the exporter
+    /// of [TextMate grammars](crate::textmate).
+    pub annotation:Annotation,
 }
 
 impl Rule {
     /// Creates a new rule.
     pub fn new(pattern:Pattern, callback:impl Into<String>) -> Self {
-        Rule{pattern,callback:callback.into()}
+        let annotation = Annotation::default();
+        Rule{pattern,callback:callback.into(),guard:None,annotation}
+    }
+
+    /// Creates a new rule that only matches when `guard` holds.
+    pub fn guarded(pattern:Pattern, guard:impl Into<String>, callback:impl Into<String>) -> Self {
+        let annotation = Annotation::default();
+        Rule{pattern,callback:callback.into(),guard:Some(guard.into()),annotation}
+    }
+
+    /// Sets the annotation of the rule.
+    pub fn annotated(mut self, annotation:Annotation) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    /// Checks whether the code generated for the rule is the same as that for `other`.
+    ///
+    /// The code only depends on the pattern, callback and guard of a rule, so rules that differ
+    /// only in their [`Rule::annotation`] generate the same code.
+    pub fn generates_same_code(&self, other:&Rule) -> bool {
+        let same_pattern = self.pattern == other.pattern;
+        same_pattern && self.callback == other.callback && self.guard == other.guard
+    }
+}
+
+
+
+// ==================
+// === Annotation ===
+// ==================
+
+/// What a rule means to tools that cannot interpret its callback.
+///
+/// The callback of a rule is arbitrary code, so it is up to the author of the rule to describe what
+/// the callback does with the groups of the lexer.
+#[derive(Clone,Debug,Default,Eq,Hash,PartialEq)]
+pub struct Annotation {
+    /// The scope of the input that the rule matches, such as `keyword.operator.enso`.
+    pub scope:Option<String>,
+    /// The group that the callback enters, if any.
+    pub enters:Option<group::Identifier>,
+    /// Whether the callback exits the current group.
+    pub exits:bool,
+}
+
+impl Annotation {
+    /// An annotation assigning `scope` to the input that the rule matches.
+    pub fn scoped(scope:impl Into<String>) -> Self {
+        let scope = Some(scope.into());
+        Annotation{scope,..Default::default()}
+    }
+
+    /// Records that the callback enters `group`.
+    pub fn entering(mut self, group:group::Identifier) -> Self {
+        self.enters = Some(group);
+        self
+    }
+
+    /// Records that the callback exits the current group.
+    pub fn exiting(mut self) -> Self {
+        self.exits = true;
+        self
     }
 }
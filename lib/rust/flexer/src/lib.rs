@@ -71,13 +71,37 @@
 //!         self.tokens.push(token)
 //!     }
 //! }
+//!
+//! impl flexer::sink::TokenSink for TokenStream {
+//!     type Token  = Token;
+//!     type Output = TokenStream;
+//!
+//!     fn push(&mut self, token:Token) {
+//!         self.tokens.push(token)
+//!     }
+//!
+//!     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//!         flexer::sink::Checkpoint::new(self.tokens.len())
+//!     }
+//!
+//!     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//!         self.tokens.truncate(checkpoint.tokens())
+//!     }
+//!
+//!     fn finish(&mut self) -> TokenStream {
+//!         std::mem::take(self)
+//!     }
+//! }
 //! ```
 //!
 //! These tokens will be inserted into the token stream by our lexer as it recognises valid portions
 //! of our language.
 //!
-//! Whatever you choose as the `Output` type of your lexer, it will need to implement both
-//! [`std::clone::Clone`] and [`std::default::Default`].
+//! Whatever you choose as the `Output` type of your lexer, it will need to implement
+//! [`TokenSink`], which tells the flexer how to roll the output back to a [`sink::Checkpoint`] and
+//! how to obtain the result of lexing from it. It also needs to implement
+//! [`std::default::Default`] unless the lexer is created using [`Flexer::with_sink`]. Simple
+//! lexers can also use [`std::vec::Vec`] as their output type directly.
 //!
 //! ## The Lexer's State
 //!
@@ -116,6 +140,27 @@
 //! #         self.tokens.push(token)
 //! #     }
 //! # }
+//! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
 //!
 //!
 //! // === LexerState ===
@@ -180,6 +225,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -281,6 +347,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -383,6 +470,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -490,6 +598,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -624,6 +753,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -812,6 +962,27 @@
 //! #     }
 //! # }
 //! #
+//! # impl flexer::sink::TokenSink for TokenStream {
+//! #     type Token  = Token;
+//! #     type Output = TokenStream;
+//! #
+//! #     fn push(&mut self, token:Token) {
+//! #         self.tokens.push(token)
+//! #     }
+//! #
+//! #     fn checkpoint(&self) -> flexer::sink::Checkpoint {
+//! #         flexer::sink::Checkpoint::new(self.tokens.len())
+//! #     }
+//! #
+//! #     fn rollback(&mut self, checkpoint:flexer::sink::Checkpoint) {
+//! #         self.tokens.truncate(checkpoint.tokens())
+//! #     }
+//! #
+//! #     fn finish(&mut self) -> TokenStream {
+//! #         std::mem::take(self)
+//! #     }
+//! # }
+//! #
 //! #
 //! # // === LexerState ===
 //! #
@@ -1004,9 +1175,12 @@
 //! 4.  Re-export this output file from your cargo project's `lib.rs`.
 //!
 //! The process of specialization will generate quite a bit of code, but most importantly it will
-//! generate `pub fn run<R:LazyReader>(&mut self, mut reader:R) -> Result<Output>`, where `Output`
-//! is your lexer's token type. All of these functions are defined on your lexer type (the one whose
-//! name is provided to `specialize()`.
+//! generate `pub fn run<R:LazyReader>(&mut self, mut reader:R) -> LexingResult<Output::Output>`,
+//! where `Output` is your lexer's token sink. All of these functions are defined on your lexer type
+//! (the one whose name is provided to `specialize()`.
+//!
+//! Besides the tokens, the [`LexingResult`] of a run describes where the lexer stopped, and holds
+//! the diagnostics that your callbacks reported through [`Flexer::report`].
 //!
 //! ## In Summary
 //!
@@ -1017,17 +1191,28 @@ use crate::prelude::*;
 use prelude::logger::*;
 
 use crate::generate::GenError;
+use diagnostic::Diagnostic;
+use diagnostic::Severity;
 use prelude::logger::AnyLogger;
+use prelude::reader::BookmarkId;
 use prelude::reader::BookmarkManager;
+use sink::Checkpoint;
+use span::Span;
 
 pub mod automata;
 pub mod data;
+pub mod diagnostic;
 pub mod generate;
 pub mod group;
+pub mod sink;
+pub mod span;
+pub mod spec;
+pub mod textmate;
 
 /// Useful libraries for working with the flexer.
 pub mod prelude {
     pub use crate::generate::GenError;
+    pub use crate::sink::TokenSink;
     pub use enso_prelude::*;
     pub use lazy_reader::LazyReader;
     pub use lazy_reader::Reader;
@@ -1075,44 +1260,89 @@ mod constants {
 /// `state_stack`) means that the flexer can match a certain set of rules associated with that
 /// state. The user may cause the lexer to transition between states by pushing and popping states
 /// on the stack, thus allowing a much more flexible lexing engine than pure regular grammars.
+///
+/// The methods of the flexer for entering and leaving states only change the stack. The generated
+/// code defines methods of the same names on the lexer type, which shadow those of the flexer in
+/// the callbacks of the lexer, and also run the enter and exit hooks of the groups involved before
+/// returning.
 #[derive(Clone,Debug)]
 pub struct Flexer<Definition,Output,Logger> {
     /// The stack of states that are active during lexer execution.
     pub state_stack:NonEmptyVec<group::Identifier>,
     /// The result of the current stage of the DFA.
     pub status:StageStatus,
-    /// The tokens that have been lexed.
+    /// The sink receiving the tokens that have been lexed.
     pub output:Output,
-    /// The text of the current match of the lexer.
+    /// The text of the current match of the lexer, in a buffer that is reused for every match.
     pub current_match:String,
+    /// The location of the current match of the lexer in its input.
+    pub current_span:Span,
     /// A logger for the flexer, accessible in user definitions.
     pub logger:Logger,
     /// The definition of the user-provided state for the lexer.
     definition:Definition,
+    /// The offset in the input at which the next match starts.
+    position:usize,
+    /// The number of characters before the next match.
+    consumed:usize,
+    /// The number of characters in the current match.
+    match_chars:usize,
+    /// The diagnostics reported so far.
+    diagnostics:Vec<Diagnostic>,
+    /// The bookmarks defined by the user, indexed by their handles.
+    user_bookmarks:Vec<UserBookmarkState>,
+    /// The bookmarks for the matches of guarded fallback rules, indexed by their slots.
+    fallback_bookmarks:Vec<BookmarkId>,
+    /// The error that stopped the lexer, if any.
+    error:Option<LexingError>,
+    /// The input that the lexer has read, if it keeps it.
+    input:Option<String>,
 }
 
 impl<Definition,Output,Logger> Flexer<Definition,Output,Logger>
 where Definition : State,
       Logger     : AnyLogger<Owned=Logger>,
-      Output     : Default {
+      Output     : TokenSink + Default {
     /// Create a new lexer instance.
     pub fn new(parent_logger:impl AnyLogger) -> Flexer<Definition,Output,Logger> {
-        let logger           = <Logger>::sub(&parent_logger,"Flexer");
-        let status           = default();
-        let output           = default();
-        let definition       = Definition::new(&logger);
-        let initial_state_id = definition.initial_state();
-        let mut state_stack  = NonEmptyVec::singleton(initial_state_id);
-        let current_match    = default();
+        Self::with_sink(parent_logger,default())
+    }
+}
+
+impl<Definition,Output,Logger> Flexer<Definition,Output,Logger>
+where Definition : State,
+      Logger     : AnyLogger<Owned=Logger>,
+      Output     : TokenSink {
+    /// Create a new lexer instance that writes its tokens into `output`.
+    pub fn with_sink
+    ( parent_logger : impl AnyLogger
+    , output        : Output
+    ) -> Flexer<Definition,Output,Logger> {
+        let logger             = <Logger>::sub(&parent_logger,"Flexer");
+        let status             = default();
+        let definition         = Definition::new(&logger);
+        let initial_state_id   = definition.initial_state();
+        let mut state_stack    = NonEmptyVec::singleton(initial_state_id);
+        let current_match      = default();
+        let current_span       = default();
+        let position           = 0;
+        let consumed           = 0;
+        let match_chars        = 0;
+        let diagnostics        = default();
+        let user_bookmarks     = default();
+        let fallback_bookmarks = default();
+        let error              = None;
+        let input              = None;
 
         state_stack.reserve(constants::STATE_STACK_RESERVATION);
-        Flexer{state_stack,status,output,definition,current_match,logger}
+        Flexer{state_stack,status,output,definition,current_match,current_span,logger,position,
+            consumed,match_chars,diagnostics,user_bookmarks,fallback_bookmarks,error,input}
     }
 }
 
 impl<Definition,Output,Logger> Flexer<Definition,Output,Logger>
 where Definition : State,
-      Output     : Clone,
+      Output     : TokenSink,
       Logger     : AnyLogger<Owned=Logger> {
     /// Get the lexer result.
     pub fn result(&mut self) -> &Output {
@@ -1130,6 +1360,9 @@ where Definition : State,
     }
 
     /// Tell the lexer to enter the state described by `state`.
+    ///
+    /// This does not run the enter hook of `state`, which the method of the same name generated for
+    /// the lexer runs right after calling this.
     pub fn push_state(&mut self, state:group::Identifier) {
         self.logger.group_begin(
             ||format!("Enter State: {}",self.groups().group(state).name.as_str())
@@ -1139,7 +1372,9 @@ where Definition : State,
 
     /// End the current state, returning the popped state identifier if one was ended.
     ///
-    /// It will never end the initial state of the lexer.
+    /// It will never end the initial state of the lexer. This does not run the exit hook of the
+    /// state, which the method of the same name generated for the lexer runs right after calling
+    /// this.
     pub fn pop_state(&mut self) -> Option<group::Identifier> {
         let result = self.state_stack.pop();
         match result {
@@ -1153,7 +1388,10 @@ where Definition : State,
     /// End states until the specified `state` is reached, leaving the lexer in `state`.
     ///
     /// If `state` does not exist on the lexer's stack, then the lexer will be left in the root
-    /// state. Additionally, this function cannot pop the final occurrence of the root state.
+    /// state. Additionally, this function cannot pop the final occurrence of the root state. The
+    /// states are left from the innermost outwards, and the method of the same name generated for
+    /// the lexer runs the exit hook of each state as soon as it has been left, before leaving the
+    /// next one.
     pub fn pop_states_until(&mut self, state:group::Identifier) -> group::Identifier {
         while self.current_state() != state && self.current_state() != self.initial_state() {
             self.pop_state();
@@ -1178,6 +1416,344 @@ where Definition : State,
     pub fn is_in_state(&self, state:group::Identifier) -> bool {
         self.current_state() == state
     }
+
+    /// Reset the lexer to the state that it was in before its first run, so that it can run on
+    /// another input.
+    ///
+    /// The groups and the user bookmarks that have been defined are kept, while the output that
+    /// has not been taken, the diagnostics, and the marks of the bookmarks are discarded.
+    pub fn reset(&mut self) {
+        while self.state_stack.pop().is_some() {}
+        self.status = default();
+        self.output.rollback(Checkpoint::new(0));
+        self.current_match.clear();
+        self.current_span = default();
+        self.position     = 0;
+        self.consumed     = 0;
+        self.match_chars  = 0;
+        self.error        = None;
+        self.diagnostics.clear();
+        if let Some(input) = &mut self.input {
+            input.clear();
+        }
+        for state in &mut self.user_bookmarks {
+            state.mark = None;
+        }
+        // All bookmarks besides those of a new manager are added by the flexer. A new manager
+        // numbers the bookmarks in the order they are added, so adding as many again recreates
+        // them, which is checked against the ids that the flexer saved for them.
+        let fallback_ids = self.fallback_bookmarks.iter().copied();
+        let user_ids     = self.user_bookmarks.iter().map(|state| state.id);
+        let saved_ids    = fallback_ids.chain(user_ids).collect_vec();
+        let bookmarks    = self.definition.bookmarks_mut();
+        *bookmarks       = BookmarkManager::new();
+        for _ in &saved_ids {
+            let id = bookmarks.add_bookmark();
+            let message = "The reset lexer recreated an unknown bookmark.";
+            assert!(saved_ids.contains(&id),"{} {:?}",message,id);
+        }
+        self.definition.reset();
+    }
+
+    /// Stop the lexer with an error of the provided `kind`, which occurred after the `reader` had
+    /// read its current result.
+    ///
+    /// The error records the state of the lexer at this point, and becomes part of the result of
+    /// the run. It returns the status that the lexer stage should exit with.
+    pub fn fail<R:LazyReader>(&mut self, kind:ErrorKind, reader:&R) -> StageStatus {
+        let position = self.position + reader.result().len();
+        self.fail_at(kind,position)
+    }
+
+    /// Stop the lexer with an error of the provided `kind`, which occurred at `position`.
+    ///
+    /// This is for errors that do not depend on the reader, which [`Flexer::fail`] takes the
+    /// position from.
+    pub fn fail_at(&mut self, kind:ErrorKind, position:usize) -> StageStatus {
+        let group       = self.group_name(self.current_state());
+        let state_stack = self.state_stack.iter().map(|&state| self.group_name(state)).collect();
+        let error       = LexingError{kind,group,state_stack,position};
+        self.logger.error(||error.to_string());
+        self.error = Some(error);
+        StageStatus::ExitError
+    }
+
+    /// Report a problem with the current match, described by `message`.
+    ///
+    /// The diagnostic becomes part of the result of the run, unless the match is discarded by
+    /// rewinding to a user bookmark.
+    pub fn report(&mut self, severity:Severity, message:impl Into<String>) {
+        let span = self.current_span;
+        self.report_at(severity,message,span);
+    }
+
+    /// Report a problem with the text at `span`, described by `message`.
+    ///
+    /// This is for problems that only become apparent after the text that causes them has been
+    /// matched, so `span` is usually the `current_span` of an earlier match.
+    pub fn report_at(&mut self, severity:Severity, message:impl Into<String>, span:Span) {
+        let diagnostic = Diagnostic::new(severity,message,span);
+        self.logger.info(||format!("Reported {}.",diagnostic));
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// The diagnostics that have been reported so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Finish the run of the lexer, taking its output and the information about where it stopped.
+    pub fn lexing_result(&mut self) -> LexingResult<Output::Output> {
+        let kind        = self.result_kind();
+        let tokens      = self.output.finish();
+        let position    = self.position;
+        let consumed    = self.consumed;
+        let state_stack = self.state_stack.iter().map(|&state| self.group_name(state)).collect();
+        let diagnostics = mem::take(&mut self.diagnostics);
+        LexingResult{kind,tokens,position,consumed,state_stack,diagnostics}
+    }
+
+    /// Get the kind of result that the lexer finished with, based on its current status.
+    ///
+    /// It takes the error that stopped the lexer, if there was one.
+    pub fn result_kind(&mut self) -> ResultKind {
+        match (self.status,self.error.take()) {
+            (_,Some(error))              => ResultKind::Error(error),
+            (StageStatus::ExitFinished,_) => ResultKind::Success,
+            (StageStatus::ExitFail,_)     => ResultKind::Failure,
+            _                             => ResultKind::Partial,
+        }
+    }
+
+    /// Get the name of `group`, without assuming that the group exists.
+    fn group_name(&self, group:group::Identifier) -> String {
+        let index:usize = group.into();
+        match self.groups().all().get(index) {
+            Some(group) => group.name.clone(),
+            None        => format!("{:?}",group),
+        }
+    }
+
+    /// Take the text that the `reader` has matched as the current match.
+    ///
+    /// The text is copied into the existing buffer of `current_match`, which only allocates when
+    /// the match is longer than the buffer. Callbacks that do not need to own the text of a match
+    /// can hence use it, or the `current_span`, without allocating anything.
+    pub fn take_match<R:LazyReader>(&mut self, reader:&mut R) {
+        self.current_match.clear();
+        self.current_match.push_str(reader.result());
+        reader.result_mut().clear();
+        if let Some(input) = &mut self.input {
+            input.truncate(self.position);
+            input.push_str(&self.current_match);
+        }
+        let end           = self.position + self.current_match.len();
+        self.current_span = Span::new(self.position,end);
+        self.position     = end;
+        self.match_chars  = self.current_match.chars().count();
+        self.consumed    += self.match_chars;
+        for &id in &self.fallback_bookmarks {
+            self.definition.bookmarks_mut().unset::<R>(id);
+        }
+    }
+
+    /// Keep the input that the lexer reads, so that the text of the current match can be borrowed
+    /// with [`Flexer::current_str`], and the spans of the output sliced from [`Flexer::input`].
+    ///
+    /// This suits clients that would hold their whole input in memory anyway, as the input is kept
+    /// until the lexer is reset. It has to be called before the lexer runs, as the input read
+    /// before is not kept.
+    pub fn keep_input(&mut self) {
+        self.input.get_or_insert_with(default);
+    }
+
+    /// The input that the lexer has read, if it keeps it.
+    pub fn input(&self) -> Option<&str> {
+        self.input.as_deref()
+    }
+
+    /// Borrow the text of the current match from the input kept by the lexer.
+    ///
+    /// Returns `None` unless the lexer keeps its input, as set up with [`Flexer::keep_input`].
+    pub fn current_str(&self) -> Option<&str> {
+        self.input().and_then(|input| self.current_span.slice(input))
+    }
+
+    /// Rewind the `reader` to the start of the current match, so that it is lexed again.
+    ///
+    /// None of the match is left afterwards, so rewinding it again, or keeping part of it with
+    /// [`Flexer::less`], does not change anything.
+    pub fn rewind_current_match<R:LazyReader>(&mut self, reader:&mut R) {
+        let matched_bookmark = self.definition.bookmarks().matched_bookmark;
+        self.definition.bookmarks_mut().rewind(matched_bookmark,reader);
+        self.position    = self.current_span.start;
+        self.consumed   -= self.match_chars;
+        self.match_chars = 0;
+    }
+
+    /// Keep only the first `count` characters of the current match, returning the rest of it to
+    /// the `reader` to be lexed again.
+    ///
+    /// The current match and its span are shortened accordingly. Nothing happens if the match is
+    /// not longer than `count` characters, or if it has been rewound already. Keeping no characters
+    /// at all, without changing the state of the lexer, makes it match the same input again
+    /// forever.
+    pub fn less<R:LazyReader>(&mut self, count:usize, reader:&mut R) {
+        if count >= self.match_chars {
+            return
+        }
+        let kept = match self.current_match.char_indices().nth(count) {
+            Some((ix,_)) => ix,
+            None         => return,
+        };
+        let matched_bookmark = self.definition.bookmarks().matched_bookmark;
+        self.definition.bookmarks_mut().rewind(matched_bookmark,reader);
+        for _ in 0..count {
+            reader.advance_char(self.definition.bookmarks_mut());
+        }
+        self.current_match.truncate(kept);
+        self.current_span.end = self.current_span.start + kept;
+        self.position         = self.current_span.end;
+        self.consumed        -= self.match_chars - count;
+        self.match_chars      = count;
+    }
+
+    /// Rewind the `reader` to `bookmark`, discarding the tokens output since `checkpoint`.
+    ///
+    /// The spans of subsequent matches are only correct when `bookmark` marks the start of the
+    /// current match, in which case [`Flexer::rewind_current_match`] is usually more convenient.
+    pub fn rewind<R:LazyReader>
+    ( &mut self
+    , bookmark   : BookmarkId
+    , checkpoint : Checkpoint
+    , reader     : &mut R
+    ) {
+        self.definition.bookmarks_mut().rewind(bookmark,reader);
+        self.output.rollback(checkpoint);
+    }
+
+    /// Mark the position of the `reader` as the end of the match for the guarded fallback rule with
+    /// the bookmark `slot`.
+    ///
+    /// This is used by the generated code, which rewinds to the mark with
+    /// [`Flexer::rewind_to_fallback`] when the guards of all longer matches reject them. The
+    /// bookmark is created when it is first marked, and unmarked when the next match is taken.
+    pub fn mark_fallback<R:LazyReader>(&mut self, slot:usize, reader:&mut R) {
+        while self.fallback_bookmarks.len() <= slot {
+            let id = self.definition.bookmarks_mut().add_bookmark();
+            self.fallback_bookmarks.push(id);
+        }
+        let id = self.fallback_bookmarks[slot];
+        self.definition.bookmarks_mut().bookmark(id,reader);
+    }
+
+    /// Rewind the `reader` to the end of the match for the guarded fallback rule with the bookmark
+    /// `slot`, as marked by [`Flexer::mark_fallback`].
+    ///
+    /// The lexer fails with [`ErrorKind::UnknownBookmark`] if the bookmark was never marked.
+    pub fn rewind_to_fallback<R:LazyReader>(&mut self, slot:usize, reader:&mut R) {
+        match self.fallback_bookmarks.get(slot) {
+            Some(&id) => self.definition.bookmarks_mut().rewind(id,reader),
+            None      => { self.fail(ErrorKind::UnknownBookmark(slot),reader); }
+        }
+    }
+
+    /// Define a new bookmark named `name`, returning the handle through which callbacks can use it.
+    ///
+    /// The bookmark starts out unmarked.
+    pub fn define_bookmark(&mut self, name:impl Into<String>) -> UserBookmark {
+        let name = name.into();
+        let id   = self.definition.bookmarks_mut().add_bookmark();
+        let mark = None;
+        self.user_bookmarks.push(UserBookmarkState{name,id,mark});
+        UserBookmark(self.user_bookmarks.len() - 1)
+    }
+
+    /// The name with which `bookmark` was defined.
+    pub fn bookmark_name(&self, bookmark:UserBookmark) -> &str {
+        self.user_bookmark_state(bookmark).name.as_str()
+    }
+
+    /// Check whether `bookmark` is currently marked.
+    pub fn is_marked(&self, bookmark:UserBookmark) -> bool {
+        self.user_bookmark_state(bookmark).mark.is_some()
+    }
+
+    /// Mark the end of the current match with `bookmark`, along with the tokens output and the
+    /// diagnostics reported so far.
+    ///
+    /// The `reader` keeps the input after the mark until the bookmark is unmarked, so bookmarks
+    /// should not stay marked for longer than they are needed. The lexer fails with
+    /// [`ErrorKind::UnknownBookmark`] if it did not define the bookmark.
+    pub fn mark<R:LazyReader>(&mut self, bookmark:UserBookmark, reader:&mut R) {
+        let checkpoint  = self.output.checkpoint();
+        let position    = self.position;
+        let consumed    = self.consumed;
+        let diagnostics = self.diagnostics.len();
+        let at_eof      = reader.character().is_eof();
+        match self.user_bookmarks.get_mut(bookmark.0) {
+            Some(state) => {
+                state.mark = Some(Mark{position,consumed,diagnostics,checkpoint,at_eof});
+                self.definition.bookmarks_mut().bookmark(state.id,reader);
+            }
+            None => {
+                self.fail(ErrorKind::UnknownBookmark(bookmark.0),reader);
+            }
+        }
+    }
+
+    /// Unmark `bookmark`, allowing the `reader` to discard the input after it.
+    ///
+    /// The lexer fails with [`ErrorKind::UnknownBookmark`] if it did not define the bookmark.
+    pub fn unmark(&mut self, bookmark:UserBookmark) {
+        match self.user_bookmarks.get_mut(bookmark.0) {
+            Some(state) => {
+                state.mark = None;
+                self.definition.bookmarks_mut().unset::<AnyReader>(state.id);
+            }
+            None => {
+                let position = self.position;
+                self.fail_at(ErrorKind::UnknownBookmark(bookmark.0),position);
+            }
+        }
+    }
+
+    /// Rewind the `reader` to where `bookmark` was marked, discarding the tokens output and the
+    /// diagnostics reported since, and unmark the bookmark.
+    ///
+    /// Returns `false` without doing anything if the bookmark is not marked. The lexer fails with
+    /// [`ErrorKind::UnknownBookmark`] if it did not define the bookmark.
+    pub fn rewind_to<R:LazyReader>(&mut self, bookmark:UserBookmark, reader:&mut R) -> bool {
+        let (id,mark) = match self.user_bookmarks.get(bookmark.0) {
+            Some(state) => (state.id,state.mark),
+            None        => {
+                self.fail(ErrorKind::UnknownBookmark(bookmark.0),reader);
+                return false
+            }
+        };
+        match mark {
+            None       => false,
+            Some(mark) => {
+                // The reader cannot bookmark the end of the input, but it never moves past it
+                // either, so it is already where it needs to be.
+                if !mark.at_eof {
+                    self.definition.bookmarks_mut().rewind(id,reader);
+                }
+                self.output.rollback(mark.checkpoint);
+                self.diagnostics.truncate(mark.diagnostics);
+                self.unmark(bookmark);
+                self.position    = mark.position;
+                self.consumed    = mark.consumed;
+                self.match_chars = 0;
+                true
+            }
+        }
+    }
+
+    /// Get the state of `bookmark`.
+    fn user_bookmark_state(&self, bookmark:UserBookmark) -> &UserBookmarkState {
+        &self.user_bookmarks[bookmark.0]
+    }
 }
 
 // === Trait Impls ===
@@ -1197,12 +1773,55 @@ impl<Definition,Output,Logger> DerefMut for Flexer<Definition,Output,Logger> {
 
 
 
+// ====================
+// === UserBookmark ===
+// ====================
+
+/// A handle to a bookmark defined through [`Flexer::define_bookmark`].
+///
+/// Unlike the [`BookmarkId`]s of the reader, these handles cannot be confused with the bookmarks
+/// used by the generated code, and rewinding to one of them also restores the output and the
+/// position of the next match.
+#[derive(Copy,Clone,Debug,Eq,Hash,PartialEq)]
+pub struct UserBookmark(usize);
+
+/// The state of a bookmark defined by the user.
+#[derive(Clone,Debug)]
+struct UserBookmarkState {
+    /// The name of the bookmark, for debugging purposes.
+    name:String,
+    /// The bookmark of the reader that tracks the marked position.
+    id:BookmarkId,
+    /// The marked position, if any.
+    mark:Option<Mark>,
+}
+
+/// The reader type named when unsetting a bookmark, which does not use the reader at all.
+type AnyReader = prelude::reader::Reader<prelude::reader::decoder::DecoderUTF8,&'static [u8]>;
+
+/// A position marked by a user bookmark.
+#[derive(Clone,Copy,Debug)]
+struct Mark {
+    /// The offset in the input at which the next match starts.
+    position:usize,
+    /// The number of characters before the next match.
+    consumed:usize,
+    /// The number of diagnostics reported.
+    diagnostics:usize,
+    /// The state of the output.
+    checkpoint:Checkpoint,
+    /// Whether the reader had reached the end of the input.
+    at_eof:bool,
+}
+
+
+
 // ==================
 // === SubStateId ===
 // ==================
 
 /// An identifier for a sub-state of the lexer to transition to.
-#[derive(Copy,Clone,Debug,Default,PartialEq)]
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq)]
 pub struct SubStateId(usize);
 
 impl SubStateId {
@@ -1250,6 +1869,8 @@ pub enum StageStatus {
     ExitFail,
     /// A single step of the DFA has executed successfully.
     ExitFinished,
+    /// The stage exits with an error, which the lexer has recorded.
+    ExitError,
     /// The lexer should continue, transitioning to the included state.
     ContinueWith(SubStateId)
 }
@@ -1286,18 +1907,40 @@ impl Default for StageStatus {
 // ==============
 
 /// The result of executing the lexer on a given input.
+///
+/// Besides the tokens, it describes where the lexer stopped, so that clients can explain why it
+/// did not consume all of its input.
 #[derive(Clone,Debug)]
 pub struct LexingResult<T> {
     /// The kind of the result, representing _how_ the lexer completed.
     pub kind:ResultKind,
     /// The tokens that the lexer was able to process.
-    pub tokens:T
+    pub tokens:T,
+    /// The offset in the input, in bytes, up to which the lexer matched the input.
+    pub position:usize,
+    /// The number of characters that the lexer matched.
+    pub consumed:usize,
+    /// The names of the groups on the state stack when the lexer stopped, from the outermost to
+    /// the innermost.
+    pub state_stack:Vec<String>,
+    /// The diagnostics reported while lexing.
+    pub diagnostics:Vec<Diagnostic>,
 }
 
 impl<T> LexingResult<T> {
-    /// Create a new lexer result using the provided `kind` and `tokens`.
+    /// Create a new lexer result using the provided `kind` and `tokens`, which has not matched any
+    /// of its input.
     pub fn new(kind:ResultKind,tokens:T) -> LexingResult<T> {
-        LexingResult {kind,tokens}
+        let position    = 0;
+        let consumed    = 0;
+        let state_stack = default();
+        let diagnostics = default();
+        LexingResult {kind,tokens,position,consumed,state_stack,diagnostics}
+    }
+
+    /// Check whether any of the diagnostics reported while lexing is an error.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
     }
 
     /// Create a new success result, with the provided `tokens`.
@@ -1314,19 +1957,84 @@ impl<T> LexingResult<T> {
     pub fn failure(tokens:T) -> LexingResult<T> {
         LexingResult::new(ResultKind::Failure, tokens)
     }
+
+    /// Create an error result for `error`, with the `tokens` lexed before it occurred.
+    pub fn error(error:LexingError, tokens:T) -> LexingResult<T> {
+        LexingResult::new(ResultKind::Error(error), tokens)
+    }
 }
 
 /// The kind of lexer result.
-#[derive(Copy,Clone,Debug)]
+#[derive(Clone,Debug,PartialEq)]
 pub enum ResultKind {
     /// The lexer succeeded, returning the contained token stream.
     Success,
     /// The lexer succeeded on part of the input, returning the contained token stream.
     Partial,
     /// The lexer failed on the input, returning any tokens it _did_ manage to consume.
-    Failure
+    Failure,
+    /// The lexer was stopped by the contained error, returning the tokens lexed before it.
+    Error(LexingError),
+}
+
+
+
+// ===================
+// === LexingError ===
+// ===================
+
+/// An error that stopped a lexer before it reached the end of its input.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct LexingError {
+    /// What went wrong.
+    pub kind:ErrorKind,
+    /// The name of the group that the lexer was in.
+    pub group:String,
+    /// The names of the groups on the state stack, from the outermost to the innermost.
+    pub state_stack:Vec<String>,
+    /// The offset in the input, in bytes, at which the error occurred.
+    pub position:usize,
 }
 
+/// The kinds of error that can stop a lexer.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum ErrorKind {
+    /// The reader could not provide the next character.
+    Reader(prelude::reader::Error),
+    /// The lexer was in a group that it has no code for.
+    UnknownGroup(group::Identifier),
+    /// The lexer transitioned to a state that the automaton of its group does not have.
+    UnknownState(SubStateId),
+    /// The lexer used a bookmark, given by its index, that it did not define.
+    UnknownBookmark(usize),
+}
+
+
+// === Trait Impls ===
+
+impl Display for LexingError {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{} at byte {} in group {} (state stack: {}).",
+            self.kind,self.position,self.group,self.state_stack.join(", "))
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Reader(prelude::reader::Error::EndOfGroup) =>
+                write!(f,"No rules match the input"),
+            ErrorKind::Reader(error) => write!(f,"The reader failed with {:?}",error),
+            ErrorKind::UnknownGroup(group) => write!(f,"The lexer entered unknown {:?}",group),
+            ErrorKind::UnknownState(state) => write!(f,"The lexer reached unknown {:?}",state),
+            ErrorKind::UnknownBookmark(bookmark) =>
+                write!(f,"The lexer used unknown bookmark {}",bookmark),
+        }
+    }
+}
+
+impl std::error::Error for LexingError {}
+
 
 
 // =============
@@ -1357,6 +2065,11 @@ pub trait State {
     /// This function should be implemented as a call to [`generate::specialize`], passing
     /// the name of your lexer, and the name of your lexer's output type as a string.
     fn specialize(&self) -> Result<String,GenError>;
+    /// Reset the parts of the state that change while lexing, so that the lexer can run on
+    /// another input.
+    ///
+    /// Lexers whose state only holds their groups and bookmarks do not need to implement it.
+    fn reset(&mut self) {}
 }
 
 
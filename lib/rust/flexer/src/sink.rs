@@ -0,0 +1,168 @@
+//! This module defines the interface through which lexers emit their tokens, along with sinks for
+//! the most common ways of consuming them.
+
+use crate::prelude::*;
+
+
+
+// ==================
+// === Checkpoint ===
+// ==================
+
+/// A position in a [`TokenSink`] that the sink can be rolled back to.
+///
+/// It records the number of tokens that had been pushed to the sink at the time it was taken.
+#[derive(Clone,Copy,Debug,Default,Eq,Hash,Ord,PartialEq,PartialOrd)]
+pub struct Checkpoint(usize);
+
+impl Checkpoint {
+    /// Create a checkpoint for a sink that has received `tokens` tokens.
+    pub fn new(tokens:usize) -> Checkpoint {
+        Checkpoint(tokens)
+    }
+
+    /// The number of tokens that the sink had received when the checkpoint was taken.
+    pub fn tokens(self) -> usize {
+        self.0
+    }
+}
+
+
+
+// =================
+// === TokenSink ===
+// =================
+
+/// A destination for the tokens produced by a lexer.
+///
+/// Lexers push tokens into the sink as they recognise them, and may take a [`Checkpoint`] before
+/// doing something speculative, rolling the sink back to it if they change their mind. Sinks that
+/// cannot retract a token once they have received it, such as channels, should buffer the tokens
+/// until they can no longer be rolled back.
+pub trait TokenSink {
+    /// The type of the tokens that the sink accepts.
+    type Token;
+    /// The result of lexing into this sink, which the lexer returns once it finishes.
+    type Output;
+    /// Append `token` to the sink.
+    fn push(&mut self, token:Self::Token);
+    /// Get a checkpoint for the tokens that the sink has received so far.
+    fn checkpoint(&self) -> Checkpoint;
+    /// Discard all tokens that were pushed to the sink after `checkpoint` was taken.
+    fn rollback(&mut self, checkpoint:Checkpoint);
+    /// Take the result out of the sink, leaving it ready to receive the tokens of another run.
+    fn finish(&mut self) -> Self::Output;
+}
+
+
+// === Trait Impls ===
+
+impl<T> TokenSink for Vec<T> {
+    type Token  = T;
+    type Output = Vec<T>;
+
+    fn push(&mut self, token:T) {
+        Vec::push(self,token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Self::Output {
+        mem::take(self)
+    }
+}
+
+
+
+// ===============
+// === Counter ===
+// ===============
+
+/// A sink that discards its tokens, and only counts how many of them the lexer produced.
+#[derive(Debug)]
+pub struct Counter<T> {
+    count:usize,
+    token:PhantomData<T>,
+}
+
+impl<T> Counter<T> {
+    /// Create a new counter that has not seen any tokens.
+    pub fn new() -> Counter<T> {
+        let count = 0;
+        let token = PhantomData;
+        Counter{count,token}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl<T> Default for Counter<T> {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl<T> TokenSink for Counter<T> {
+    type Token  = T;
+    type Output = usize;
+
+    fn push(&mut self, _token:T) {
+        self.count += 1
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.count)
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.count = self.count.min(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Self::Output {
+        mem::take(&mut self.count)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vec_rollback() {
+        let mut sink = Vec::new();
+        TokenSink::push(&mut sink,1);
+        let checkpoint = sink.checkpoint();
+        TokenSink::push(&mut sink,2);
+        TokenSink::push(&mut sink,3);
+        sink.rollback(checkpoint);
+        TokenSink::push(&mut sink,4);
+        assert_eq!(sink.finish(),vec![1,4]);
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn test_counter_rollback() {
+        let mut sink = Counter::new();
+        sink.push('a');
+        let checkpoint = sink.checkpoint();
+        sink.push('b');
+        sink.rollback(checkpoint);
+        sink.push('c');
+        sink.push('d');
+        assert_eq!(sink.finish(),3);
+        assert_eq!(sink.checkpoint(),Checkpoint::new(0));
+    }
+}
@@ -0,0 +1,93 @@
+//! This module provides spans, which locate the text of a match in the input of a lexer without
+//! copying it.
+
+use crate::prelude::*;
+
+
+
+// ============
+// === Span ===
+// ============
+
+/// A range of bytes in the input of a lexer.
+///
+/// The offsets are into the UTF-8 encoding of the input, regardless of the encoding that the
+/// reader decodes it from.
+#[derive(Clone,Copy,Debug,Default,Eq,Hash,PartialEq)]
+pub struct Span {
+    /// The offset of the first byte in the span.
+    pub start:usize,
+    /// The offset one past the last byte in the span.
+    pub end:usize,
+}
+
+impl Span {
+    /// Create a new span from `start` up to, but not including, `end`.
+    pub fn new(start:usize, end:usize) -> Span {
+        Span{start,end}
+    }
+
+    /// The number of bytes in the span.
+    pub fn len(self) -> usize {
+        self.end - self.start
+    }
+
+    /// Check whether the span contains no bytes.
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    /// Get the smallest span that covers both `self` and `other`.
+    pub fn cover(self, other:Span) -> Span {
+        Span::new(self.start.min(other.start),self.end.max(other.end))
+    }
+
+    /// Borrow the text of the span from `input`, which must be the text that the lexer ran on.
+    ///
+    /// Returns `None` if the span does not lie on character boundaries within `input`.
+    pub fn slice(self, input:&str) -> Option<&str> {
+        input.get(self.start..self.end)
+    }
+}
+
+
+// === Trait Impls ===
+
+impl From<Span> for Range<usize> {
+    fn from(span:Span) -> Self {
+        span.start..span.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,"{}..{}",self.start,self.end)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice() {
+        let input = "föo bar";
+        assert_eq!(Span::new(0,4).slice(input),Some("föo"));
+        assert_eq!(Span::new(5,8).slice(input),Some("bar"));
+        assert_eq!(Span::new(0,2).slice(input),None);
+        assert_eq!(Span::new(5,9).slice(input),None);
+    }
+
+    #[test]
+    fn test_cover() {
+        assert_eq!(Span::new(2,4).cover(Span::new(6,9)),Span::new(2,9));
+        assert_eq!(Span::new(6,9).cover(Span::new(2,4)),Span::new(2,9));
+        assert_eq!(Span::new(2,9).cover(Span::new(4,6)),Span::new(2,9));
+    }
+}
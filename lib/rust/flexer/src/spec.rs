@@ -0,0 +1,865 @@
+//! This module provides a declarative format for the specification of lexers, as an alternative
+//! to defining their groups and rules imperatively in Rust.
+//!
+//! A specification file (conventionally with the `.flex` extension) declares the lexer type that
+//! the code is generated for, the type of its output, named patterns, and the groups of the lexer
+//! with their rules. The callbacks of rules and end of input hooks are the names of methods on the
+//! lexer type, which are called with the reader, while guards and the enter and exit hooks are the
+//! names of methods called without arguments.
+//!
+//! ```text
+//! # Words made of `a`s or `b`s, separated by spaces.
+//! lexer  TestLexer
+//! output TokenStream
+//!
+//! let word = 'a'+ | 'b'+
+//!
+//! group ROOT
+//!     word => on_first_word
+//!     eof  => on_no_err_suffix_first_word
+//!     any  => on_err_suffix_first_word
+//!
+//! group SEEN_FIRST_WORD : ROOT
+//!     on_enter => on_enter_seen_first_word
+//!     ' ' word if is_ready => on_spaced_word
+//! ```
+//!
+//! The first group is the initial state of the lexer, and a group may inherit the rules of a
+//! group declared before it. Patterns are made of:
+//!
+//! - Characters, such as `'a'` or `'\n'`, and strings, such as `"foo"`, where `""` matches the
+//!   empty input.
+//! - Classes of characters, such as `[a-z_]`, and their complements, such as `[^"\\]`.
+//! - The names of patterns declared before, and the built-in patterns `any`, `eof`, `always` and
+//!   `never`.
+//! - Sequences of patterns separated by spaces, alternatives separated by `|`, and repetitions
+//!   with `*`, `+` and `?`, grouped with parentheses.
+//!
+//! Everything after a `#` outside of a literal is a comment.
+
+use crate::prelude::*;
+
+use crate::automata::pattern::Pattern;
+use crate::automata::symbol::Symbol;
+use crate::generate::CodegenOptions;
+use crate::generate::GenError;
+use crate::generate;
+use crate::group;
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The words that cannot be used as the names of patterns.
+const KEYWORDS:&[&str] = &[
+    "always","any","eof","group","if","let","lexer","never","on_enter","on_eof","on_exit","output"
+];
+
+
+
+// ============
+// === Spec ===
+// ============
+
+/// The specification of a lexer, loaded from its declarative form.
+#[derive(Clone,Debug)]
+pub struct Spec {
+    /// The name of the lexer type for which code is generated.
+    pub lexer:String,
+    /// The path of the type of the lexer's output.
+    pub output:String,
+    /// The groups of the lexer, the first of which is its initial state.
+    pub registry:group::Registry,
+    /// The patterns declared by name.
+    pub patterns:HashMap<String,Pattern>,
+}
+
+impl Spec {
+    /// Load the specification from the file at `path`.
+    pub fn load(path:impl AsRef<Path>) -> Result<Spec,SpecError> {
+        let path = path.as_ref();
+        let file = path.display().to_string();
+        match fs::read_to_string(path) {
+            Ok(text)   => Spec::parse(file,&text),
+            Err(error) => Err(SpecError::new(file,None,format!("Cannot read the file: {}.",error))),
+        }
+    }
+
+    /// Parse the specification in `text`, reporting errors in the file called `file`.
+    pub fn parse(file:impl Into<String>, text:&str) -> Result<Spec,SpecError> {
+        let mut parser = Parser::new(file.into());
+        for (index,line) in text.lines().enumerate() {
+            parser.parse_line(index + 1,line)?;
+        }
+        parser.finish(text.lines().count().max(1))
+    }
+
+    /// Get the identifier of the group called `name`.
+    pub fn group(&self, name:&str) -> Option<group::Identifier> {
+        self.registry.all().iter().find(|group| group.name == name).map(|group| group.id)
+    }
+
+    /// Generate specialized code for the lexer, as [`generate::specialize`] does for lexers
+    /// defined in Rust.
+    pub fn specialize(&self) -> Result<String,GenError> {
+        let options = CodegenOptions::default();
+        let output  = self.output.as_str();
+        let result  = generate::specialize_groups(&self.registry,&self.lexer,output,&options);
+        result.map(|(code,_)| code)
+    }
+}
+
+
+
+// =================
+// === SpecError ===
+// =================
+
+/// An error in a specification, or in reading it.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct SpecError {
+    /// The file containing the specification.
+    pub file:String,
+    /// The line and column of the error, both starting at one, if the file could be read.
+    pub position:Option<(usize,usize)>,
+    /// A description of the error.
+    pub message:String,
+}
+
+impl SpecError {
+    /// Create a new error.
+    pub fn new
+    (file:impl Into<String>, position:Option<(usize,usize)>, message:impl Into<String>) -> Self {
+        let file    = file.into();
+        let message = message.into();
+        SpecError{file,position,message}
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Display for SpecError {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line,column)) => write!(f,"{}:{}:{}: {}",self.file,line,column,self.message),
+            None                => write!(f,"{}: {}",self.file,self.message),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+
+
+// ==============
+// === Tokens ===
+// ==============
+
+/// The kinds of tokens on a line of a specification.
+#[derive(Clone,Debug,PartialEq)]
+enum Lexeme {
+    /// A name, which may be a path such as `token::Stream`.
+    Name(String),
+    /// A character literal.
+    Char(char),
+    /// A string literal.
+    Text(String),
+    /// A class of characters, made of ranges, which matches the characters outside of them if it
+    /// is negated.
+    Class{ranges:Vec<RangeInclusive<char>>, negated:bool},
+    /// A punctuation mark.
+    Punct(&'static str),
+}
+
+/// A token on a line of a specification.
+#[derive(Clone,Debug)]
+struct Token {
+    /// The kind of the token.
+    lexeme:Lexeme,
+    /// The column at which the token starts.
+    column:usize,
+}
+
+/// The punctuation marks, longest first.
+const PUNCTUATION:&[&str] = &["=>","|","*","+","?","(",")","=",":"];
+
+/// Splits a line of a specification into tokens.
+#[derive(Debug)]
+struct Scanner<'a> {
+    file:&'a str,
+    line:usize,
+    chars:Vec<char>,
+    index:usize,
+}
+
+impl<'a> Scanner<'a> {
+    /// Split the `text` of the line with number `line` into tokens.
+    fn tokens(file:&'a str, line:usize, text:&str) -> Result<Vec<Token>,SpecError> {
+        let chars       = text.chars().collect();
+        let mut scanner = Scanner{file,line,chars,index:0};
+        let mut tokens  = Vec::new();
+        while let Some(char) = scanner.peek() {
+            if char == '#' {
+                break
+            }
+            if char.is_whitespace() {
+                scanner.index += 1;
+                continue
+            }
+            let column = scanner.index + 1;
+            let lexeme = match char {
+                '\''                                     => Lexeme::Char(scanner.char_literal()?),
+                '"'                                      => Lexeme::Text(scanner.text_literal()?),
+                '['                                      => scanner.class()?,
+                _ if char.is_alphabetic() || char == '_' => Lexeme::Name(scanner.name()),
+                _                                        => Lexeme::Punct(scanner.punctuation()?),
+            };
+            tokens.push(Token{lexeme,column});
+        }
+        Ok(tokens)
+    }
+
+    /// Get the next character.
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    /// Get the character `offset` characters after the next one.
+    fn peek_at(&self, offset:usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    /// Create an error at the next character.
+    fn error(&self, message:impl Into<String>) -> SpecError {
+        SpecError::new(self.file,Some((self.line,self.index + 1)),message)
+    }
+
+    /// Scan a name, joining the segments of a path.
+    fn name(&mut self) -> String {
+        let is_name_char = |char:char| char.is_alphanumeric() || char == '_';
+        let mut name     = String::new();
+        while let Some(char) = self.peek().filter(|&char| is_name_char(char)) {
+            name.push(char);
+            self.index += 1;
+            let separator = self.peek() == Some(':') && self.peek_at(1) == Some(':');
+            if separator && matches!(self.peek_at(2),Some(char) if is_name_char(char)) {
+                name.push_str("::");
+                self.index += 2;
+            }
+        }
+        name
+    }
+
+    /// Scan a single character of a literal, which may be escaped.
+    fn literal_char(&mut self) -> Result<char,SpecError> {
+        let char = self.peek().ok_or_else(|| self.error("Unterminated literal."))?;
+        self.index += 1;
+        if char != '\\' {
+            return Ok(char)
+        }
+        let escaped = self.peek().ok_or_else(|| self.error("Unterminated literal."))?;
+        self.index += 1;
+        match escaped {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '0' => Ok('\0'),
+            'u' => self.unicode_escape(),
+            '\\' | '\'' | '"' | '[' | ']' | '-' | '^' => Ok(escaped),
+            _   => {
+                self.index -= 2;
+                Err(self.error(format!("Unknown escape `\\{}`.",escaped)))
+            }
+        }
+    }
+
+    /// Scan the `{...}` part of a `\u{...}` escape.
+    fn unicode_escape(&mut self) -> Result<char,SpecError> {
+        let start  = self.index;
+        let length = self.chars[start..].iter().position(|&char| char == '}');
+        match length {
+            Some(length) if self.peek() == Some('{') => {
+                let digits:String = self.chars[start + 1..start + length].iter().collect();
+                let code          = u32::from_str_radix(&digits,16).ok();
+                match code.and_then(std::char::from_u32) {
+                    Some(char) => {
+                        self.index += length + 1;
+                        Ok(char)
+                    }
+                    None => Err(self.error(format!("Invalid unicode escape `\\u{{{}}}`.",digits))),
+                }
+            }
+            _ => Err(self.error("Expected `{` and `}` around the code of a unicode escape.")),
+        }
+    }
+
+    /// Scan a character literal, such as `'a'`.
+    fn char_literal(&mut self) -> Result<char,SpecError> {
+        self.index += 1;
+        let char    = self.literal_char()?;
+        self.expect('\'',"Expected `'` to end the character.")?;
+        Ok(char)
+    }
+
+    /// Scan a string literal, such as `"abc"`.
+    fn text_literal(&mut self) -> Result<String,SpecError> {
+        self.index += 1;
+        let mut text = String::new();
+        while self.peek() != Some('"') {
+            text.push(self.literal_char()?);
+        }
+        self.index += 1;
+        Ok(text)
+    }
+
+    /// Scan a class of characters, such as `[a-z_]`.
+    fn class(&mut self) -> Result<Lexeme,SpecError> {
+        self.index += 1;
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.index += 1;
+        }
+        let mut ranges = Vec::new();
+        while self.peek() != Some(']') {
+            let start = self.literal_char()?;
+            let end   = if self.peek() == Some('-') && self.peek_at(1) != Some(']') {
+                self.index += 1;
+                self.literal_char()?
+            } else {
+                start
+            };
+            if end < start {
+                return Err(self.error(format!("The range `{}-{}` is empty.",start,end)))
+            }
+            ranges.push(start..=end);
+        }
+        self.index += 1;
+        Ok(Lexeme::Class{ranges,negated})
+    }
+
+    /// Scan one of the [`PUNCTUATION`] marks.
+    fn punctuation(&mut self) -> Result<&'static str,SpecError> {
+        let rest  = &self.chars[self.index..];
+        let found = PUNCTUATION.iter().find(|punct| {
+            rest.iter().take(punct.len()).copied().eq(punct.chars())
+        });
+        match found {
+            Some(punct) => {
+                self.index += punct.len();
+                Ok(punct)
+            }
+            None => Err(self.error(format!("Unexpected character `{}`.",rest[0]))),
+        }
+    }
+
+    /// Skip `char`, failing with `message` if it is not next.
+    fn expect(&mut self, char:char, message:&str) -> Result<(),SpecError> {
+        if self.peek() == Some(char) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+}
+
+
+
+// ==============
+// === Cursor ===
+// ==============
+
+/// A position in the tokens of a line of a specification.
+#[derive(Debug)]
+struct Cursor<'a> {
+    file:&'a str,
+    line:usize,
+    tokens:&'a [Token],
+    index:usize,
+    /// The column just after the end of the line.
+    end:usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Get the next token.
+    fn peek(&self) -> Option<&'a Lexeme> {
+        self.tokens.get(self.index).map(|token| &token.lexeme)
+    }
+
+    /// Get the column of the next token.
+    fn column(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end,|token| token.column)
+    }
+
+    /// Create an error at the next token.
+    fn error(&self, message:impl Into<String>) -> SpecError {
+        SpecError::new(self.file,Some((self.line,self.column())),message)
+    }
+
+    /// Move to the token after the next one.
+    fn advance(&mut self) {
+        self.index += 1;
+    }
+
+    /// Skip the punctuation mark `punct` if it is next.
+    fn eat(&mut self, punct:&str) -> bool {
+        let found = matches!(self.peek(),Some(Lexeme::Punct(next)) if *next == punct);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    /// Skip the punctuation mark `punct`, failing if it is not next.
+    fn expect(&mut self, punct:&str) -> Result<(),SpecError> {
+        if self.eat(punct) {Ok(())} else {Err(self.error(format!("Expected `{}`.",punct)))}
+    }
+
+    /// Check whether the next token is the keyword `keyword`.
+    fn at_keyword(&self, keyword:&str) -> bool {
+        matches!(self.peek(),Some(Lexeme::Name(name)) if name == keyword)
+    }
+
+    /// Expect a name, described by `what` in the error if there is none.
+    fn name(&mut self, what:&str) -> Result<String,SpecError> {
+        match self.peek() {
+            Some(Lexeme::Name(name)) => {
+                self.advance();
+                Ok(name.clone())
+            }
+            _ => Err(self.error(format!("Expected {}.",what))),
+        }
+    }
+
+    /// Expect the name of a method, which cannot be a path.
+    fn method(&mut self) -> Result<String,SpecError> {
+        let column = self.column();
+        let name   = self.name("the name of a method")?;
+        if name.contains("::") {
+            let position = Some((self.line,column));
+            Err(SpecError::new(self.file,position,format!("`{}` is not a method name.",name)))
+        } else {
+            Ok(name)
+        }
+    }
+
+    /// Check that all of the tokens on the line have been used.
+    fn finish(&self) -> Result<(),SpecError> {
+        if self.index < self.tokens.len() {
+            Err(self.error("Unexpected input at the end of the line."))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+/// Builds a specification from its lines.
+#[derive(Debug)]
+struct Parser {
+    file:String,
+    lexer:Option<String>,
+    output:Option<String>,
+    registry:group::Registry,
+    patterns:HashMap<String,Pattern>,
+    current_group:Option<group::Identifier>,
+}
+
+impl Parser {
+    /// Create a parser for the specification in `file`.
+    fn new(file:String) -> Parser {
+        let lexer         = None;
+        let output        = None;
+        let registry      = default();
+        let patterns      = default();
+        let current_group = None;
+        Parser{file,lexer,output,registry,patterns,current_group}
+    }
+
+    /// Parse the `text` of the line with number `line`.
+    fn parse_line(&mut self, line:usize, text:&str) -> Result<(),SpecError> {
+        let tokens     = Scanner::tokens(&self.file,line,text)?;
+        let end        = text.chars().count() + 1;
+        let file       = self.file.clone();
+        let mut cursor = Cursor{file:&file,line,tokens:&tokens,index:0,end};
+        let keyword    = match cursor.peek() {
+            None                     => return Ok(()),
+            Some(Lexeme::Name(name)) => name.as_str(),
+            Some(_)                  => "",
+        };
+        match keyword {
+            "lexer"  => {
+                cursor.advance();
+                self.lexer = Some(cursor.name("the name of the lexer type")?);
+            }
+            "output" => {
+                cursor.advance();
+                self.output = Some(cursor.name("the name of the output type")?);
+            }
+            "let"    => self.parse_pattern_declaration(&mut cursor)?,
+            "group"  => self.parse_group(&mut cursor)?,
+            "on_enter" | "on_exit" | "on_eof" => self.parse_hook(&mut cursor,keyword)?,
+            _        => self.parse_rule(&mut cursor)?,
+        }
+        cursor.finish()
+    }
+
+    /// Parse `let <name> = <pattern>`.
+    fn parse_pattern_declaration(&mut self, cursor:&mut Cursor) -> Result<(),SpecError> {
+        cursor.advance();
+        let column = cursor.column();
+        let name   = cursor.name("the name of the pattern")?;
+        let error  = |message:String| {
+            Err(SpecError::new(cursor.file,Some((cursor.line,column)),message))
+        };
+        if KEYWORDS.contains(&name.as_str()) || name.contains("::") {
+            return error(format!("`{}` cannot be the name of a pattern.",name))
+        }
+        if self.patterns.contains_key(&name) {
+            return error(format!("The pattern `{}` is already declared.",name))
+        }
+        cursor.expect("=")?;
+        let pattern = self.parse_alternative(cursor)?;
+        self.patterns.insert(name,pattern);
+        Ok(())
+    }
+
+    /// Parse `group <name>`, optionally followed by `: <parent>`.
+    fn parse_group(&mut self, cursor:&mut Cursor) -> Result<(),SpecError> {
+        cursor.advance();
+        let column = cursor.column();
+        let name   = cursor.name("the name of the group")?;
+        if self.find_group(&name).is_some() {
+            let message  = format!("The group `{}` is already declared.",name);
+            return Err(SpecError::new(cursor.file,Some((cursor.line,column)),message))
+        }
+        let parent = if cursor.eat(":") {
+            let column = cursor.column();
+            let parent = cursor.name("the name of the parent group")?;
+            match self.find_group(&parent) {
+                Some(parent) => Some(parent),
+                None         => {
+                    let message  = format!("Unknown group `{}`.",parent);
+                    return Err(SpecError::new(cursor.file,Some((cursor.line,column)),message))
+                }
+            }
+        } else {
+            None
+        };
+        self.current_group = Some(self.registry.define_group(name,parent));
+        Ok(())
+    }
+
+    /// Parse `<hook> => <method>` for the current group.
+    fn parse_hook(&mut self, cursor:&mut Cursor, hook:&str) -> Result<(),SpecError> {
+        let group = self.current_group(cursor)?;
+        cursor.advance();
+        cursor.expect("=>")?;
+        let method = cursor.method()?;
+        let group  = self.registry.group_mut(group);
+        match hook {
+            "on_enter" => group.set_on_enter(&format!("self.{}()",method)),
+            "on_exit"  => group.set_on_exit(&format!("self.{}()",method)),
+            _          => group.set_on_eof(&format!("self.{}(reader)",method)),
+        }
+        Ok(())
+    }
+
+    /// Parse `<pattern> => <method>`, optionally with `if <method>` before the arrow, for the
+    /// current group.
+    fn parse_rule(&mut self, cursor:&mut Cursor) -> Result<(),SpecError> {
+        let group   = self.current_group(cursor)?;
+        let pattern = self.parse_alternative(cursor)?;
+        let guard   = if cursor.at_keyword("if") {
+            cursor.advance();
+            Some(format!("self.{}()",cursor.method()?))
+        } else {
+            None
+        };
+        cursor.expect("=>")?;
+        let callback = format!("self.{}(reader)",cursor.method()?);
+        match guard {
+            None        => self.registry.create_rule(group,&pattern,callback),
+            Some(guard) => self.registry.create_guarded_rule(group,&pattern,guard,callback),
+        }
+        Ok(())
+    }
+
+    /// Parse alternatives separated by `|`.
+    fn parse_alternative(&self, cursor:&mut Cursor) -> Result<Pattern,SpecError> {
+        let mut pattern = self.parse_sequence(cursor)?;
+        while cursor.eat("|") {
+            pattern = pattern | self.parse_sequence(cursor)?;
+        }
+        Ok(pattern)
+    }
+
+    /// Parse a sequence of patterns.
+    fn parse_sequence(&self, cursor:&mut Cursor) -> Result<Pattern,SpecError> {
+        let mut patterns = Vec::new();
+        while let Some(pattern) = self.parse_repetition(cursor)? {
+            patterns.push(pattern);
+        }
+        patterns.into_iter().fold1(|lhs,rhs| lhs >> rhs).ok_or_else(|| {
+            cursor.error("Expected a pattern.")
+        })
+    }
+
+    /// Parse a pattern followed by any number of `*`, `+` and `?`.
+    fn parse_repetition(&self, cursor:&mut Cursor) -> Result<Option<Pattern>,SpecError> {
+        let mut pattern = match self.parse_atom(cursor)? {
+            Some(pattern) => pattern,
+            None          => return Ok(None),
+        };
+        loop {
+            pattern = if cursor.eat("*") {
+                pattern.many()
+            } else if cursor.eat("+") {
+                pattern.many1()
+            } else if cursor.eat("?") {
+                pattern.opt()
+            } else {
+                return Ok(Some(pattern))
+            }
+        }
+    }
+
+    /// Parse a single pattern, or return `None` if the next token cannot start one.
+    fn parse_atom(&self, cursor:&mut Cursor) -> Result<Option<Pattern>,SpecError> {
+        let pattern = match cursor.peek() {
+            Some(Lexeme::Char(char))            => Pattern::char(*char),
+            Some(Lexeme::Text(text))            => match text.as_str() {
+                ""   => Pattern::always(),
+                text => Pattern::all_of(text),
+            }
+            Some(Lexeme::Class{ranges,negated}) => class_pattern(ranges,*negated),
+            Some(Lexeme::Punct("("))            => {
+                cursor.advance();
+                let pattern = self.parse_alternative(cursor)?;
+                cursor.expect(")")?;
+                return Ok(Some(pattern))
+            }
+            Some(Lexeme::Name(name)) => match name.as_str() {
+                "if"     => return Ok(None),
+                "any"    => Pattern::any(),
+                "eof"    => Pattern::eof(),
+                "always" => Pattern::always(),
+                "never"  => Pattern::never(),
+                name     => match self.patterns.get(name) {
+                    Some(pattern) => pattern.clone(),
+                    None          => {
+                        return Err(cursor.error(format!("Unknown pattern `{}`.",name)))
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+        cursor.advance();
+        Ok(Some(pattern))
+    }
+
+    /// Get the group declared last, which rules and hooks belong to.
+    fn current_group(&self, cursor:&Cursor) -> Result<group::Identifier,SpecError> {
+        self.current_group.ok_or_else(|| {
+            cursor.error("Rules and hooks must follow the declaration of a group.")
+        })
+    }
+
+    /// Get the identifier of the group called `name`.
+    fn find_group(&self, name:&str) -> Option<group::Identifier> {
+        self.registry.all().iter().find(|group| group.name == name).map(|group| group.id)
+    }
+
+    /// Check that the specification is complete, reporting missing declarations at `last_line`.
+    fn finish(self, last_line:usize) -> Result<Spec,SpecError> {
+        let Parser{file,lexer,output,registry,patterns,..} = self;
+        let missing = |what:&str| {
+            let message = format!("The specification does not declare {}.",what);
+            SpecError::new(file.as_str(),Some((last_line,1)),message)
+        };
+        let lexer  = lexer.ok_or_else(|| missing("the `lexer` type"))?;
+        let output = output.ok_or_else(|| missing("the `output` type"))?;
+        if registry.all().is_empty() {
+            return Err(missing("any groups"))
+        }
+        Ok(Spec{lexer,output,registry,patterns})
+    }
+}
+
+/// The pattern matching the characters in `ranges`, or all other characters if `negated`.
+fn class_pattern(ranges:&[RangeInclusive<char>], negated:bool) -> Pattern {
+    if !negated {
+        let patterns = ranges.iter().map(|range| Pattern::range(range.clone()));
+        return patterns.fold1(|lhs,rhs| lhs | rhs).unwrap_or_else(Pattern::never)
+    }
+    let codes     = ranges.iter().map(|range| (*range.start() as u32,*range.end() as u32));
+    let mut codes = codes.collect_vec();
+    codes.sort_unstable();
+    let last_code      = Symbol::EOF_CODE.value - 1;
+    let mut start      = 0;
+    let mut complement = Vec::new();
+    for (first,last) in codes {
+        if first > start {
+            complement.push(Pattern::symbols(Symbol::from(start)..=Symbol::from(first - 1)));
+        }
+        start = start.max(last + 1);
+    }
+    if start <= last_code {
+        complement.push(Pattern::symbols(Symbol::from(start)..=Symbol::from(last_code)));
+    }
+    complement.into_iter().fold1(|lhs,rhs| lhs | rhs).unwrap_or_else(Pattern::never)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// A specification in the style of the test lexer.
+    const WORDS:&str = r#"
+# Words made of `a`s or `b`s, separated by spaces.
+lexer  TestLexer
+output TokenStream
+
+let word = 'a'+ | 'b'+
+
+group ROOT
+    word => on_first_word
+    eof  => on_no_err_suffix_first_word
+    any  => on_err_suffix_first_word
+
+group SEEN_FIRST_WORD : ROOT
+    on_enter => on_enter_seen_first_word
+    ' ' word if is_ready => on_spaced_word # A trailing comment.
+"#;
+
+    fn spec(text:&str) -> Spec {
+        Spec::parse("test.flex",text).unwrap()
+    }
+
+    fn error(text:&str) -> String {
+        Spec::parse("test.flex",text).unwrap_err().to_string()
+    }
+
+    /// The pattern declared as `p` in a specification that declares `patterns`.
+    fn pattern(patterns:&str) -> Pattern {
+        let text = format!("lexer L\noutput O\n{}\ngroup G",patterns);
+        spec(&text).patterns["p"].clone()
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec   = spec(WORDS);
+        let root   = spec.group("ROOT").unwrap();
+        let seen   = spec.group("SEEN_FIRST_WORD").unwrap();
+        let groups = spec.registry.all();
+        assert_eq!(spec.lexer,"TestLexer");
+        assert_eq!(spec.output,"TokenStream");
+        assert_eq!(groups.iter().map(|group| group.name.as_str()).collect_vec(),
+            vec!["ROOT","SEEN_FIRST_WORD"]);
+        let on_enter = "self.on_enter_seen_first_word()";
+        assert_eq!(spec.registry.group(seen).parent_index,Some(root));
+        assert_eq!(spec.registry.group(seen).on_enter.as_deref(),Some(on_enter));
+        let rules = spec.registry.rules_for(seen);
+        let word  = Pattern::char('a').many1() | Pattern::char('b').many1();
+        assert_eq!(rules.len(),4);
+        assert_eq!(rules[0].callback,"self.on_spaced_word(reader)");
+        assert_eq!(rules[0].guard.as_deref(),Some("self.is_ready()"));
+        assert!(rules[0].pattern.equivalent(&(Pattern::char(' ') >> &word)).is_ok());
+        assert_eq!(rules[3].callback,"self.on_err_suffix_first_word(reader)");
+        assert_eq!(rules[3].guard,None);
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        let equivalent = |patterns:&str, expected:Pattern| {
+            assert!(pattern(patterns).equivalent(&expected).is_ok(),"{}",patterns);
+        };
+        equivalent(r#"let p = "if" | "in""#,Pattern::literals(&["if","in"]));
+        let repeated = (Pattern::all_of("ab") >> Pattern::char('c').opt()).many();
+        equivalent(r#"let p = ("ab" 'c'?)* eof"#,repeated >> Pattern::eof());
+        equivalent(r#"let p = "" 'a' """#,Pattern::char('a'));
+        let class = Pattern::range('a'..='c') | Pattern::char('_') | Pattern::char(']');
+        equivalent(r#"let p = [a-c_\]]"#,class);
+        let not_eof = Pattern::symbols(Symbol::from(0)..=Symbol::from(Symbol::EOF_CODE.value - 1));
+        equivalent(r#"let p = [^]"#,not_eof.clone());
+        let negated = pattern(r#"let p = [^"\\]"#);
+        assert!((Pattern::char('\0') | Pattern::char('#')).is_subset_of(&negated).is_ok());
+        assert!(negated.is_subset_of(&not_eof).is_ok());
+        assert!(Pattern::char('"').is_subset_of(&negated).is_err());
+        assert!(Pattern::char('\\').is_subset_of(&negated).is_err());
+        equivalent(r#"let p = '\n' '\u{e9}' "\t""#,Pattern::all_of("\né\t"));
+        equivalent("let q = 'a'+\nlet p = q q",Pattern::char('a') >> Pattern::char('a').many1());
+        equivalent("let p = any",Pattern::any());
+    }
+
+    #[test]
+    fn test_spec_errors() {
+        let header = "lexer L\noutput O\n";
+        let with   = |lines:&str| format!("{}{}",header,lines);
+        assert_eq!(error(&with("let p = q")),"test.flex:3:9: Unknown pattern `q`.");
+        assert_eq!(error(&with("let if = 'a'")),
+            "test.flex:3:5: `if` cannot be the name of a pattern.");
+        assert_eq!(error(&with("let p = 'ab'")),
+            "test.flex:3:11: Expected `'` to end the character.");
+        assert_eq!(error(&with("let p = \"ab")),"test.flex:3:12: Unterminated literal.");
+        assert_eq!(error(&with("let p = [z-a]")),"test.flex:3:13: The range `z-a` is empty.");
+        assert_eq!(error(&with("let p = '\\q'")),"test.flex:3:10: Unknown escape `\\q`.");
+        assert_eq!(error(&with("let p = ('a'")),"test.flex:3:13: Expected `)`.");
+        assert_eq!(error(&with("let p = 'a' ;")),"test.flex:3:13: Unexpected character `;`.");
+        assert_eq!(error(&with("'a' => on_a")),
+            "test.flex:3:1: Rules and hooks must follow the declaration of a group.");
+        assert_eq!(error(&with("group A : B")),"test.flex:3:11: Unknown group `B`.");
+        assert_eq!(error(&with("group A\ngroup A")),
+            "test.flex:4:7: The group `A` is already declared.");
+        assert_eq!(error(&with("group A\n'a'")),"test.flex:4:4: Expected `=>`.");
+        assert_eq!(error(&with("group A\n'a' => a::b")),
+            "test.flex:4:8: `a::b` is not a method name.");
+        assert_eq!(error(&with("group A\n => on_a")),"test.flex:4:2: Expected a pattern.");
+        assert_eq!(error(&with("group A\n'a' => on_a x")),
+            "test.flex:4:13: Unexpected input at the end of the line.");
+        assert_eq!(error("output O\ngroup A"),
+            "test.flex:2:1: The specification does not declare the `lexer` type.");
+        assert_eq!(error(header),"test.flex:2:1: The specification does not declare any groups.");
+    }
+
+    #[test]
+    fn test_specialize_spec() {
+        let code = spec(WORDS).specialize().unwrap().replace(' ',"");
+        assert!(code.contains("implTestLexer"));
+        assert!(code.contains("self.on_spaced_word(reader)"));
+        assert!(code.contains("self.is_ready()"));
+        let invalid_name = WORDS.replace("TestLexer","fn");
+        assert!(spec(&invalid_name).specialize().is_err());
+    }
+
+    #[test]
+    fn test_load_spec() {
+        let path = std::env::temp_dir().join(format!("flexer_spec_{}.flex",std::process::id()));
+        fs::write(&path,WORDS).unwrap();
+        let loaded = Spec::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().registry.all().len(),2);
+        let error = Spec::load(&path).unwrap_err();
+        assert_eq!(error.position,None);
+        assert!(error.to_string().starts_with(&format!("{}: Cannot read the file",path.display())));
+    }
+}
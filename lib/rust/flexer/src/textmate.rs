@@ -0,0 +1,510 @@
+//! This module exports the groups of a lexer as a [TextMate grammar][grammar], which editors such
+//! as VS Code use to highlight code.
+//!
+//! The patterns of the rules are converted to [Oniguruma][oniguruma] regular expressions, and the
+//! rules are described to the exporter with their [`Annotation`]s:
+//!
+//! - A rule that does not affect the groups becomes a `match` rule with the scope of the rule, so
+//!   that rules without a scope still take their input from the rules after them.
+//! - A rule that enters a group becomes a `begin` rule whose context includes the rules of the
+//!   group, and ends at any of the rules of that group that exit it.
+//! - Rules that can match the empty input, such as those that only check what follows a token,
+//!   are left out of the grammar, as editors cannot make progress with them.
+//!
+//! A regular expression takes the first of its alternatives that matches instead of the longest,
+//! and an editor takes the rule that matches earliest on a line instead of at the current position,
+//! so the grammar approximates the lexer rather than reproducing it exactly.
+//!
+//! [grammar]: https://macromates.com/manual/en/language_grammars
+//! [oniguruma]: https://github.com/kkos/oniguruma/blob/master/doc/RE
+
+use crate::prelude::*;
+
+use crate::automata::pattern::Pattern;
+use crate::automata::symbol::Symbol;
+use crate::group::rule::Annotation;
+use crate::group::rule::Rule;
+use crate::group;
+
+use std::ops::RangeInclusive;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// A regular expression that never matches.
+const NEVER:&str = "(?!)";
+
+/// A regular expression that matches at the end of the input.
+const END_OF_INPUT:&str = r"\z";
+
+/// The greatest unicode code point.
+const MAX_CHAR:u32 = 0x10FFFF;
+
+/// The code points reserved for UTF-16 surrogates, which are not characters.
+const SURROGATES:RangeInclusive<u32> = 0xD800..=0xDFFF;
+
+
+
+// ===============
+// === Grammar ===
+// ===============
+
+/// The description of a language that a TextMate grammar is exported for.
+#[derive(Clone,Debug)]
+pub struct Grammar {
+    /// The name of the language, such as `Enso`.
+    pub name:String,
+    /// The scope of the whole file, such as `source.enso`.
+    pub scope_name:String,
+    /// The extensions of the files in the language, without the leading dot.
+    pub file_types:Vec<String>,
+}
+
+impl Grammar {
+    /// Creates the description of a language.
+    pub fn new(name:impl Into<String>, scope_name:impl Into<String>) -> Self {
+        let name       = name.into();
+        let scope_name = scope_name.into();
+        let file_types = Vec::new();
+        Grammar{name,scope_name,file_types}
+    }
+
+    /// Adds an extension of the files in the language.
+    pub fn file_type(mut self, extension:impl Into<String>) -> Self {
+        self.file_types.push(extension.into());
+        self
+    }
+
+    /// Exports the groups in `registry` as the JSON of a TextMate grammar.
+    ///
+    /// The first group in `registry` is the one that the grammar starts in, and each group is
+    /// defined in the repository of the grammar under its name.
+    pub fn export(&self, registry:&group::Registry) -> String {
+        let file_types = self.file_types.iter().map(|ext| Json::text(ext)).collect();
+        let patterns   = registry.all().first().map(|root| include(&root.name)).into_iter();
+        let repository = registry.all().iter().map(|group| {
+            let rules    = registry.rules_for(group.id);
+            let patterns = rules.into_iter().filter_map(|rule| rule_json(registry,rule)).collect();
+            (group.name.clone(),Json::Object(vec![("patterns".into(),Json::List(patterns))]))
+        }).collect();
+        let grammar = Json::Object(vec![
+            ("name".into()      ,Json::text(&self.name)),
+            ("scopeName".into() ,Json::text(&self.scope_name)),
+            ("fileTypes".into() ,Json::List(file_types)),
+            ("patterns".into()  ,Json::List(patterns.collect())),
+            ("repository".into(),Json::Object(repository)),
+        ]);
+        let mut json = String::new();
+        grammar.write(&mut json,0);
+        json.push('\n');
+        json
+    }
+}
+
+/// The grammar entry for `rule`, or `None` if the rule is left out of the grammar.
+fn rule_json(registry:&group::Registry, rule:&Rule) -> Option<Json> {
+    let Annotation{scope,enters,exits} = &rule.annotation;
+    let mut fields = Vec::new();
+    if *exits || matches_empty(&rule.pattern) {
+        return None
+    }
+    match enters {
+        Some(group) => {
+            let group      = registry.group(*group);
+            let rules      = registry.rules_for(group.id).into_iter();
+            let exit_rules = rules.filter(|rule| rule.annotation.exits).collect_vec();
+            fields.push(("begin".into(),Json::text(&regex(&rule.pattern))));
+            fields.push(("end".into(),Json::text(&end_regex(&exit_rules))));
+            if let Some(scope) = scope {
+                fields.push(("name".into(),Json::text(scope)));
+            }
+            let scopes = exit_rules.iter().map(|rule| rule.annotation.scope.as_ref());
+            let scopes = scopes.enumerate().filter_map(|(ix,scope)| Some((ix,scope?)));
+            let scopes = scopes.map(|(ix,scope)| {
+                let capture = if exit_rules.len() == 1 {0} else {ix + 1};
+                (capture.to_string(),Json::Object(vec![("name".into(),Json::text(scope))]))
+            }).collect_vec();
+            if !scopes.is_empty() {
+                fields.push(("endCaptures".into(),Json::Object(scopes)));
+            }
+            fields.push(("patterns".into(),Json::List(vec![include(&group.name)])));
+        }
+        None => {
+            fields.push(("match".into(),Json::text(&regex(&rule.pattern))));
+            if let Some(scope) = scope {
+                fields.push(("name".into(),Json::text(scope)));
+            }
+        }
+    }
+    Some(Json::Object(fields))
+}
+
+/// Checks whether `pattern` can match the empty input.
+fn matches_empty(pattern:&Pattern) -> bool {
+    match pattern {
+        Pattern::Range(_)           => false,
+        Pattern::Or(patterns)       => patterns.iter().any(matches_empty),
+        Pattern::Seq(patterns)      => patterns.iter().all(matches_empty),
+        Pattern::Many(_)            => true,
+        Pattern::Literals(literals) => literals.iter().any(|literal| literal.is_empty()),
+        Pattern::Always             => true,
+    }
+}
+
+/// A grammar entry that includes the rules of the group called `name`.
+fn include(name:&str) -> Json {
+    Json::Object(vec![("include".into(),Json::text(&format!("#{}",name)))])
+}
+
+/// The regular expression that ends a context at any of the `exit_rules`.
+///
+/// Each of the rules is captured separately when there are several of them, so that they can be
+/// given different scopes.
+fn end_regex(exit_rules:&[&Rule]) -> String {
+    match exit_rules {
+        []     => NEVER.into(),
+        [rule] => regex(&rule.pattern),
+        rules  => rules.iter().map(|rule| format!("({})",regex(&rule.pattern))).join("|"),
+    }
+}
+
+
+
+// =============
+// === Regex ===
+// =============
+
+/// Converts `pattern` to an Oniguruma regular expression.
+///
+/// The end of the file is matched by `\z`, and the symbols that are not characters are left out.
+pub fn regex(pattern:&Pattern) -> String {
+    Regex::from(&pattern.normalize()).text
+}
+
+/// How tightly the parts of a regular expression are bound together, loosest first.
+#[derive(Clone,Copy,Debug,Eq,Ord,PartialEq,PartialOrd)]
+enum Precedence {
+    Alternative,
+    Sequence,
+    Atom,
+}
+
+/// A regular expression along with its precedence.
+#[derive(Clone,Debug)]
+struct Regex {
+    text:String,
+    precedence:Precedence,
+}
+
+impl Regex {
+    fn new(text:impl Into<String>, precedence:Precedence) -> Self {
+        Regex{text:text.into(),precedence}
+    }
+
+    /// The text of the regular expression, grouped if its precedence is lower than `precedence`.
+    fn bound(&self, precedence:Precedence) -> String {
+        if self.precedence < precedence {
+            format!("(?:{})",self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    fn alternatives(alternatives:Vec<Regex>) -> Regex {
+        match alternatives.len() {
+            0 => Regex::new(NEVER,Precedence::Atom),
+            1 => alternatives.into_iter().next().unwrap(),
+            _ => {
+                let text = alternatives.iter().map(|r| r.bound(Precedence::Sequence)).join("|");
+                Regex::new(text,Precedence::Alternative)
+            }
+        }
+    }
+
+    fn sequence(items:Vec<Regex>) -> Regex {
+        match items.len() {
+            1 => items.into_iter().next().unwrap(),
+            _ => {
+                let text = items.iter().map(|r| r.bound(Precedence::Sequence)).join("");
+                Regex::new(text,Precedence::Sequence)
+            }
+        }
+    }
+
+    /// The regular expression that matches any of the symbols in `ranges`, as a single class of
+    /// characters where possible.
+    fn ranges(ranges:&[&RangeInclusive<Symbol>]) -> Regex {
+        let is_never  = |range:&&&RangeInclusive<Symbol>| {
+            *range.start() == Symbol::INVALID_SYMBOL && *range.end() == Symbol::INVALID_SYMBOL
+        };
+        let ranges    = ranges.iter().filter(|range| !is_never(range)).collect_vec();
+        let codes     = ranges.iter().map(|range| (range.start().value,range.end().value));
+        let chars     = codes.flat_map(|(start,end)| char_ranges(start,end.min(MAX_CHAR)));
+        let chars     = chars.collect_vec();
+        let mut alternatives = Vec::new();
+        match chars.as_slice() {
+            []                              => {}
+            [(first,last)] if first == last => alternatives.push(escape(*first)),
+            chars                           => {
+                let chars = chars.iter().map(|(first,last)| match last - first {
+                    0 => escape_in_class(*first),
+                    1 => format!("{}{}",escape_in_class(*first),escape_in_class(*last)),
+                    _ => format!("{}-{}",escape_in_class(*first),escape_in_class(*last)),
+                });
+                alternatives.push(format!("[{}]",chars.collect::<String>()));
+            }
+        }
+        if ranges.iter().any(|range| *range.end() == Symbol::EOF_CODE) {
+            alternatives.push(END_OF_INPUT.into());
+        }
+        let alternatives = alternatives.into_iter().map(|r| Regex::new(r,Precedence::Atom));
+        let regex        = Regex::alternatives(alternatives.collect());
+        Regex::new(regex.bound(Precedence::Atom),Precedence::Atom)
+    }
+
+    fn literal(literal:&[Symbol]) -> Regex {
+        let chars = literal.iter().filter_map(|symbol| char::from_u32(symbol.value));
+        let text  = chars.map(|char| escape(char as u32)).collect::<String>();
+        let atom  = literal.len() == 1;
+        Regex::new(text,if atom {Precedence::Atom} else {Precedence::Sequence})
+    }
+}
+
+
+// === Trait Impls ===
+
+impl From<&Pattern> for Regex {
+    fn from(pattern:&Pattern) -> Self {
+        match pattern {
+            Pattern::Range(range)       => Regex::ranges(&[range]),
+            Pattern::Seq(items)         => Regex::sequence(items.iter().map(Regex::from).collect()),
+            Pattern::Or(alternatives)   => {
+                // Single characters and the empty input are tried last, as a regex takes the
+                // first alternative that matches rather than the longest.
+                let ranges       = alternatives.iter().filter_map(|pattern| match pattern {
+                    Pattern::Range(range) => Some(range),
+                    _                     => None,
+                }).collect_vec();
+                let is_last      = |p:&&Pattern| matches!(p,Pattern::Range(_) | Pattern::Always);
+                let others       = alternatives.iter().filter(|p| !is_last(p));
+                let mut patterns = others.map(Regex::from).collect_vec();
+                if !ranges.is_empty() {
+                    patterns.push(Regex::ranges(&ranges));
+                }
+                if alternatives.iter().any(|p| matches!(p,Pattern::Always)) {
+                    patterns.push(Regex::from(&Pattern::Always));
+                }
+                Regex::alternatives(patterns)
+            }
+            Pattern::Many(pattern)      => {
+                let text = format!("{}*",Regex::from(pattern.as_ref()).bound(Precedence::Atom));
+                Regex::new(text,Precedence::Sequence)
+            }
+            Pattern::Literals(literals) => {
+                let mut literals = literals.iter().collect_vec();
+                literals.sort_by_key(|literal| std::cmp::Reverse(literal.len()));
+                Regex::alternatives(literals.into_iter().map(|l| Regex::literal(l)).collect())
+            }
+            Pattern::Always             => Regex::new("",Precedence::Sequence),
+        }
+    }
+}
+
+
+// === Characters ===
+
+/// The ranges of characters from `start` to `end`, leaving out the surrogates.
+fn char_ranges(start:u32, end:u32) -> Vec<(u32,u32)> {
+    let before = (start,end.min(SURROGATES.start() - 1));
+    let after  = (start.max(SURROGATES.end() + 1),end);
+    vec![before,after].into_iter().filter(|(first,last)| first <= last).collect()
+}
+
+/// The regular expression that matches the character with the code point `code`.
+fn escape(code:u32) -> String {
+    match char::from_u32(code) {
+        Some(char) if "\\^$.|?*+()[]{}/".contains(char) => format!("\\{}",char),
+        _                                                => escape_in_class(code),
+    }
+}
+
+/// The representation of the character with the code point `code` in a class of characters.
+fn escape_in_class(code:u32) -> String {
+    match char::from_u32(code) {
+        Some(char) if "\\^-[]&".contains(char)          => format!("\\{}",char),
+        Some(char) if char == ' ' || char.is_alphanumeric() || char.is_ascii_punctuation() =>
+            char.to_string(),
+        _ => format!("\\x{{{:X}}}",code),
+    }
+}
+
+
+
+// ============
+// === Json ===
+// ============
+
+/// A JSON value, with the fields of objects kept in order so that the output is stable.
+#[derive(Clone,Debug)]
+enum Json {
+    Text(String),
+    List(Vec<Json>),
+    Object(Vec<(String,Json)>),
+}
+
+impl Json {
+    fn text(text:&str) -> Json {
+        Json::Text(text.into())
+    }
+
+    /// Writes the value to `out`, with nested values indented by two spaces per level.
+    fn write(&self, out:&mut String, level:usize) {
+        let indent = |out:&mut String, level:usize| out.push_str(&"  ".repeat(level));
+        match self {
+            Json::Text(text) => write_string(out,text),
+            Json::List(items) if items.is_empty() => out.push_str("[]"),
+            Json::List(items) => {
+                out.push_str("[\n");
+                for (ix,item) in items.iter().enumerate() {
+                    indent(out,level + 1);
+                    item.write(out,level + 1);
+                    out.push_str(if ix + 1 < items.len() {",\n"} else {"\n"});
+                }
+                indent(out,level);
+                out.push(']');
+            }
+            Json::Object(fields) if fields.is_empty() => out.push_str("{}"),
+            Json::Object(fields) => {
+                out.push_str("{\n");
+                for (ix,(key,value)) in fields.iter().enumerate() {
+                    indent(out,level + 1);
+                    write_string(out,key);
+                    out.push_str(": ");
+                    value.write(out,level + 1);
+                    out.push_str(if ix + 1 < fields.len() {",\n"} else {"\n"});
+                }
+                indent(out,level);
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Writes `text` to `out` as a JSON string.
+fn write_string(out:&mut String, text:&str) {
+    out.push('"');
+    for char in text.chars() {
+        match char {
+            '"'                  => out.push_str("\\\""),
+            '\\'                 => out.push_str("\\\\"),
+            '\n'                 => out.push_str("\\n"),
+            '\t'                 => out.push_str("\\t"),
+            char if char < ' '   => out.push_str(&format!("\\u{:04x}",char as u32)),
+            char                 => out.push(char),
+        }
+    }
+    out.push('"');
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use crate::group::Registry;
+
+    #[test]
+    fn test_regex() {
+        let keywords = Pattern::literals(&["if","infix"]);
+        assert_eq!(regex(&keywords),"infix|if");
+        assert_eq!(regex(&(Pattern::char('a') >> Pattern::char('b').many1())),"abb*");
+        assert_eq!(regex(&Pattern::range('a'..='z').many()),"[a-z]*");
+        assert_eq!(regex(&(Pattern::any_of("(.") | Pattern::char('-'))),r"[(\-.]");
+        assert_eq!(regex(&(Pattern::char('a') | Pattern::all_of("ab"))),"ab|a");
+        assert_eq!(regex(&Pattern::char('\t').opt()),r"\x{9}|");
+        assert_eq!(regex(&(Pattern::char(' ') >> Pattern::eof())),r" \z");
+        assert_eq!(regex(&Pattern::never()),NEVER);
+        assert_eq!(regex(&Pattern::any()),r"(?:[\x{0}-\x{D7FF}\x{E000}-\x{10FFFF}]|\z)");
+        assert_eq!(regex(&Pattern::none_of("\"")),r"[\x{1}-!#-\x{D7FF}\x{E000}-\x{10FFFF}]");
+    }
+
+    #[test]
+    fn test_export() {
+        let mut registry = Registry::default();
+        let root         = registry.define_group("ROOT",None);
+        let text         = registry.define_group("TEXT",None);
+        let quote        = Pattern::char('"');
+        let escape       = Pattern::char('\\') >> Pattern::any();
+        let keyword      = Annotation::scoped("keyword.control");
+        let string       = Annotation::scoped("string.quoted").entering(text);
+        let end          = Annotation::scoped("punctuation.end").exiting();
+        let root_group   = registry.group_mut(root);
+        root_group.create_annotated_rule(&Pattern::all_of("if"),"self.on_if(reader)",keyword);
+        root_group.create_annotated_rule(&quote,"self.on_quote(reader)",string);
+        root_group.create_rule(&Pattern::char(' '),"self.on_space(reader)");
+        root_group.create_rule(&Pattern::always(),"self.on_nothing(reader)");
+        let text_group = registry.group_mut(text);
+        text_group.create_annotated_rule(&quote,"self.on_end(reader)",end);
+        text_group.create_annotated_rule(&escape,"",Annotation::scoped("constant.character"));
+
+        let grammar = Grammar::new("Test","source.test").file_type("test").export(&registry);
+        let expected = r##"{
+  "name": "Test",
+  "scopeName": "source.test",
+  "fileTypes": [
+    "test"
+  ],
+  "patterns": [
+    {
+      "include": "#ROOT"
+    }
+  ],
+  "repository": {
+    "ROOT": {
+      "patterns": [
+        {
+          "match": "if",
+          "name": "keyword.control"
+        },
+        {
+          "begin": "\"",
+          "end": "\"",
+          "name": "string.quoted",
+          "endCaptures": {
+            "0": {
+              "name": "punctuation.end"
+            }
+          },
+          "patterns": [
+            {
+              "include": "#TEXT"
+            }
+          ]
+        },
+        {
+          "match": " "
+        }
+      ]
+    },
+    "TEXT": {
+      "patterns": [
+        {
+          "match": "\\\\(?:[\\x{0}-\\x{D7FF}\\x{E000}-\\x{10FFFF}]|\\z)",
+          "name": "constant.character"
+        }
+      ]
+    }
+  }
+}
+"##;
+        assert_eq!(grammar,expected);
+    }
+}
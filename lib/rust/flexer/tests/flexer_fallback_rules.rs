@@ -0,0 +1,76 @@
+//! This file contains tests for the fallback rules that the flexer code generator computes for the
+//! states of an automaton.
+
+#![allow(missing_docs)]
+
+use flexer::automata::dfa::DFA;
+use flexer::automata::pattern::Pattern;
+use flexer::automata::state;
+use flexer::generate::FallbackDFA;
+use flexer::group::Registry;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Get the state that `dfa` reaches from its initial state on `input`.
+fn state_after(dfa:&DFA, input:&str) -> state::Identifier {
+    let initial = state::Identifier::new(0);
+    input.chars().fold(initial,|state,char| dfa.next_state(state,char.into()))
+}
+
+/// Get the code of the rules that the state reached on `input` falls back to, in order.
+fn fallbacks_after(dfa:&FallbackDFA, input:&str) -> Vec<String> {
+    let state = state_after(&dfa.dfa,input);
+    dfa.fallbacks[state.id].iter().map(|rule| rule.code.clone()).collect()
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn fallback_depends_on_path() {
+    let mut registry = Registry::default();
+    let group_id     = registry.define_group("ROOT",None);
+    let group        = registry.group_mut(group_id);
+    let a            = Pattern::char('a');
+    let b            = Pattern::char('b');
+    let a_or_b_cd    = (&a | &b) >> Pattern::char('c') >> Pattern::char('d');
+    group.create_rule(&a,"self.on_a(reader)");
+    group.create_rule(&b,"self.on_b(reader)");
+    group.create_rule(&a_or_b_cd,"self.on_cd(reader)");
+    let rule_a = group.callback_name(0);
+    let rule_b = group.callback_name(1);
+    let dfa    = FallbackDFA::from(&DFA::from(&registry.to_nfa_from(group_id)));
+    assert_eq!(fallbacks_after(&dfa,""),Vec::<String>::new());
+    assert_eq!(fallbacks_after(&dfa,"ac"),vec![rule_a]);
+    assert_eq!(fallbacks_after(&dfa,"bc"),vec![rule_b]);
+    assert_eq!(fallbacks_after(&dfa,"bcd"),Vec::<String>::new());
+    assert!(dfa.guarded_fallbacks.is_empty());
+}
+
+#[test]
+fn fallbacks_reach_every_shorter_match() {
+    let mut registry = Registry::default();
+    let group_id     = registry.define_group("ROOT",None);
+    let group        = registry.group_mut(group_id);
+    let a            = Pattern::char('a');
+    let ab           = &a >> Pattern::char('b');
+    let abc          = &ab >> Pattern::char('c');
+    group.create_rule(&a,"self.on_a(reader)");
+    group.create_guarded_rule(&ab,"false","self.on_ab(reader)");
+    group.create_guarded_rule(&abc,"false","self.on_abc(reader)");
+    let rule_a  = group.callback_name(0);
+    let rule_ab = group.callback_name(1);
+    let dfa     = FallbackDFA::from(&DFA::from(&registry.to_nfa_from(group_id)));
+    assert_eq!(fallbacks_after(&dfa,"a"),Vec::<String>::new());
+    assert_eq!(fallbacks_after(&dfa,"ab"),vec![rule_a.clone()]);
+    assert_eq!(fallbacks_after(&dfa,"abc"),vec![rule_ab.clone(),rule_a]);
+    let guarded = dfa.guarded_fallbacks.iter().map(|rule| rule.code.clone()).collect::<Vec<_>>();
+    assert_eq!(guarded,vec![rule_ab]);
+}
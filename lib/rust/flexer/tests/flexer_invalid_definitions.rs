@@ -16,6 +16,7 @@ use flexer::generate;
 use flexer::group::{Registry, Identifier};
 use flexer::group;
 use flexer::prelude::*;
+use flexer::sink::Checkpoint;
 use flexer::State;
 use flexer;
 
@@ -47,6 +48,27 @@ pub struct Output {
     tokens:Vec<Token>
 }
 
+impl TokenSink for Output {
+    type Token  = Token;
+    type Output = Output;
+
+    fn push(&mut self, token:Token) {
+        self.tokens.push(token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.tokens.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.tokens.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Output {
+        mem::take(self)
+    }
+}
+
 /// A testing lexer state.
 pub struct LexerState {
     lexer_states:group::Registry,
@@ -444,3 +466,216 @@ pub fn test_bad_output_name() {
     let message = result.unwrap_err().to_string();
     assert_eq!(message,"`Bad output name` is not a valid rust path.");
 }
+
+
+// ====================
+// === Definition 5 ===
+// ====================
+
+pub struct Lexer5 {
+    lexer:Flexer<LexerState,Output,Logger>
+}
+
+impl Deref for Lexer5 {
+    type Target = Flexer<LexerState,Output,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for Lexer5 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl Lexer5 {
+    pub fn new() -> Lexer5 {
+        let logger = Logger::new("Lexer5");
+        let lexer  = Flexer::new(logger);
+        Lexer5{lexer}
+    }
+
+    pub fn my_test_fun<R:LazyReader>(&mut self, _reader:&mut R) {
+        unimplemented!()
+    }
+}
+
+impl flexer::Definition for Lexer5 {
+    fn define() -> Self {
+        let mut lexer = Self::new();
+
+        let foo = Pattern::all_of("foo");
+
+        let root_group_id = lexer.initial_state();
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+        root_group.create_rule(&foo, "self.my_test_fun(reader)");
+        root_group.set_on_eof("self.test_function_no_reader()");
+
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {
+        unimplemented!()
+    }
+
+    fn tear_down(&mut self) {
+        unimplemented!()
+    }
+}
+
+#[test]
+pub fn test_no_reader_arg_in_hook() {
+    let lexer            = Lexer5::define();
+    let result           = lexer.specialize();
+    let expected_message =
+        "Bad argument to a callback function. It must take a single argument `reader`.";
+    assert!(result.is_err());
+    let message = result.unwrap_err().to_string();
+    assert_eq!(message,expected_message);
+}
+
+
+// ====================
+// === Definition 6 ===
+// ====================
+
+pub struct Lexer6 {
+    lexer:Flexer<LexerState,Output,Logger>
+}
+
+impl Deref for Lexer6 {
+    type Target = Flexer<LexerState,Output,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for Lexer6 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl Lexer6 {
+    pub fn new() -> Lexer6 {
+        let logger = Logger::new("Lexer6");
+        let lexer  = Flexer::new(logger);
+        Lexer6{lexer}
+    }
+
+    pub fn my_test_fun<R:LazyReader>(&mut self, _reader:&mut R) {
+        unimplemented!()
+    }
+}
+
+impl flexer::Definition for Lexer6 {
+    fn define() -> Self {
+        let mut lexer = Self::new();
+
+        let foo = Pattern::all_of("foo");
+
+        let root_group_id = lexer.initial_state();
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+        root_group.create_guarded_rule(&foo,"self.is_ready(","self.my_test_fun(reader)");
+
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {
+        unimplemented!()
+    }
+
+    fn tear_down(&mut self) {
+        unimplemented!()
+    }
+}
+
+#[test]
+pub fn test_bad_guard_expression() {
+    let lexer  = Lexer6::define();
+    let result = lexer.specialize();
+    assert!(result.is_err());
+    let message = result.unwrap_err().to_string();
+    assert_eq!(message,"`self.is_ready(` is not a valid rust expression.");
+}
+
+
+// ====================
+// === Definition 7 ===
+// ====================
+
+pub struct Lexer7 {
+    lexer:Flexer<LexerState,Output,Logger>
+}
+
+impl Deref for Lexer7 {
+    type Target = Flexer<LexerState,Output,Logger>;
+    fn deref(&self) -> &Self::Target {
+        &self.lexer
+    }
+}
+
+impl DerefMut for Lexer7 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lexer
+    }
+}
+
+impl Lexer7 {
+    pub fn new() -> Lexer7 {
+        let logger = Logger::new("Lexer7");
+        let lexer  = Flexer::new(logger);
+        Lexer7{lexer}
+    }
+
+    pub fn my_test_fun<R:LazyReader>(&mut self, _reader:&mut R) {
+        unimplemented!()
+    }
+}
+
+impl flexer::Definition for Lexer7 {
+    fn define() -> Self {
+        let mut lexer = Self::new();
+
+        let foo = Pattern::all_of("foo");
+
+        let root_group_id = lexer.initial_state();
+        let root_group    = lexer.groups_mut().group_mut(root_group_id);
+        root_group.create_rule(&foo, "self.my_test_fun(reader)");
+        root_group.set_on_exit("self.my_test_fun(reader)");
+
+        lexer
+    }
+
+    fn groups(&self) -> &Registry {
+        self.lexer.groups()
+    }
+
+    fn set_up(&mut self) {
+        unimplemented!()
+    }
+
+    fn tear_down(&mut self) {
+        unimplemented!()
+    }
+}
+
+#[test]
+pub fn test_reader_arg_in_exit_hook() {
+    let lexer            = Lexer7::define();
+    let result           = lexer.specialize();
+    let expected_message =
+        "Bad argument to an enter or exit hook. It must not take the argument `reader`.";
+    assert!(result.is_err());
+    let message = result.unwrap_err().to_string();
+    assert_eq!(message,expected_message);
+}
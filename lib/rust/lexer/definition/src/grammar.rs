@@ -0,0 +1,29 @@
+//! This module exports the Enso lexer as a TextMate grammar, which editors use to highlight Enso
+//! code consistently with the lexer.
+//!
+//! The scopes of the tokens are given by the annotations of the rules in [`crate::lexer`].
+//!
+//! Text blocks and documentation comments end at the first line that is indented less than their
+//! contents, which the grammar cannot express, so only the first line of their contents is
+//! highlighted with them.
+
+use crate::lexer::EnsoLexer;
+
+use flexer::Definition;
+use flexer::textmate::Grammar;
+
+
+
+// ===============
+// === Grammar ===
+// ===============
+
+/// The name of the scope of Enso source files.
+pub const SCOPE_NAME:&str = "source.enso";
+
+/// Exports the TextMate grammar for Enso, as JSON.
+pub fn grammar() -> String {
+    let lexer   = EnsoLexer::define();
+    let grammar = Grammar::new("Enso",SCOPE_NAME).file_type("enso");
+    grammar.export(lexer.groups())
+}
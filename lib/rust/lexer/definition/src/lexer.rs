@@ -3,15 +3,21 @@
 use crate::prelude::*;
 use flexer::*;
 
+use crate::library::number;
+use crate::library::symbol::Interner;
+use crate::library::symbol::Symbol;
 use crate::library::token::BlockType;
 use crate::library::token::Token;
 use crate::library::token;
 
 use flexer::automata::pattern::Pattern;
+use flexer::diagnostic::Severity;
 use flexer::group::Group;
+use flexer::group::rule::Annotation;
 use flexer::group::Registry;
 use flexer::prelude::logger::Disabled;
 use flexer::prelude::reader;
+use flexer::span::Span;
 use flexer::State as FlexerState;
 use flexer;
 use std::collections::VecDeque;
@@ -28,12 +34,24 @@ type Flexer = flexer::Flexer<State<Logger>,token::Stream,Logger>;
 
 
 
+// =================
+// === Constants ===
+// =================
+
+/// The number of spaces in each level of indentation.
+const INDENT_WIDTH:usize = 4;
+
+
+
 // ==================
 // === Enso Lexer ===
 // ==================
 
 /// The Enso lexer.
-#[derive(Debug)]
+///
+/// Cloning a defined lexer is much cheaper than defining it again, and the clone can be used to
+/// lex on another thread.
+#[derive(Clone,Debug)]
 pub struct EnsoLexer(Flexer);
 
 impl Deref for EnsoLexer {
@@ -93,16 +111,28 @@ impl EnsoLexer {
         mem::take(&mut self.output)
     }
 
-    /// Consume the current match and replace it with the empty string.
-    pub fn consume_current(&mut self) -> String {
+    /// Consume the current match as a symbol, which shares its text with the equal matches before
+    /// it.
+    ///
+    /// The buffer of the current match is kept, so that it does not have to grow again for the
+    /// next match.
+    pub fn consume_current(&mut self) -> Symbol {
+        self.consume_current_from(0)
+    }
+
+    /// Consume the current match from the byte offset `start` onwards as a symbol.
+    pub fn consume_current_from(&mut self, start:usize) -> Symbol {
         debug!(self.logger,"Consume: {self.current_match:?}");
-        mem::take(&mut self.current_match)
+        let current_match  = mem::take(&mut self.current_match);
+        let symbol         = self.interner.intern(&current_match[start..]);
+        self.current_match = current_match;
+        symbol
     }
 
     /// Discard the current match and replace it with the empty string.
     pub fn discard_current(&mut self) {
         debug!(self.logger,"Discard: {self.current_match:?}");
-        self.current_match = default();
+        self.current_match.clear();
     }
 }
 
@@ -143,6 +173,11 @@ impl EnsoLexer {
         Pattern::char(' ').many1()
     }
 
+    /// Match the indentation at the start of a line, including any tabs that it wrongly contains.
+    fn indentation() -> Pattern {
+        Pattern::any_of(" \t").many()
+    }
+
     /// Match a newline.
     ///
     /// This matches both Unix (LF) and Windows (CRLF) styles of newlines. This is particularly
@@ -188,7 +223,7 @@ impl EnsoLexer {
         let op_modifier_check = self.operator_modifier_check;
         let operator          = self.consume_current();
         let offset            = self.offset.consume();
-        let token             = Token::Operator(operator,offset);
+        let token             = Token::Operator(operator,offset).with_span(self.current_span);
         self.append_token(token);
         self.push_state(op_modifier_check);
     }
@@ -198,26 +233,18 @@ impl EnsoLexer {
         let op_suffix_check = self.operator_suffix_check;
         let operator        = self.consume_current();
         let offset          = self.offset.consume();
-        let token           = Token::Operator(operator,offset);
+        let token           = Token::Operator(operator,offset).with_span(self.current_span);
         self.append_token(token);
         self.push_state(op_suffix_check);
     }
 
-    /// Create a grouping operator.
-    fn on_group<R:LazyReader>(&mut self, reader:&mut R) {
-        let operator = self.consume_current();
-        let offset   = self.offset.consume();
-        let token    = Token::Operator(operator,offset);
-        self.append_token(token);
-        self.ident_on_no_error_suffix(reader);
-    }
-
     /// Create an operator modifier.
     fn on_modifier<R:LazyReader>(&mut self, _reader:&mut R) {
         match self.output.pop() {
             Some(token) => match token.shape {
                 token::Shape::Operator(name) => {
-                    let new_token = Token::Modifier(name,token.offset);
+                    let span      = Span::new(token.span.start,self.current_span.end);
+                    let new_token = Token::Modifier(name,token.offset).with_span(span);
                     self.discard_current();
                     self.append_token(new_token);
                 },
@@ -239,15 +266,15 @@ impl EnsoLexer {
         let ops_eq          = &equals | l!("==") | l!(">=") | l!("<=") | l!("!=") | l!("#=");
         let ops_in          = l!("in");
         let ops_dot         = dot | comma | l!("..") | l!("...");
-        let ops_group       = Pattern::any_of(Self::group_chars().as_str());
-        let ops_comment     = c!('#') | l!("##");
-        let ops_no_modifier = &ops_eq | &ops_dot | &ops_comment | &ops_in;
+        let ops_no_modifier = &ops_eq | &ops_dot | &ops_in;
+        let operator        = Annotation::scoped("keyword.operator.enso");
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
-        initial_state.create_rule(&operator_body,  "self.on_operator(reader)");
-        initial_state.create_rule(&ops_no_modifier,"self.on_operator_no_modifier(reader)");
-        initial_state.create_rule(&ops_group,      "self.on_group(reader)");
+        let on_operator      = "self.on_operator(reader)";
+        let on_no_modifier   = "self.on_operator_no_modifier(reader)";
+        initial_state.create_annotated_rule(&operator_body,on_operator,operator.clone());
+        initial_state.create_annotated_rule(&ops_no_modifier,on_no_modifier,operator);
 
         let operator_mod_check_id = lexer.operator_modifier_check;
         let operator_mod_check    = lexer.group_mut(operator_mod_check_id);
@@ -269,7 +296,9 @@ impl EnsoLexer {
 
     /// Create a variable identifier from the current match.
     fn on_variable_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Variable(self.consume_current(),self.offset.consume());
+        let name         = self.consume_current();
+        let offset       = self.offset.consume();
+        let token        = Token::Variable(name,offset).with_span(self.current_span);
         let suffix_check = self.ident_suffix_check;
         self.append_token(token);
         self.push_state(suffix_check);
@@ -277,7 +306,9 @@ impl EnsoLexer {
 
     /// Create a referent identifier from the current match.
     fn on_referent_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Referent(self.consume_current(),self.offset.consume());
+        let name         = self.consume_current();
+        let offset       = self.offset.consume();
+        let token        = Token::Referent(name,offset).with_span(self.current_span);
         let suffix_check = self.ident_suffix_check;
         self.append_token(token);
         self.push_state(suffix_check);
@@ -285,7 +316,9 @@ impl EnsoLexer {
 
     /// Create an external identifier from the current match.
     fn on_external_ident<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::External(self.consume_current(),self.offset.consume());
+        let name         = self.consume_current();
+        let offset       = self.offset.consume();
+        let token        = Token::External(name,offset).with_span(self.current_span);
         let suffix_check = self.ident_suffix_check;
         self.append_token(token);
         self.push_state(suffix_check);
@@ -293,7 +326,7 @@ impl EnsoLexer {
 
     /// Create a blank identifier from the current match.
     fn on_blank<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token        = Token::Blank(self.offset.consume());
+        let token        = Token::Blank(self.offset.consume()).with_span(self.current_span);
         let suffix_check = self.ident_suffix_check;
         self.discard_current();
         self.append_token(token);
@@ -302,7 +335,9 @@ impl EnsoLexer {
 
     /// Tokenize an unexpected error suffix.
     fn ident_on_error_suffix<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token = Token::InvalidSuffix(self.consume_current(),self.offset.consume());
+        let text   = self.consume_current();
+        let offset = self.offset.consume();
+        let token  = Token::InvalidSuffix(text,offset).with_span(self.current_span);
         self.append_token(token);
         self.pop_state();
     }
@@ -327,13 +362,21 @@ impl EnsoLexer {
         let referent_ident = &init_ref_seg >> (&underscore >> &ref_seg).many() >> &ticks;
         let external_ident = &external_start >> external_body.many() >> &ticks;
         let error_suffix   = Pattern::none_of(EnsoLexer::break_chars().as_str()).many1();
+        let variable       = Annotation::scoped("variable.other.enso");
+        let referent       = Annotation::scoped("entity.name.type.enso");
+        let blank          = Annotation::scoped("variable.language.blank.enso");
+        let external       = Annotation::scoped("variable.other.external.enso");
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
-        initial_state.create_rule(&variable_ident,"self.on_variable_ident(reader)");
-        initial_state.create_rule(&referent_ident,"self.on_referent_ident(reader)");
-        initial_state.create_rule(&underscore,    "self.on_blank(reader)");
-        initial_state.create_rule(&external_ident,"self.on_external_ident(reader)");
+        let on_variable      = "self.on_variable_ident(reader)";
+        let on_referent      = "self.on_referent_ident(reader)";
+        let on_blank         = "self.on_blank(reader)";
+        let on_external      = "self.on_external_ident(reader)";
+        initial_state.create_annotated_rule(&variable_ident,on_variable,variable);
+        initial_state.create_annotated_rule(&referent_ident,on_referent,referent);
+        initial_state.create_annotated_rule(&underscore,    on_blank,   blank);
+        initial_state.create_annotated_rule(&external_ident,on_external,external);
 
         let suffix_check_id = lexer.ident_suffix_check;
         let suffix_check    = lexer.group_mut(suffix_check_id);
@@ -353,13 +396,18 @@ impl EnsoLexer {
     fn finalize_explicit_base(&mut self) {
         let number_part_2 = self.number_phase_two;
         self.pop_states_including(number_part_2);
+    }
+
+    /// Triggered when the lexer leaves the state for the second phase of a number.
+    fn on_number_exit(&mut self) {
         self.number_state.reset();
     }
 
     /// Triggered when the lexer matches an integer with an implicit base.
     fn on_integer<R:LazyReader>(&mut self, _reader:&mut R) {
-        let number_phase_2 = self.number_phase_two;
+        let number_phase_2        = self.number_phase_two;
         self.number_state.literal = self.consume_current();
+        self.number_state.start   = self.current_span.start;
         self.push_state(number_phase_2)
     }
 
@@ -367,18 +415,49 @@ impl EnsoLexer {
     fn on_explicit_base<R:LazyReader>(&mut self, _reader:&mut R) {
         let literal               = self.consume_current();
         self.number_state.literal = literal;
+        self.check_explicit_base();
         let offset                = self.offset.consume();
-        let token                 = self.number_state.consume_token(offset);
+        let end                   = self.current_span.end;
+        let token                 = self.number_state.consume_token(offset,end);
         self.append_token(token);
         self.finalize_explicit_base();
     }
 
+    /// Report a base that is not supported, or the digits of the number that are not valid in its
+    /// base.
+    ///
+    /// The number is still lexed as a number, so that a mistyped digit does not change the
+    /// structure of the code around it.
+    fn check_explicit_base(&mut self) {
+        let digits_span = self.current_span;
+        let base        = self.number_state.base.clone();
+        match number::parse_base(&base) {
+            None => {
+                let base_end = digits_span.start - 1;
+                let span     = Span::new(base_end - base.len(),base_end);
+                let error    = number::Error::InvalidBase(base.to_string());
+                self.report_at(Severity::Error,error.to_string(),span);
+            }
+            Some(radix) => {
+                let literal = &self.number_state.literal;
+                let invalid = number::invalid_digits(literal,radix).collect_vec();
+                for (index,digit) in invalid {
+                    let start = digits_span.start + index;
+                    let span  = Span::new(start,start + digit.len_utf8());
+                    let error = number::Error::InvalidDigit{index,digit};
+                    self.report_at(Severity::Error,error.to_string(),span);
+                }
+            }
+        }
+    }
+
     /// Triggered when the lexer has seen an explicit base definition that isn't followed by an
     /// actual number.
     fn on_dangling_base<R:LazyReader>(&mut self, _reader:&mut R) {
+        let span   = Span::new(self.number_state.start,self.current_span.start);
         let base   = self.number_state.consume_base();
         let offset = self.offset.consume();
-        let token  = Token::DanglingBase(base,offset);
+        let token  = Token::DanglingBase(base,offset).with_span(span);
         self.append_token(token);
         self.discard_current();
         self.finalize_explicit_base();
@@ -388,8 +467,10 @@ impl EnsoLexer {
     fn on_decimal<R:LazyReader>(&mut self, _reader:&mut R) {
         let decimal_suffix_check  = self.decimal_suffix_check;
         self.number_state.literal = self.consume_current();
+        self.number_state.start   = self.current_span.start;
         let offset                = self.offset.consume();
-        let token                 = self.number_state.consume_token(offset);
+        let end                   = self.current_span.end;
+        let token                 = self.number_state.consume_token(offset,end);
         self.append_token(token);
         self.push_state(decimal_suffix_check);
     }
@@ -404,7 +485,8 @@ impl EnsoLexer {
     /// Submit an integer token into the lexer.
     fn submit_integer<R:LazyReader>(&mut self, _reader:&mut R) {
         let offset = self.offset.consume();
-        let token  = self.number_state.consume_token(offset);
+        let end    = self.current_span.start;
+        let token  = self.number_state.consume_token(offset,end);
         self.append_token(token);
         self.pop_state();
     }
@@ -414,7 +496,8 @@ impl EnsoLexer {
         let decimal_suffix_check = self.decimal_suffix_check;
         let current_match        = self.consume_current();
         let offset               = self.offset.consume();
-        let token                = Token::InvalidSuffix(current_match,offset);
+        let span                 = self.current_span;
+        let token                = Token::InvalidSuffix(current_match,offset).with_span(span);
         self.append_token(token);
         self.pop_states_including(decimal_suffix_check);
     }
@@ -434,16 +517,18 @@ impl EnsoLexer {
         let arbitrary_digits  = EnsoLexer::ascii_alpha_num().many1();
         let arbitrary_decimal = &arbitrary_digits >> (&point >> &arbitrary_digits).opt();
         let error_suffix      = Pattern::none_of(EnsoLexer::break_chars().as_str()).many1();
+        let number            = Annotation::scoped("constant.numeric.enso");
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
-        initial_state.create_rule(&digits,"self.on_integer(reader)");
-        initial_state.create_rule(&decimal,"self.on_decimal(reader)");
+        initial_state.create_annotated_rule(&decimal,"self.on_decimal(reader)",number.clone());
+        initial_state.create_annotated_rule(&digits,"self.on_integer(reader)",number);
 
         let number_phase_2_id = lexer.number_phase_two;
         let number_phase_2    = lexer.groups_mut().group_mut(number_phase_2_id);
         number_phase_2.create_rule(&underscore,       "self.seen_base(reader)");
         number_phase_2.create_rule(&Pattern::always(),"self.submit_integer(reader)");
+        number_phase_2.set_on_exit("self.on_number_exit()");
 
         let seen_base_id = lexer.number_seen_base;
         let seen_base    = lexer.groups_mut().group_mut(seen_base_id);
@@ -464,9 +549,643 @@ impl EnsoLexer {
 #[allow(dead_code)]
 impl EnsoLexer {
 
+    /// Triggered when the opening quote of a raw text literal is seen.
+    fn on_text_raw_line_start<R:LazyReader>(&mut self, reader:&mut R) {
+        let text_raw_line = self.text_raw_line;
+        self.text_on_line_start(token::TextStyle::RawLine,text_raw_line,reader);
+    }
+
+    /// Triggered when the opening quote of a format text literal is seen.
+    fn on_text_format_line_start<R:LazyReader>(&mut self, reader:&mut R) {
+        let text_format_line = self.text_format_line;
+        self.text_on_line_start(token::TextStyle::FormatLine,text_format_line,reader);
+    }
+
+    /// Common functionality for the start of both styles of text literal.
+    fn text_on_line_start<R:LazyReader>
+    (&mut self, style:token::TextStyle, state:group::Identifier, _reader:&mut R) {
+        let offset = self.offset.consume();
+        let span   = self.current_span;
+        self.discard_current();
+        self.text_state.begin_literal(style,offset,span);
+        self.push_state(state);
+    }
+
+    /// Triggered when a raw text block is opened.
+    fn on_text_raw_block_start<R:LazyReader>(&mut self, reader:&mut R) {
+        self.text_on_block_start(token::TextStyle::RawBlock,reader);
+    }
+
+    /// Triggered when a format text block is opened.
+    fn on_text_format_block_start<R:LazyReader>(&mut self, reader:&mut R) {
+        self.text_on_block_start(token::TextStyle::FormatBlock,reader);
+    }
+
+    /// Common functionality for the start of both styles of text block.
+    ///
+    /// The match consists of the opening quotes followed by optional spaces and then a line ending
+    /// or the end of the file.
+    fn text_on_block_start<R:LazyReader>(&mut self, style:token::TextStyle, _reader:&mut R) {
+        let text_block_newline = self.text_block_newline;
+        let opening            = &self.current_match;
+        let line_ending        = if opening.ends_with("\r\n") {
+            token::LineEnding::CRLF
+        } else if opening.ends_with('\n') {
+            token::LineEnding::LF
+        } else {
+            token::LineEnding::None
+        };
+        let quotes_len     = 3;
+        let opening_offset = opening.chars().count() - quotes_len - line_ending.size();
+        let offset         = self.offset.consume();
+        let span           = self.current_span;
+        self.discard_current();
+        self.text_state.begin_block(style,offset,span,opening_offset,line_ending);
+        self.push_state(text_block_newline);
+    }
+
+    /// Triggered at the start of a line in a text block, once its indentation has been matched.
+    fn on_text_block_line_start<R:LazyReader>(&mut self, reader:&mut R) {
+        if self.text_begin_line(reader) {
+            let line_state = match self.text_state.current_mut().style {
+                token::TextStyle::FormatBlock => self.text_format_block,
+                _                             => self.text_raw_block,
+            };
+            self.push_state(line_state);
+        } else {
+            self.text_block_end(reader);
+        }
+    }
+
+    /// Begin a line of a text block or documentation comment, returning whether the line is part
+    /// of it.
+    ///
+    /// A line that is indented less than the margin ends the block, and is then lexed again as
+    /// part of the enclosing block.
+    fn text_begin_line<R:LazyReader>(&mut self, reader:&mut R) -> bool {
+        let indent       = self.current_match.chars().count();
+        let line_start   = self.current_span.end;
+        let block_indent = self.block_state.current().indent;
+        let text_state   = self.text_state.current_mut();
+        let is_in_block  = text_state.begin_line(indent,block_indent,line_start);
+        if !is_in_block {
+            self.rewind_current_match(reader);
+        }
+        self.discard_current();
+        is_in_block
+    }
+
+    /// Triggered on a blank line in a text block that ends in a unix-style line ending.
+    fn on_text_block_blank_lf<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.text_block_blank_line(token::LineEnding::LF);
+    }
+
+    /// Triggered on a blank line in a text block that ends in a windows-style line ending.
+    fn on_text_block_blank_crlf<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.text_block_blank_line(token::LineEnding::CRLF);
+    }
+
+    /// Submit a blank line in a text block.
+    fn text_block_blank_line(&mut self, line_ending:token::LineEnding) {
+        let offset = self.current_match.chars().count() - line_ending.size();
+        let end    = self.current_span.end;
+        let span   = Span::new(end - line_ending.size(),end);
+        self.discard_current();
+        self.text_state.current_mut().push_blank_line(offset,line_ending,span);
+    }
+
+    /// Triggered when a line in a text block ends in a unix-style line ending.
+    fn on_text_block_lf<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        self.text_block_line_end(token::LineEnding::LF);
+    }
+
+    /// Triggered when a line in a text block ends in a windows-style line ending.
+    fn on_text_block_crlf<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.discard_current();
+        self.text_block_line_end(token::LineEnding::CRLF);
+    }
+
+    /// Submit the current line of a text block, whose `line_ending` is the current match unless
+    /// the line ends the file.
+    fn text_block_line_end(&mut self, line_ending:token::LineEnding) {
+        let line_ending_span = match line_ending {
+            token::LineEnding::None => None,
+            _                       => Some(self.current_span),
+        };
+        self.text_state.current_mut().end_line(line_ending,line_ending_span);
+        self.pop_state();
+    }
+
+    /// Submit the current text block.
+    fn text_block_end<R:LazyReader>(&mut self, reader:&mut R) {
+        let text_block_newline = self.text_block_newline;
+        self.text_layout_end(text_block_newline,TextState::into_block_token,reader);
+    }
+
+    /// Submit the text block or documentation comment that is lexed in `newline_state`, using
+    /// `into_token` to create its token.
+    ///
+    /// The line ending of the last line in the block, as well as any blank lines that follow it,
+    /// belong to the enclosing block, so they are handed over to the block lexing state.
+    fn text_layout_end<R:LazyReader>
+    ( &mut self
+    , newline_state : group::Identifier
+    , into_token    : fn(TextState) -> Token
+    , reader        : &mut R
+    ) {
+        self.pop_states_including(newline_state);
+        let mut literal = match self.text_state.end_literal() {
+            Some(literal) => literal,
+            None          => unreachable_panic!("A text block is always being lexed here."),
+        };
+        let (line_ending,blank_lines) = literal.split_off_trailing_lines();
+        self.append_token(into_token(literal));
+        if line_ending != token::LineEnding::None {
+            self.block_state.push_line_ending(line_ending);
+            self.block_on_line_ending(reader);
+            for line in blank_lines {
+                if let token::Shape::BlankLine(line_ending) = line.shape {
+                    self.offset.increase(line.offset,0);
+                    self.block_state.push_line_ending(line_ending);
+                    self.block_in_empty_line(line.span,reader);
+                }
+            }
+        }
+    }
+
+    /// Triggered when the file ends on a line of a text block or documentation comment.
+    fn on_text_block_line_eof<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.text_block_line_end(token::LineEnding::None);
+    }
+
+    /// Triggered when the file ends inside an interpolated expression.
+    fn on_text_splice_eof<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.text_splice_unclosed();
+    }
+
+    /// Triggered on a segment of text that is taken as written.
+    fn on_text_segment_raw<R:LazyReader>(&mut self, _reader:&mut R) {
+        let token = Token::TextSegmentRaw(self.consume_current(),0).with_span(self.current_span);
+        self.text_state.push_segment(token);
+    }
+
+    /// Triggered on an escape sequence consisting of a single character.
+    fn on_text_escape_literal<R:LazyReader>(&mut self, _reader:&mut R) {
+        let repr  = self.consume_current();
+        let value = repr.chars().nth(1).and_then(EnsoLexer::literal_escape_value);
+        self.text_push_escape(token::EscapeStyle::Literal,repr,value);
+    }
+
+    /// Triggered on an escape sequence giving a character code in two hexadecimal digits.
+    fn on_text_escape_byte<R:LazyReader>(&mut self, _reader:&mut R) {
+        let repr  = self.consume_current();
+        let code  = u32::from_str_radix(&repr[2..],16).ok();
+        let value = code.and_then(std::char::from_u32);
+        self.text_push_escape(token::EscapeStyle::Byte,repr,value);
+    }
+
+    /// Triggered on an escape sequence giving a unicode code point.
+    ///
+    /// The code point has to be at most six digits long, and has to denote a unicode scalar value
+    /// for the escape to be valid.
+    fn on_text_escape_unicode<R:LazyReader>(&mut self, _reader:&mut R) {
+        let repr   = self.consume_current();
+        let digits = &repr[3..repr.len()-1];
+        let code   = if digits.len() > 6 { None } else { u32::from_str_radix(digits,16).ok() };
+        let value  = code.and_then(std::char::from_u32);
+        self.text_push_escape(token::EscapeStyle::Unicode,repr,value);
+    }
+
+    /// Triggered on an escape sequence that is not valid.
+    fn on_text_escape_invalid<R:LazyReader>(&mut self, _reader:&mut R) {
+        let span  = self.current_span;
+        let token = Token::TextSegmentInvalidEscape(self.consume_current(),0).with_span(span);
+        self.text_state.push_segment(token);
+    }
+
+    /// Submit an escape sequence, which is invalid if it has no `value`.
+    fn text_push_escape(&mut self, style:token::EscapeStyle, repr:Symbol, value:Option<char>) {
+        let token = match value {
+            Some(value) => Token::TextSegmentEscape(style,repr,value,0),
+            None        => Token::TextSegmentInvalidEscape(repr,0),
+        };
+        self.text_state.push_segment(token.with_span(self.current_span));
+    }
+
+    /// Triggered when the closing quote of a text literal is seen.
+    fn on_text_line_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let close = self.current_span;
+        self.discard_current();
+        if let Some(literal) = self.text_state.end_literal() {
+            self.append_token(literal.into_token(close));
+        }
+        self.pop_state();
+    }
+
+    /// Triggered when a text literal reaches the end of its line without being closed.
+    fn on_text_line_unclosed<R:LazyReader>(&mut self, _reader:&mut R) {
+        if let Some(literal) = self.text_state.end_literal() {
+            self.append_token(literal.into_unclosed_token());
+        }
+        self.pop_state();
+    }
+
+    /// Triggered when the opening backtick of an interpolated expression is seen.
+    fn on_text_splice_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text_splice = self.text_splice;
+        self.text_state.current_mut().splice_span = self.current_span;
+        self.discard_current();
+        self.push_tokens();
+        self.offset.push();
+        self.group_state.begin_scope(false);
+        self.push_state(text_splice);
+    }
+
+    /// Triggered when the closing backtick of an interpolated expression is seen.
+    fn on_text_splice_end<R:LazyReader>(&mut self, _reader:&mut R) {
+        let close = self.current_span;
+        self.discard_current();
+        let trailing_offset = self.offset.consume();
+        let tokens          = self.text_splice_end();
+        let span            = self.text_state.current_mut().splice_span.cover(close);
+        let token           = Token::TextSegmentInterpolate(tokens,trailing_offset,0);
+        self.text_state.push_segment(token.with_span(span));
+    }
+
+    /// Triggered when an interpolated expression reaches the end of its line without being
+    /// closed.
+    ///
+    /// The line ending is left for the enclosing text literal, which is unclosed as well.
+    fn on_text_splice_unclosed<R:LazyReader>(&mut self, reader:&mut R) {
+        self.rewind_current_match(reader);
+        self.text_splice_unclosed();
+    }
+
+    /// Submit the current interpolated expression as unclosed.
+    fn text_splice_unclosed(&mut self) {
+        self.discard_current();
+        let tokens = self.text_splice_end();
+        let open   = self.text_state.current_mut().splice_span;
+        let span   = Token::covering_span(&tokens).map_or(open,|span| open.cover(span));
+        let token  = Token::TextSegmentUnclosedInterpolate(tokens,0).with_span(span);
+        self.text_state.push_segment(token);
+    }
+
+    /// Leave an interpolated expression, returning the tokens lexed inside it.
+    ///
+    /// The groups that are still open inside the expression are closed as unclosed.
+    fn text_splice_end(&mut self) -> Vec<Token> {
+        self.group_end_unclosed(0);
+        self.group_state.end_scope();
+        let tokens = self.consume_tokens();
+        self.pop_tokens();
+        self.offset.pop();
+        self.pop_state();
+        tokens.into()
+    }
+
+    /// The character that the single-character escape `\escape` stands for, if any.
+    fn literal_escape_value(escape:char) -> Option<char> {
+        match escape {
+            '0'  => Some('\0'),
+            'a'  => Some('\x07'),
+            'b'  => Some('\x08'),
+            'f'  => Some('\x0C'),
+            'n'  => Some('\n'),
+            'r'  => Some('\r'),
+            't'  => Some('\t'),
+            'v'  => Some('\x0B'),
+            'e'  => Some('\x1B'),
+            '\\' => Some('\\'),
+            '"'  => Some('"'),
+            '\'' => Some('\''),
+            '`'  => Some('`'),
+            _    => None,
+        }
+    }
+
+    /// Match a hexadecimal digit.
+    fn hex_digit() -> Pattern {
+        EnsoLexer::ascii_digit() | Pattern::range('a'..='f') | Pattern::range('A'..='F')
+    }
+
     /// Define the rules for lexing Enso text literals.
-    fn add_text_rules(_lexer:&mut EnsoLexer) {
-        // TODO [AA] Write the lexing rules for text literals.
+    fn add_text_rules(lexer:&mut EnsoLexer) {
+        let raw_quote      = c!('"');
+        let format_quote   = c!('\'');
+        let raw_segment    = Pattern::none_of("\"\\\r\n").many1();
+        let format_segment = Pattern::none_of("'`\\\r\n").many1();
+        let block_segment  = Pattern::none_of("\\\r\n").many1();
+        let splice_segment = Pattern::none_of("`\\\r\n").many1();
+        let block_opening  = EnsoLexer::spaces().opt() >> (EnsoLexer::newline() | Pattern::eof());
+        let raw_block      = l!("\"\"\"") >> &block_opening;
+        let format_block   = l!("'''") >> &block_opening;
+        let backtick       = c!('`');
+        let raw_scope      = "string.quoted.double.enso";
+        let format_scope   = "string.quoted.single.enso";
+        let block_scope    = "string.quoted.triple.enso";
+        let raw_line_id    = lexer.text_raw_line;
+        let format_line_id = lexer.text_format_line;
+        let splice_id      = lexer.text_splice;
+        let raw_text       = Annotation::scoped(raw_scope).entering(raw_line_id);
+        let format_text    = Annotation::scoped(format_scope).entering(format_line_id);
+        let splice         = Annotation::scoped("meta.embedded.line.enso").entering(splice_id);
+        let splice_end     = Annotation::scoped("punctuation.section.embedded.end.enso").exiting();
+        let quote_end      = Annotation::scoped("punctuation.definition.string.end.enso").exiting();
+
+        let raw_block_id      = lexer.text_raw_block;
+        let format_block_id   = lexer.text_format_block;
+        let raw_block_text    = Annotation::scoped(block_scope).entering(raw_block_id);
+        let format_block_text = Annotation::scoped(block_scope).entering(format_block_id);
+        let block_line_end    = Annotation::default().exiting();
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        let on_raw_start     = "self.on_text_raw_line_start(reader)";
+        let on_format_start  = "self.on_text_format_line_start(reader)";
+        let on_raw_block     = "self.on_text_raw_block_start(reader)";
+        let on_format_block  = "self.on_text_format_block_start(reader)";
+        initial_state.create_annotated_rule(&raw_quote,on_raw_start,raw_text);
+        initial_state.create_annotated_rule(&format_quote,on_format_start,format_text);
+        initial_state.create_annotated_rule(&raw_block,on_raw_block,raw_block_text);
+        initial_state.create_annotated_rule(&format_block,on_format_block,format_block_text);
+
+        let raw_line    = (raw_line_id,&raw_quote,&raw_segment);
+        let format_line = (format_line_id,&format_quote,&format_segment);
+        for &(state_id,quote,segment) in &[raw_line,format_line] {
+            let state = lexer.group_mut(state_id);
+            state.create_annotated_rule(quote,"self.on_text_line_end(reader)",quote_end.clone());
+            EnsoLexer::add_text_segment_rules(lexer,state_id,segment);
+            let state = lexer.group_mut(state_id);
+            state.create_rule(&Pattern::always(),"self.on_text_line_unclosed(reader)");
+            state.set_on_eof("self.on_text_line_unclosed(reader)");
+        }
+
+        let blocks = [(raw_block_id,&block_segment),(format_block_id,&splice_segment)];
+        for &(state_id,segment) in &blocks {
+            EnsoLexer::add_text_segment_rules(lexer,state_id,segment);
+            let state   = lexer.group_mut(state_id);
+            let on_lf   = "self.on_text_block_lf(reader)";
+            let on_crlf = "self.on_text_block_crlf(reader)";
+            state.create_annotated_rule(&c!('\n'),   on_lf,  block_line_end.clone());
+            state.create_annotated_rule(&l!("\r\n"), on_crlf,block_line_end.clone());
+            state.create_rule(&Pattern::eof(),"self.on_eof(reader)");
+            state.set_on_eof("self.on_text_block_line_eof(reader)");
+        }
+
+        for &state_id in &[format_line_id,format_block_id] {
+            let state = lexer.group_mut(state_id);
+            let rule  = "self.on_text_splice_start(reader)";
+            state.create_annotated_rule(&backtick,rule,splice.clone());
+        }
+
+        let opt_spaces         = EnsoLexer::spaces().opt();
+        let blank_lf           = &opt_spaces >> c!('\n');
+        let blank_crlf         = &opt_spaces >> l!("\r\n");
+        let eof_line           = &opt_spaces >> Pattern::eof();
+        let block_newline_id   = lexer.text_block_newline;
+        let text_block_newline = lexer.group_mut(block_newline_id);
+        text_block_newline.create_rule(&opt_spaces,"self.on_text_block_line_start(reader)");
+        text_block_newline.create_rule(&blank_lf,  "self.on_text_block_blank_lf(reader)");
+        text_block_newline.create_rule(&blank_crlf,"self.on_text_block_blank_crlf(reader)");
+        text_block_newline.create_rule(&eof_line,  "self.on_eof(reader)");
+        text_block_newline.set_on_eof("self.text_block_end(reader)");
+
+        let text_splice = lexer.group_mut(splice_id);
+        text_splice.create_annotated_rule(&backtick,"self.on_text_splice_end(reader)",splice_end);
+        text_splice.create_rule(&EnsoLexer::newline(),"self.on_text_splice_unclosed(reader)");
+        text_splice.create_rule(&Pattern::eof(),"self.on_eof(reader)");
+        text_splice.set_on_eof("self.on_text_splice_eof(reader)");
+    }
+
+    /// Define the rules for the segments and escape sequences of text in the state `state_id`.
+    fn add_text_segment_rules(lexer:&mut EnsoLexer, state_id:group::Identifier, segment:&Pattern) {
+        let hex_digit      = EnsoLexer::hex_digit();
+        let escape_literal = c!('\\') >> Pattern::any_of("0abfnrtve\\\"'`");
+        let escape_byte    = l!("\\x") >> &hex_digit >> &hex_digit;
+        let escape_unicode = l!("\\u{") >> hex_digit.many1() >> c!('}');
+        let escape_invalid = c!('\\') >> Pattern::none_of("\r\n").opt();
+        let escape         = Annotation::scoped("constant.character.escape.enso");
+        let invalid_escape = Annotation::scoped("invalid.illegal.escape.enso");
+
+        let state      = lexer.group_mut(state_id);
+        let on_literal = "self.on_text_escape_literal(reader)";
+        let on_byte    = "self.on_text_escape_byte(reader)";
+        let on_unicode = "self.on_text_escape_unicode(reader)";
+        let on_invalid = "self.on_text_escape_invalid(reader)";
+        state.create_rule(segment,"self.on_text_segment_raw(reader)");
+        state.create_annotated_rule(&escape_literal,on_literal,escape.clone());
+        state.create_annotated_rule(&escape_byte,   on_byte,   escape.clone());
+        state.create_annotated_rule(&escape_unicode,on_unicode,escape);
+        state.create_annotated_rule(&escape_invalid,on_invalid,invalid_escape);
+    }
+}
+
+
+// === Comments ===
+
+/// The set of rules for lexing comments in the Enso language.
+#[allow(dead_code)]
+impl EnsoLexer {
+
+    /// Triggered on a disable comment, which extends to the end of the line.
+    fn on_disable_comment<R:LazyReader>(&mut self, _reader:&mut R) {
+        let text  = self.consume_current_from(1);
+        let token = Token::DisableComment(text,self.offset.consume()).with_span(self.current_span);
+        self.append_token(token);
+    }
+
+    /// Triggered on the `##` that opens a documentation comment.
+    ///
+    /// Documentation comments are lexed like raw text blocks, except that their first line
+    /// directly follows the `##` and that they contain no escape sequences.
+    fn on_doc_comment_start<R:LazyReader>(&mut self, _reader:&mut R) {
+        let doc_comment_newline = self.doc_comment_newline;
+        let doc_comment_line    = self.doc_comment_line;
+        let style               = token::TextStyle::RawBlock;
+        let offset              = self.offset.consume();
+        let span                = self.current_span;
+        self.discard_current();
+        self.text_state.begin_block(style,offset,span,0,token::LineEnding::None);
+        self.push_state(doc_comment_newline);
+        self.push_state(doc_comment_line);
+    }
+
+    /// Triggered at the start of a line following a documentation comment, once its indentation
+    /// has been matched.
+    fn on_doc_comment_line_start<R:LazyReader>(&mut self, reader:&mut R) {
+        let doc_comment_line = self.doc_comment_line;
+        if self.text_begin_line(reader) {
+            self.push_state(doc_comment_line);
+        } else {
+            self.doc_comment_end(reader);
+        }
+    }
+
+    /// Submit the current documentation comment.
+    fn doc_comment_end<R:LazyReader>(&mut self, reader:&mut R) {
+        let doc_comment_newline = self.doc_comment_newline;
+        self.text_layout_end(doc_comment_newline,TextState::into_doc_comment_token,reader);
+    }
+
+    /// The rules for lexing Enso comments.
+    fn add_comment_rules(lexer:&mut EnsoLexer) {
+        let line_body       = Pattern::none_of("\r\n").many1();
+        let disable_comment = c!('#') >> (Pattern::none_of("#=\r\n") >> line_body.opt()).opt();
+        let doc_comment     = l!("##");
+        let opt_spaces      = EnsoLexer::spaces().opt();
+        let blank_lf        = &opt_spaces >> c!('\n');
+        let blank_crlf      = &opt_spaces >> l!("\r\n");
+        let eof_line        = &opt_spaces >> Pattern::eof();
+        let doc_line_id     = lexer.doc_comment_line;
+        let disable         = Annotation::scoped("comment.line.number-sign.enso");
+        let documentation   = Annotation::scoped("comment.block.documentation.enso");
+        let documentation   = documentation.entering(doc_line_id);
+        let doc_line_end    = Annotation::default().exiting();
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        let on_disable       = "self.on_disable_comment(reader)";
+        let on_doc_start     = "self.on_doc_comment_start(reader)";
+        initial_state.create_annotated_rule(&disable_comment,on_disable,disable);
+        initial_state.create_annotated_rule(&doc_comment,on_doc_start,documentation);
+
+        let doc_comment_line = lexer.group_mut(doc_line_id);
+        let on_lf            = "self.on_text_block_lf(reader)";
+        let on_crlf          = "self.on_text_block_crlf(reader)";
+        doc_comment_line.create_rule(&line_body,"self.on_text_segment_raw(reader)");
+        doc_comment_line.create_annotated_rule(&c!('\n'),   on_lf,  doc_line_end.clone());
+        doc_comment_line.create_annotated_rule(&l!("\r\n"), on_crlf,doc_line_end);
+        doc_comment_line.create_rule(&Pattern::eof(),"self.on_eof(reader)");
+        doc_comment_line.set_on_eof("self.on_text_block_line_eof(reader)");
+
+        let doc_comment_newline_id = lexer.doc_comment_newline;
+        let doc_comment_newline    = lexer.group_mut(doc_comment_newline_id);
+        doc_comment_newline.create_rule(&opt_spaces,"self.on_doc_comment_line_start(reader)");
+        doc_comment_newline.create_rule(&blank_lf,  "self.on_text_block_blank_lf(reader)");
+        doc_comment_newline.create_rule(&blank_crlf,"self.on_text_block_blank_crlf(reader)");
+        doc_comment_newline.create_rule(&eof_line,  "self.on_eof(reader)");
+        doc_comment_newline.set_on_eof("self.doc_comment_end(reader)");
+    }
+}
+
+
+// === Groups ===
+
+/// The set of rules for lexing groups in the Enso language.
+#[allow(dead_code)]
+impl EnsoLexer {
+
+    /// Triggered on an opening bracket, which begins a group.
+    fn on_group_open<R:LazyReader>(&mut self, _reader:&mut R) {
+        let open   = self.consume_bracket();
+        let offset = self.offset.consume();
+        let span   = self.current_span;
+        self.group_state.begin_group(open,offset,span);
+        self.push_tokens();
+    }
+
+    /// Triggered on a closing bracket.
+    ///
+    /// The bracket closes the innermost group in the current scope that it matches, and the
+    /// groups opened inside that one are left unclosed. If no group in the current scope matches,
+    /// the bracket may close a group opened on the line that the current block belongs to, which
+    /// ends the block, along with any blocks between the two. When the bracket starts its line in
+    /// the block, the line ending before it and the indentation of its line belong to the group. A
+    /// bracket that matches no group is kept as an unmatched bracket.
+    fn on_group_close<R:LazyReader>(&mut self, reader:&mut R) {
+        let close = self.consume_bracket();
+        match self.group_state.find_enclosing_group(close) {
+            Some((blocks,depth)) => {
+                let starts_line         = self.group_state.depth() == 0 && self.output.is_empty();
+                let mut line_ending     = token::LineEnding::None;
+                let mut trailing_offset = self.offset.consume();
+                self.block_state.shift_line_endings(blocks);
+                if blocks > 0 && starts_line {
+                    trailing_offset += self.block_state.current().indent;
+                    line_ending      = self.block_state.pop_line_ending();
+                }
+                for _ in 0..blocks {
+                    self.block_submit(reader);
+                }
+                self.group_close(close,depth,line_ending,trailing_offset);
+            }
+            None => {
+                let message = match self.group_state.current() {
+                    Some(group) => format!(
+                        "Expected `{}` to close `{}`, but found `{}`.",
+                        group.closing_bracket(),group.open,close
+                    ),
+                    None => format!("The bracket `{}` does not close an open bracket.",close),
+                };
+                self.report(Severity::Error,message);
+                let offset = self.offset.consume();
+                let token  = Token::UnmatchedBracket(close,offset).with_span(self.current_span);
+                self.append_token(token);
+            }
+        }
+    }
+
+    /// Close the group at `depth` in the current scope with the `close` bracket, leaving the groups
+    /// opened inside it unclosed.
+    fn group_close
+    ( &mut self
+    , close           : char
+    , depth           : usize
+    , line_ending     : token::LineEnding
+    , trailing_offset : usize
+    ) {
+        self.group_end_unclosed(depth + 1);
+        let (group,tokens) = self.group_end();
+        let (open,offset)  = (group.open,group.offset);
+        let span           = group.span.cover(self.current_span);
+        let token          = Token::Group(open,tokens,line_ending,trailing_offset,close,offset);
+        self.append_token(token.with_span(span));
+    }
+
+    /// Consume the current match, which is a single bracket.
+    fn consume_bracket(&mut self) -> char {
+        match self.current_match.chars().next() {
+            Some(bracket) => bracket,
+            None          => unreachable_panic!("A bracket is always matched here."),
+        }
+    }
+
+    /// Leave the current group, returning its state and the tokens lexed inside it.
+    fn group_end(&mut self) -> (GroupState,Vec<Token>) {
+        let tokens = self.consume_tokens();
+        self.pop_tokens();
+        let group = match self.group_state.end_group() {
+            Some(group) => group,
+            None        => unreachable_panic!("A group is always open here."),
+        };
+        (group,tokens.into())
+    }
+
+    /// Submit the groups of the current scope beyond the outermost `depth` of them as unclosed.
+    fn group_end_unclosed(&mut self, depth:usize) {
+        while self.group_state.depth() > depth {
+            let (group,tokens) = self.group_end();
+            let message        = format!("The bracket `{}` is never closed.",group.open);
+            self.report_at(Severity::Error,message,group.span);
+            let open  = group.span;
+            let span  = Token::covering_span(&tokens).map_or(open,|span| open.cover(span));
+            let token = Token::UnclosedGroup(group.open,tokens,group.offset).with_span(span);
+            self.append_token(token);
+        }
+    }
+
+    /// The rules for lexing Enso groups.
+    fn add_group_rules(lexer:&mut EnsoLexer) {
+        let open  = Pattern::any_of("([{");
+        let close = Pattern::any_of(")]}");
+        let group = Annotation::scoped("punctuation.section.group.enso");
+
+        let initial_state_id = lexer.initial_state;
+        let initial_state    = lexer.group_mut(initial_state_id);
+        initial_state.create_annotated_rule(&open,"self.on_group_open(reader)",group.clone());
+        initial_state.create_annotated_rule(&close,"self.on_group_close(reader)",group);
     }
 }
 
@@ -498,9 +1217,14 @@ impl EnsoLexer {
     }
 
     /// Transitions the lexer into a state in which it knows it is lexing a block line.
+    ///
+    /// The indentation is only checked once the line turns out not to be blank, so it is
+    /// remembered until then.
     fn block_in_line<R:LazyReader>(&mut self, _reader:&mut R) {
         let indent_len = self.current_match.chars().count();
         self.offset.increase(indent_len,0);
+        self.block_state.indent_span    = self.current_span;
+        self.block_state.indent_has_tab = self.current_match.contains('\t');
         let in_block_line = self.in_block_line;
         self.push_state(in_block_line);
     }
@@ -509,6 +1233,7 @@ impl EnsoLexer {
     fn block_on_non_empty_line<R:LazyReader>(&mut self, reader:&mut R) {
         let block_newline = self.block_newline;
         self.pop_states_including(block_newline);
+        self.block_check_tabs();
 
         match self.offset.current.cmp(&self.block_state.current().indent) {
             Ordering::Equal => {
@@ -517,6 +1242,7 @@ impl EnsoLexer {
             },
             Ordering::Greater => {
                 let new_indent = self.offset.consume();
+                self.block_check_indent_width(new_indent);
                 self.begin_block(new_indent,reader);
             },
             Ordering::Less => {
@@ -526,16 +1252,43 @@ impl EnsoLexer {
         }
     }
 
+    /// Report any tabs in the indentation of the current line.
+    ///
+    /// Each tab counts as a single column of indentation, so that the lines still form blocks,
+    /// even though those blocks are unlikely to be the ones that were intended.
+    fn block_check_tabs(&mut self) {
+        if self.block_state.indent_has_tab {
+            let span = self.block_state.indent_span;
+            self.report_at(Severity::Error,"Indentation must use spaces, not tabs.",span);
+        }
+    }
+
+    /// Report an `indent` that is not a whole number of indentation levels.
+    ///
+    /// Indentation containing tabs has already been reported, so it is not reported again.
+    fn block_check_indent_width(&mut self, indent:usize) {
+        let extra_spaces = indent % INDENT_WIDTH;
+        if !self.block_state.indent_has_tab && extra_spaces != 0 {
+            let span    = self.block_state.indent_span;
+            let message = format!(
+                "Indentation of {} spaces is not a multiple of {}.",indent,INDENT_WIDTH
+            );
+            self.report_at(Severity::Warning,message,span);
+        }
+    }
+
     /// Triggered when lexing a block line that is empty and ends in a unix-style line ending.
     fn block_on_empty_lf_line<R:LazyReader>(&mut self, reader:&mut R) {
+        let span = self.current_span;
         self.block_state.push_line_ending(token::LineEnding::LF);
-        self.block_in_empty_line(reader);
+        self.block_in_empty_line(span,reader);
     }
 
     /// Triggered when lexing a block line that is empty and ends in a windows-style line ending.
     fn block_on_empty_crlf_line<R:LazyReader>(&mut self, reader:&mut R) {
+        let span = self.current_span;
         self.block_state.push_line_ending(token::LineEnding::CRLF);
-        self.block_in_empty_line(reader);
+        self.block_in_empty_line(span,reader);
     }
 
     /// Begin a new block.
@@ -543,36 +1296,32 @@ impl EnsoLexer {
         let is_orphan = self.output.is_empty();
         self.push_tokens();
         self.block_state.begin_block(block_indent,is_orphan);
+        self.group_state.begin_scope(true);
     }
 
-    /// Triggered when lexing an empty line in a block.
-    fn block_in_empty_line<R:LazyReader>(&mut self, reader:&mut R) {
+    /// Triggered when lexing an empty line in a block, which ends in the line ending at `span`.
+    fn block_in_empty_line<R:LazyReader>(&mut self, span:Span, reader:&mut R) {
         self.block_submit_line(reader);
         let offset        = self.offset.consume();
         let block_newline = self.block_newline;
         self.pop_states_until(block_newline);
-        self.block_state.push_empty_line(offset);
-    }
-
-    /// Triggered when lexing a line in a block that ends a file.
-    fn block_in_eof_line<R:LazyReader>(&mut self, reader:&mut R) {
-        let initial_state = self.initial_state;
-        self.pop_states_until(initial_state);
-        self.on_eof(reader);
+        self.block_state.push_empty_line(offset,span);
     }
 
     /// Triggered when beginning a top-level block.
     fn block_begin_top_level<R:LazyReader>(&mut self, reader:&mut R) {
-        let matched_bookmark = self.bookmarks.matched_bookmark;
-        let block_newline    = self.block_newline;
+        let block_newline = self.block_newline;
         let initial_state = self.initial_state;
-        self.bookmarks.rewind(matched_bookmark,reader);
+        self.rewind_current_match(reader);
         self.offset.push();
         self.pop_states_until(initial_state);
         self.push_state(block_newline);
     }
 
     /// Triggered when a block is ended.
+    ///
+    /// When `new_indent` does not match the indentation of any enclosing block, the line is
+    /// reported and starts a new block that is marked as invalid.
     fn on_block_end<R:LazyReader>(&mut self, new_indent:usize, reader:&mut R) {
         if self.block_state.seen_newline {
             while new_indent < self.block_state.current().indent {
@@ -580,6 +1329,11 @@ impl EnsoLexer {
             }
             if new_indent > self.block_state.current().indent {
                 info!(self.logger,"Block with invalid indentation.");
+                let span    = self.block_state.indent_span;
+                let message = format!(
+                    "Indentation of {} does not match any enclosing block.",new_indent
+                );
+                self.report_at(Severity::Error,message,span);
                 self.begin_block(new_indent, reader);
                 self.block_state.current_mut().is_valid = false;
             } else {
@@ -598,11 +1352,15 @@ impl EnsoLexer {
     }
 
     /// Submit a block to the token stream of the lexer.
+    ///
+    /// A block without any lines is left out, as it only happens when a closing bracket ends the
+    /// block on its first line, in which case that line belongs to the group of the bracket.
     fn block_submit<R:LazyReader>(&mut self, reader:&mut R) {
         let mut block = self.build_block(reader);
         self.pop_tokens();
         self.offset.pop();
         self.block_state.end_block();
+        self.group_state.end_scope();
 
         if let Some(Token{shape:token::Shape::Operator(_),..}) = self.last_token() {
             if let token::Shape::Block {indent,lines,..} = block.shape {
@@ -610,14 +1368,20 @@ impl EnsoLexer {
             }
         }
 
-        self.append_token(block);
+        let is_empty = matches!(&block.shape,token::Shape::Block{lines,..} if lines.is_empty());
+        if !is_empty {
+            self.append_token(block);
+        }
         self.offset.push();
     }
 
     /// Submit a line in a block.
     ///
-    /// It should be noted that lines that have content in blocks cannot have an offset.
+    /// Groups only extend past the end of a line into the blocks that belong to the line, so those
+    /// that are still open when it is submitted are closed as unclosed. It should be noted that
+    /// lines that have content in blocks cannot have an offset.
     fn block_submit_line<R:LazyReader>(&mut self, _reader:&mut R) {
+        self.group_end_unclosed(0);
         if self.block_state.seen_newline {
             if !self.output.is_empty() {
                 let token_stream = self.consume_tokens();
@@ -644,11 +1408,12 @@ impl EnsoLexer {
 
     /// The rule definitions for lexing blocks in Enso.
     fn add_block_rules(lexer:&mut EnsoLexer) {
-        let spaces     = EnsoLexer::spaces();
-        let lf         = c!('\n');
-        let crlf       = l!("\r\n");
-        let opt_spaces = spaces.opt();
-        let eof_line   = &opt_spaces >> Pattern::eof();
+        let spaces      = EnsoLexer::spaces();
+        let indentation = EnsoLexer::indentation();
+        let lf          = c!('\n');
+        let crlf        = l!("\r\n");
+        let opt_spaces  = spaces.opt();
+        let eof_line    = &indentation >> Pattern::eof();
 
         let root_state_id = lexer.initial_state;
         let root_state    = lexer.group_mut(root_state_id);
@@ -657,8 +1422,8 @@ impl EnsoLexer {
 
         let block_newline_id = lexer.block_newline;
         let block_newline    = lexer.group_mut(block_newline_id);
-        block_newline.create_rule(&opt_spaces,"self.block_in_line(reader)");
-        block_newline.create_rule(&eof_line,  "self.block_in_eof_line(reader)");
+        block_newline.create_rule(&indentation,"self.block_in_line(reader)");
+        block_newline.create_rule(&eof_line,   "self.on_eof(reader)");
 
         let in_block_line_id = lexer.in_block_line;
         let in_block_line    = lexer.group_mut(in_block_line_id);
@@ -687,7 +1452,14 @@ impl EnsoLexer {
     }
 
     /// Triggered on an arbitrary eof character.
-    fn on_eof<R:LazyReader>(&mut self, reader:&mut R) {
+    ///
+    /// Nothing is left to do at this point. Once the lexer has finished, the end of input hooks
+    /// close whatever is still open in the states that the lexer leaves, and the hook of the root
+    /// state then submits the blocks.
+    fn on_eof<R:LazyReader>(&mut self, _reader:&mut R) {}
+
+    /// Triggered when the file ends, once the lexer has left every other state.
+    fn on_root_eof<R:LazyReader>(&mut self, reader:&mut R) {
         self.offset.push();
         self.block_submit_line(reader);
         self.on_block_end(0,reader);
@@ -696,7 +1468,9 @@ impl EnsoLexer {
 
     /// Triggered on any unrecognized character.
     fn on_unrecognized<R:LazyReader>(&mut self, _reader:&mut R) {
-        let token = Token::Unrecognized(self.consume_current(),self.offset.consume());
+        let text   = self.consume_current();
+        let offset = self.offset.consume();
+        let token  = Token::Unrecognized(text,offset).with_span(self.current_span);
         self.append_token(token);
     }
 
@@ -705,12 +1479,14 @@ impl EnsoLexer {
         let space = Pattern::char(' ');
         let eof   = Pattern::eof();
         let any   = Pattern::any();
+        let error = Annotation::scoped("invalid.illegal.enso");
 
         let initial_state_id = lexer.initial_state;
         let initial_state    = lexer.group_mut(initial_state_id);
         initial_state.create_rule(&space,"self.on_space(reader)");
         initial_state.create_rule(&eof,  "self.on_eof(reader)");
-        initial_state.create_rule(&any,  "self.on_unrecognized(reader)");
+        initial_state.create_annotated_rule(&any,"self.on_unrecognized(reader)",error);
+        initial_state.set_on_eof("self.on_root_eof(reader)");
     }
 }
 
@@ -723,9 +1499,11 @@ impl flexer::Definition for EnsoLexer {
         let mut lexer = EnsoLexer::new();
 
         EnsoLexer::add_operator_rules(&mut lexer);
+        EnsoLexer::add_group_rules(&mut lexer);
         EnsoLexer::add_identifier_rules(&mut lexer);
         EnsoLexer::add_number_rules(&mut lexer);
         EnsoLexer::add_text_rules(&mut lexer);
+        EnsoLexer::add_comment_rules(&mut lexer);
         EnsoLexer::add_block_rules(&mut lexer);
         EnsoLexer::add_default_rules(&mut lexer);
 
@@ -757,7 +1535,7 @@ impl Default for EnsoLexer {
 // ===================
 
 /// The state for the Enso lexer.
-#[derive(Debug)]
+#[derive(Clone,Debug)]
 pub struct State<Logger> {
     /// The logger for the lexing state.
     logger : Logger,
@@ -785,14 +1563,36 @@ pub struct State<Logger> {
     block_newline : group::Identifier,
     /// The state entered when within the line of a block.
     in_block_line : group::Identifier,
+    /// The state for lexing a raw text literal on a single line.
+    text_raw_line : group::Identifier,
+    /// The state for lexing a format text literal on a single line.
+    text_format_line : group::Identifier,
+    /// The state for lexing an expression interpolated into format text.
+    text_splice : group::Identifier,
+    /// The state entered at the start of each line in a text block.
+    text_block_newline : group::Identifier,
+    /// The state for lexing a line in a raw text block.
+    text_raw_block : group::Identifier,
+    /// The state for lexing a line in a format text block.
+    text_format_block : group::Identifier,
+    /// The state entered at the start of each line following a documentation comment.
+    doc_comment_newline : group::Identifier,
+    /// The state for lexing a line in a documentation comment.
+    doc_comment_line : group::Identifier,
     /// A stack of token matches.
     tokens_stack : Vec<token::Stream>,
+    /// The symbols of the matches lexed so far.
+    interner : Interner,
     /// Tracking for the current offset.
     offset : Offset<Logger>,
     /// State specifically for lexing Enso numbers.
     number_state : NumberLexingState<Logger>,
     /// State specifically for lexing Enso blocks.
-    block_state : BlockLexingState<Logger>
+    block_state : BlockLexingState<Logger>,
+    /// State specifically for lexing Enso text literals.
+    text_state : TextLexingState<Logger>,
+    /// State specifically for lexing Enso groups.
+    group_state : GroupLexingState<Logger>,
 }
 
 impl<Logger:AnyLogger<Owned=Logger>> State<Logger> {
@@ -826,13 +1626,26 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         let block_top_level     = lexer_states.define_group("BLOCK_MODULE", None);
         let block_newline       = lexer_states.define_group("BLOCK_NEWLINE",None);
         let in_block_line       = lexer_states.define_group("IN_BLOCK_LINE",None);
+        let text_raw_line       = lexer_states.define_group("TEXT_RAW_LINE",None);
+        let text_format_line    = lexer_states.define_group("TEXT_FORMAT_LINE",None);
+        let text_splice         = lexer_states.define_group("TEXT_SPLICE",Some(initial_state));
+        let text_block_newline  = lexer_states.define_group("TEXT_BLOCK_NEWLINE",None);
+        let text_raw_block      = lexer_states.define_group("TEXT_RAW_BLOCK",None);
+        let text_format_block   = lexer_states.define_group("TEXT_FORMAT_BLOCK",None);
+        let doc_comment_newline = lexer_states.define_group("DOC_COMMENT_NEWLINE",None);
+        let doc_comment_line    = lexer_states.define_group("DOC_COMMENT_LINE",None);
         let tokens_stack        = Vec::new();
+        let interner            = Interner::default();
         let offset_logger       = <Logger>::sub(&logger,"Offset");
         let offset              = Offset::new(offset_logger);
         let number_state_logger = <Logger>::sub(&logger,"NumberState");
         let number_state        = NumberLexingState::new(number_state_logger);
         let block_state_logger  = <Logger>::sub(&logger,"BlockLexingState");
         let block_state         = BlockLexingState::new(block_state_logger);
+        let text_state_logger   = <Logger>::sub(&logger,"TextLexingState");
+        let text_state          = TextLexingState::new(text_state_logger);
+        let group_state_logger  = <Logger>::sub(&logger,"GroupLexingState");
+        let group_state         = GroupLexingState::new(group_state_logger);
 
         Self
         { logger
@@ -848,10 +1661,21 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
         , block_top_level
         , block_newline
         , in_block_line
+        , text_raw_line
+        , text_format_line
+        , text_splice
+        , text_block_newline
+        , text_raw_block
+        , text_format_block
+        , doc_comment_newline
+        , doc_comment_line
         , tokens_stack
+        , interner
         , offset
         , number_state
         , block_state
+        , text_state
+        , group_state
         }
     }
 
@@ -878,6 +1702,16 @@ impl<Logger:AnyLogger<Owned=Logger>> flexer::State for State<Logger> {
     fn specialize(&self) -> Result<String, GenError> {
         generate::specialize(self,"EnsoLexer","token::Stream")
     }
+
+    fn reset(&mut self) {
+        self.tokens_stack.clear();
+        self.interner.clear();
+        self.offset.reset();
+        self.number_state.reset();
+        self.block_state.reset();
+        self.text_state.reset();
+        self.group_state.reset();
+    }
 }
 
 
@@ -915,6 +1749,13 @@ impl<Logger:AnyLogger> Offset<Logger> {
         self.current = 0;
     }
 
+    /// Reset the offset state, discarding the offset stack.
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.stack.clear();
+        debug!(self.logger,"Reset Offset");
+    }
+
     /// Pop the top offset from the offset stack.
     pub fn pop(&mut self) {
         self.current = self.stack.pop().unwrap_or(0);
@@ -948,9 +1789,11 @@ impl<Logger:AnyLogger> Offset<Logger> {
 #[derive(Clone,Debug,Default,PartialEq,Eq)]
 pub struct NumberLexingState<Logger> {
     /// The (optional) base for the number.
-    pub base : String,
+    pub base : Symbol,
     /// The literal number, to be interpreted in `base`.
-    pub literal : String,
+    pub literal : Symbol,
+    /// The offset in the input at which the number starts.
+    pub start : usize,
     /// A logger for the number state.
     logger : Logger,
 }
@@ -958,15 +1801,17 @@ pub struct NumberLexingState<Logger> {
 impl<Logger:AnyLogger> NumberLexingState<Logger> {
     /// Create a new number lexing state.
     pub fn new(logger:Logger) -> Self {
-        let base = default();
+        let base    = default();
         let literal = default();
-        NumberLexingState{base,literal,logger}
+        let start   = default();
+        NumberLexingState{base,literal,start,logger}
     }
 
     /// Reset the number lexing state.
     pub fn reset(&mut self) {
-        self.base.truncate(0);
-        self.literal.truncate(0);
+        self.base    = default();
+        self.literal = default();
+        self.start   = default();
         debug!(self.logger,"Reset Number State");
     }
 
@@ -976,19 +1821,22 @@ impl<Logger:AnyLogger> NumberLexingState<Logger> {
         mem::swap(&mut self.base,&mut self.literal);
     }
 
-    /// Convert `self` into a token, resetting the lexing state.
-    pub fn consume_token(&mut self, offset:usize) -> Token {
+    /// Convert `self` into a token that ends at the offset `end` in the input, resetting the
+    /// lexing state.
+    pub fn consume_token(&mut self, offset:usize, end:usize) -> Token {
         debug!(self.logger,"Consuming Number: Base = {self.base}, Number = {self.literal}");
-        Token::Number(mem::take(&mut self.base),mem::take(&mut self.literal),offset)
+        let span  = Span::new(self.start,end);
+        let token = Token::Number(mem::take(&mut self.base),mem::take(&mut self.literal),offset);
+        token.with_span(span)
     }
 
     /// Take the `literal` portion of the number lexing state.
-    pub fn consume_literal(&mut self) -> String {
+    pub fn consume_literal(&mut self) -> Symbol {
         mem::take(&mut self.literal)
     }
 
     /// Take the `base` portion of the number lexing state.
-    pub fn consume_base(&mut self) -> String {
+    pub fn consume_base(&mut self) -> Symbol {
         mem::take(&mut self.base)
     }
 }
@@ -1006,6 +1854,10 @@ pub struct BlockLexingState<Logger> {
     stack : NonEmptyVec<BlockState>,
     /// Whether or not the lexer has seen an explicit newline.
     seen_newline : bool,
+    /// The location of the indentation of the line being lexed.
+    indent_span : Span,
+    /// Whether the indentation of the line being lexed contains a tab.
+    indent_has_tab : bool,
     /// A logger for the lexing state.
     logger : Logger,
 }
@@ -1013,9 +1865,21 @@ pub struct BlockLexingState<Logger> {
 impl<Logger:AnyLogger> BlockLexingState<Logger> {
     /// Construct a new block lexing state.
     pub fn new(logger:Logger) -> Self {
-        let stack        = NonEmptyVec::singleton(default());
-        let seen_newline = false;
-        BlockLexingState{stack,seen_newline,logger}
+        let stack          = NonEmptyVec::singleton(default());
+        let seen_newline   = false;
+        let indent_span    = default();
+        let indent_has_tab = false;
+        BlockLexingState{stack,seen_newline,indent_span,indent_has_tab,logger}
+    }
+
+    /// Reset the block lexing state, discarding the stack of blocks.
+    pub fn reset(&mut self) {
+        while self.stack.pop().is_some() {}
+        *self.current_mut() = default();
+        self.seen_newline   = false;
+        self.indent_span    = default();
+        self.indent_has_tab = false;
+        debug!(self.logger,"Reset Block State");
     }
 
     /// Set the last seen line ending.
@@ -1041,6 +1905,25 @@ impl<Logger:AnyLogger> BlockLexingState<Logger> {
         self.current_mut().push_line(tokens, offset, trailing_line_ending);
     }
 
+    /// Move the first line ending seen in each of the `count` blocks around the current one into
+    /// the block directly inside it.
+    ///
+    /// This is for when the innermost `count` blocks end before the line that they belong to does,
+    /// so that the line ending of that line is still to come. The last lines of the blocks take
+    /// the line endings that precede the blocks instead, so that every line ending is in exactly
+    /// one line.
+    pub fn shift_line_endings(&mut self, count:usize) {
+        let len = self.stack.len();
+        for inner in (len - count..len).rev() {
+            let outer       = self.stack.get_mut(inner - 1);
+            let line_ending = outer.and_then(|block| block.seen_line_endings.pop_front());
+            if let (Some(line_ending),Some(block)) = (line_ending,self.stack.get_mut(inner)) {
+                block.seen_line_endings.push_back(line_ending);
+            }
+        }
+        debug!(self.logger,"Shift Line Endings: Blocks = {count}");
+    }
+
     /// Get a reference to the current block.
     pub fn current(&self) -> &BlockState {
         self.stack.last()
@@ -1072,10 +1955,10 @@ impl<Logger:AnyLogger> BlockLexingState<Logger> {
         block
     }
 
-    /// Push an empty line into the storage for them.
-    pub fn push_empty_line(&mut self, offset:usize) {
+    /// Push an empty line, whose line ending is at `span`, into the storage for them.
+    pub fn push_empty_line(&mut self, offset:usize, span:Span) {
         let trailing_line_ending = self.pop_line_ending();
-        self.current_mut().push_empty_line(offset, trailing_line_ending);
+        self.current_mut().push_empty_line(offset, trailing_line_ending, span);
         debug!(self.logger,"Append Empty line: Line Ending = {trailing_line_ending:?}");
     }
 }
@@ -1115,32 +1998,38 @@ impl BlockState {
     }
 
     /// Push a line into the block.
+    ///
+    /// The span of the line covers its tokens, as its line ending may be lexed after the blocks
+    /// that belong to it.
     pub fn push_line
     (&mut self
      , tokens               : Vec<Token>
      , indent: usize
      , trailing_line_ending : token::LineEnding
     ) {
-        let line = Token::Line(tokens,indent,trailing_line_ending);
+        let span = Token::covering_span(&tokens).unwrap_or_default();
+        let line = Token::Line(tokens,indent,trailing_line_ending).with_span(span);
         self.lines.push(line)
     }
 
-    /// Push a blank line into the block.
+    /// Push a blank line, whose line ending is at `span`, into the block.
     ///
     /// The offset here should be the offset from the baseline, not from the block indent level.
-    pub fn push_empty_line(&mut self, offset:usize, trailing_line_ending:token::LineEnding) {
-        let line = Token::BlankLine(offset, trailing_line_ending);
+    pub fn push_empty_line
+    (&mut self, offset:usize, trailing_line_ending:token::LineEnding, span:Span) {
+        let line = Token::BlankLine(offset, trailing_line_ending).with_span(span);
         self.lines.push(line);
     }
 
     /// Convert the block state into a block token.
     pub fn into_token(self, offset:usize) -> Token {
+        let span = Token::covering_span(&self.lines).unwrap_or_default();
         Token::Block(
             BlockType::Continuous,
             self.indent,
             self.lines,
             offset
-        )
+        ).with_span(span)
     }
 
     /// Consume the lines in the block.
@@ -1157,3 +2046,370 @@ impl Default for BlockState {
         BlockState::new()
     }
 }
+
+
+
+// =======================
+// === TextLexingState ===
+// =======================
+
+/// The state for managing the lexing of text literals in Enso.
+#[derive(Clone,Debug,PartialEq)]
+pub struct TextLexingState<Logger> {
+    /// The stack of text literals being lexed.
+    stack : Vec<TextState>,
+    /// A logger for the lexing state.
+    logger : Logger,
+}
+
+impl<Logger:AnyLogger> TextLexingState<Logger> {
+    /// Construct a new text lexing state.
+    pub fn new(logger:Logger) -> Self {
+        let stack = default();
+        TextLexingState{stack,logger}
+    }
+
+    /// Reset the text lexing state, discarding the stack of literals.
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        debug!(self.logger,"Reset Text State");
+    }
+
+    /// Begin lexing a text literal in the provided `style`, whose opening quote is at `span`.
+    pub fn begin_literal(&mut self, style:token::TextStyle, offset:usize, span:Span) {
+        debug!(self.logger,"Begin Text Literal: Style = {style:?}");
+        self.stack.push(TextState::new(style,offset,span));
+    }
+
+    /// Begin lexing a text block in the provided `style`, whose opening quotes are followed by
+    /// `opening_offset` spaces and `start_line_ending`, all of which are at `span`.
+    pub fn begin_block
+    ( &mut self
+    , style             : token::TextStyle
+    , offset            : usize
+    , span              : Span
+    , opening_offset    : usize
+    , start_line_ending : token::LineEnding
+    ) {
+        debug!(self.logger,"Begin Text Block: Style = {style:?}");
+        let mut block           = TextState::new(style,offset,span);
+        block.opening_offset    = opening_offset;
+        block.start_line_ending = start_line_ending;
+        self.stack.push(block);
+    }
+
+    /// Get a mutable reference to the text literal being lexed.
+    pub fn current_mut(&mut self) -> &mut TextState {
+        match self.stack.last_mut() {
+            Some(literal) => literal,
+            None          => unreachable_panic!("There is always a text literal being lexed."),
+        }
+    }
+
+    /// Append a segment to the text literal being lexed.
+    pub fn push_segment(&mut self, segment:Token) {
+        debug!(self.logger,"Push Text Segment: {&segment:?}");
+        self.current_mut().segments.push(segment);
+    }
+
+    /// Finish lexing the current text literal.
+    pub fn end_literal(&mut self) -> Option<TextState> {
+        debug!(self.logger,"End Text Literal");
+        self.stack.pop()
+    }
+}
+
+
+
+// =================
+// === TextState ===
+// =================
+
+/// The state for lexing a given text literal in Enso.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct TextState {
+    /// The style of the literal.
+    pub style : token::TextStyle,
+    /// The offset of the literal from the preceding token.
+    pub offset : usize,
+    /// The location of the opening delimiter of the literal.
+    pub span : Span,
+    /// The offset in the input at which the current line of a text block starts.
+    pub line_start : usize,
+    /// The location of the opening backtick of the expression being interpolated.
+    pub splice_span : Span,
+    /// The segments lexed so far on the current line.
+    pub segments : Vec<Token>,
+    /// The number of spaces between the opening quotes of a text block and its line ending.
+    pub opening_offset : usize,
+    /// The line ending after the opening quotes of a text block.
+    pub start_line_ending : token::LineEnding,
+    /// The margin of a text block, which is set by its first line.
+    pub margin : Option<usize>,
+    /// The indentation of the current line in a text block beyond its margin.
+    pub line_offset : usize,
+    /// The lines of a text block lexed so far.
+    pub lines : Vec<Token>,
+}
+
+impl TextState {
+    /// Construct a new text literal state, whose opening delimiter is at `span`.
+    pub fn new(style:token::TextStyle, offset:usize, span:Span) -> Self {
+        let line_start        = span.end;
+        let splice_span       = default();
+        let segments          = default();
+        let opening_offset    = default();
+        let start_line_ending = default();
+        let margin            = default();
+        let line_offset       = default();
+        let lines             = default();
+        TextState
+        { style
+        , offset
+        , span
+        , line_start
+        , splice_span
+        , segments
+        , opening_offset
+        , start_line_ending
+        , margin
+        , line_offset
+        , lines
+        }
+    }
+
+    /// Begin a line with `indent` in a text block, returning whether the line is part of it.
+    ///
+    /// The first line of the block sets its margin, and has to be indented further than the
+    /// `block_indent` of the enclosing block. The text of the line starts at the offset
+    /// `line_start` in the input.
+    pub fn begin_line(&mut self, indent:usize, block_indent:usize, line_start:usize) -> bool {
+        self.line_start = line_start;
+        match self.margin {
+            None if indent > block_indent => {
+                self.margin      = Some(indent);
+                self.line_offset = 0;
+                true
+            }
+            Some(margin) if indent >= margin => {
+                self.line_offset = indent - margin;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// End the current line of a text block with `line_ending`, which is at `line_ending_span`
+    /// unless the line ends the file.
+    pub fn end_line(&mut self, line_ending:token::LineEnding, line_ending_span:Option<Span>) {
+        let segments = mem::take(&mut self.segments);
+        let offset   = mem::take(&mut self.line_offset);
+        let start    = Span::new(self.line_start,self.line_start);
+        let span     = Token::covering_span(&segments).map_or(start,|span| start.cover(span));
+        let span     = line_ending_span.map_or(span,|line_ending| span.cover(line_ending));
+        self.lines.push(Token::Line(segments,offset,line_ending).with_span(span));
+    }
+
+    /// Push a blank line, whose line ending is at `span`, into a text block.
+    ///
+    /// The offset here should be the offset from the baseline, not from the margin of the block.
+    pub fn push_blank_line(&mut self, offset:usize, line_ending:token::LineEnding, span:Span) {
+        self.lines.push(Token::BlankLine(offset,line_ending).with_span(span));
+    }
+
+    /// Get the span from the opening delimiter of the literal to the end of the `tokens` in it.
+    pub fn span_covering(&self, tokens:&[Token]) -> Span {
+        Token::covering_span(tokens).map_or(self.span,|span| self.span.cover(span))
+    }
+
+    /// Convert the state into a token for a text literal closed by the quote at `close`.
+    pub fn into_token(self, close:Span) -> Token {
+        let span = self.span.cover(close);
+        Token::TextLine(self.style,self.segments,self.offset).with_span(span)
+    }
+
+    /// Convert the state into a token for a text literal that was not closed.
+    pub fn into_unclosed_token(self) -> Token {
+        let span = self.span_covering(&self.segments);
+        Token::UnclosedTextLine(self.style,self.segments,self.offset).with_span(span)
+    }
+
+    /// Split off the line ending of the last line in a text block, along with the blank lines
+    /// that follow that line.
+    ///
+    /// These belong to the block that contains the text block, rather than to the text block.
+    pub fn split_off_trailing_lines(&mut self) -> (token::LineEnding,Vec<Token>) {
+        let is_line     = |line:&Token| matches!(line.shape,token::Shape::Line{..});
+        let text_len    = self.lines.iter().rposition(is_line).map_or(0,|ix| ix + 1);
+        let blank_lines = self.lines.split_off(text_len);
+        let line_ending = match self.lines.pop() {
+            Some(Token{shape:token::Shape::Line{tokens,trailing_line_ending},offset,span,..}) => {
+                let span = Span::new(span.start,span.end - trailing_line_ending.size());
+                let line = Token::Line(tokens,offset,token::LineEnding::None).with_span(span);
+                self.lines.push(line);
+                trailing_line_ending
+            }
+            _ => {
+                let line_ending = mem::take(&mut self.start_line_ending);
+                self.span.end  -= line_ending.size();
+                line_ending
+            }
+        };
+        (line_ending,blank_lines)
+    }
+
+    /// Convert the state into a token for a text block.
+    pub fn into_block_token(self) -> Token {
+        let margin      = self.margin.unwrap_or_default();
+        let style       = self.style;
+        let line_ending = self.start_line_ending;
+        let span        = self.span_covering(&self.lines);
+        let token       = Token::TextBlock
+            (self.opening_offset,line_ending,style,margin,self.lines,self.offset);
+        token.with_span(span)
+    }
+
+    /// Convert the state into a token for a documentation comment.
+    pub fn into_doc_comment_token(self) -> Token {
+        let margin = self.margin.unwrap_or_default();
+        let span   = self.span_covering(&self.lines);
+        Token::DocComment(self.lines,margin,self.offset).with_span(span)
+    }
+}
+
+
+
+// ========================
+// === GroupLexingState ===
+// ========================
+
+/// The state for managing the lexing of groups in Enso.
+///
+/// Each block and interpolated expression begins a new scope, in which only the groups opened
+/// inside it can be closed. The exception are the groups opened on the line that a block belongs
+/// to, which a closing bracket in the block closes by ending the block.
+#[derive(Clone,Debug,PartialEq)]
+pub struct GroupLexingState<Logger> {
+    /// The stack of scopes.
+    scopes : NonEmptyVec<GroupScope>,
+    /// A logger for the lexing state.
+    logger : Logger,
+}
+
+impl<Logger:AnyLogger> GroupLexingState<Logger> {
+    /// Construct a new group lexing state.
+    pub fn new(logger:Logger) -> Self {
+        let scopes = NonEmptyVec::singleton(default());
+        GroupLexingState{scopes,logger}
+    }
+
+    /// Reset the group lexing state, discarding all of the scopes.
+    pub fn reset(&mut self) {
+        while self.scopes.pop().is_some() {}
+        *self.scopes.last_mut() = default();
+        debug!(self.logger,"Reset Group State");
+    }
+
+    /// Begin a new scope of groups, which belongs to a block if `is_block` holds.
+    pub fn begin_scope(&mut self, is_block:bool) {
+        debug!(self.logger,"Begin Group Scope: Block = {is_block}");
+        let groups = default();
+        self.scopes.push(GroupScope{groups,is_block});
+    }
+
+    /// End the current scope of groups.
+    pub fn end_scope(&mut self) {
+        debug!(self.logger,"End Group Scope");
+        self.scopes.pop();
+    }
+
+    /// Begin a group opened by the `open` bracket, which is located at `span`.
+    pub fn begin_group(&mut self, open:char, offset:usize, span:Span) {
+        debug!(self.logger,"Begin Group: Open = {open:?}");
+        self.scopes.last_mut().groups.push(GroupState{open,offset,span});
+    }
+
+    /// Finish lexing the innermost group in the current scope.
+    pub fn end_group(&mut self) -> Option<GroupState> {
+        debug!(self.logger,"End Group");
+        self.scopes.last_mut().groups.pop()
+    }
+
+    /// Get the innermost group in the current scope.
+    pub fn current(&self) -> Option<&GroupState> {
+        self.scopes.last().groups.last()
+    }
+
+    /// The number of groups open in the current scope.
+    pub fn depth(&self) -> usize {
+        self.scopes.last().groups.len()
+    }
+
+    /// Find the innermost group closed by `close` that is either in the current scope, or in a
+    /// scope that can be reached by leaving only the scopes of blocks.
+    ///
+    /// Returns the number of scopes to leave, along with the depth of the group in its scope.
+    pub fn find_enclosing_group(&self, close:char) -> Option<(usize,usize)> {
+        for (left,scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(depth) = scope.find_group(close) {
+                return Some((left,depth))
+            }
+            if !scope.is_block {
+                break
+            }
+        }
+        None
+    }
+}
+
+
+
+// ==================
+// === GroupScope ===
+// ==================
+
+/// The groups that are open in a single scope.
+#[derive(Clone,Debug,Default,PartialEq,Eq)]
+pub struct GroupScope {
+    /// The stack of groups open in the scope.
+    pub groups : Vec<GroupState>,
+    /// Whether the scope belongs to a block, rather than to the whole input or to an interpolated
+    /// expression.
+    pub is_block : bool,
+}
+
+impl GroupScope {
+    /// Find the depth of the innermost group in the scope that is closed by `close`.
+    pub fn find_group(&self, close:char) -> Option<usize> {
+        self.groups.iter().rposition(|group| group.closing_bracket() == close)
+    }
+}
+
+
+
+// ==================
+// === GroupState ===
+// ==================
+
+/// The state for lexing a given group in Enso.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct GroupState {
+    /// The bracket that opens the group.
+    pub open : char,
+    /// The offset of the group from the preceding token.
+    pub offset : usize,
+    /// The location of the opening bracket.
+    pub span : Span,
+}
+
+impl GroupState {
+    /// The bracket that closes the group.
+    pub fn closing_bracket(&self) -> char {
+        match self.open {
+            '(' => ')',
+            '[' => ']',
+            '{' => '}',
+            _   => unreachable_panic!("Groups are only opened by brackets."),
+        }
+    }
+}
@@ -10,7 +10,11 @@
 
 //! This library defines the lexer for the syntax of the Enso language.
 
+pub mod grammar;
 pub mod lexer;
+pub mod matcher;
+pub mod number;
+pub mod symbol;
 pub mod token;
 
 /// A module that can be re-exported under the same name in the generation crate.
@@ -19,6 +23,8 @@ pub mod token;
 /// Enso lexer definition. In this project, imports should _not_ be made from the crate root
 /// _except_ through use of this `library` module.
 pub mod library {
+    pub use crate::number;
+    pub use crate::symbol;
     pub use crate::token;
 }
 
@@ -0,0 +1,72 @@
+//! This module provides a matcher for patterns over tokens, which lets the parser recognise
+//! sequences of tokens, such as the segments of macros, using the same automata as the lexer.
+
+use crate::token::Kind;
+use crate::token::Token;
+
+use flexer::automata::matcher;
+use flexer::automata::pattern::Pattern;
+
+pub use flexer::automata::matcher::Match;
+
+
+
+// ===============
+// === Matcher ===
+// ===============
+
+/// A matcher for a list of patterns over the kinds of tokens.
+#[derive(Clone,Debug)]
+pub struct Matcher {
+    matcher:matcher::Matcher<Kind>,
+}
+
+impl Matcher {
+    /// Create a matcher for `patterns`.
+    ///
+    /// When several patterns match the same tokens, the one that comes first in `patterns` wins.
+    pub fn new(patterns:&[Pattern<Kind>]) -> Matcher {
+        let matcher = matcher::Matcher::new(patterns);
+        Matcher{matcher}
+    }
+
+    /// Find the pattern that matches the longest prefix of `tokens`.
+    pub fn longest_match(&self, tokens:&[Token]) -> Option<Match> {
+        self.matcher.longest_match(tokens.iter().map(Token::kind))
+    }
+
+    /// Find the pattern that matches all of `tokens`.
+    pub fn full_match(&self, tokens:&[Token]) -> Option<usize> {
+        self.matcher.full_match(tokens.iter().map(Token::kind))
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_tokens() {
+        let name       = Pattern::symbol(Kind::Variable) | Pattern::symbol(Kind::Referent);
+        let operator   = Pattern::symbol(Kind::Operator);
+        let assignment = &name >> &operator >> Pattern::symbols(Kind::Variable..=Kind::TextLine);
+        let matcher    = Matcher::new(&[assignment,name.many1()]);
+        let tokens     = vec![
+            Token::Variable("a",1),
+            Token::Operator("=",1),
+            Token::Number("","1",0),
+            Token::Operator("+",0),
+        ];
+        assert_eq!(matcher.longest_match(&tokens),Some(Match{pattern:0,length:3}));
+        assert_eq!(matcher.longest_match(&tokens[..1]),Some(Match{pattern:1,length:1}));
+        assert_eq!(matcher.longest_match(&tokens[1..]),None);
+        assert_eq!(matcher.full_match(&tokens[..3]),Some(0));
+        assert_eq!(matcher.full_match(&tokens),None);
+    }
+}
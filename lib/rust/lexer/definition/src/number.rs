@@ -0,0 +1,263 @@
+//! This module validates and decodes number literals, which the lexer keeps as the text of their
+//! base and digits.
+//!
+//! The value of a literal is decoded exactly, as a literal in one base often has no exact
+//! representation in another. A decimal is therefore kept as the integer of its digits together
+//! with the number of those digits that follow its point.
+
+use crate::prelude::*;
+
+use crate::token::Kind;
+use crate::token::Shape;
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+
+
+// =============
+// === Bases ===
+// =============
+
+/// The smallest base that a number literal can be written in.
+pub const MIN_BASE:u32 = 2;
+
+/// The largest base that a number literal can be written in, using all of the ASCII digits and
+/// letters as its digits.
+pub const MAX_BASE:u32 = 36;
+
+/// The base of a number literal that is written without one.
+pub const DEFAULT_BASE:u32 = 10;
+
+/// The character that separates the integral and fractional digits of a decimal.
+const POINT:char = '.';
+
+/// Get the base written as `base` in a number literal, or `None` if it is not a supported base.
+///
+/// An empty `base` is the default base.
+pub fn parse_base(base:&str) -> Option<u32> {
+    if base.is_empty() {
+        Some(DEFAULT_BASE)
+    } else {
+        base.parse().ok().filter(|base| (MIN_BASE..=MAX_BASE).contains(base))
+    }
+}
+
+/// Get the characters of `number` that are not digits in `base`, with their byte offsets.
+///
+/// The point of a decimal is not a digit, but it is not reported either. The `base` must be a
+/// supported base.
+pub fn invalid_digits(number:&str, base:u32) -> impl Iterator<Item=(usize,char)> + '_ {
+    number.char_indices().filter(move |&(_,char)| char != POINT && !char.is_digit(base))
+}
+
+
+
+// =============
+// === Value ===
+// =============
+
+/// The value of a number literal.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Value {
+    /// A number without a fractional part.
+    Integer(BigUint),
+    /// A number with a fractional part.
+    Decimal(Decimal),
+}
+
+impl Value {
+    /// Decode the value of a [`Shape::Number`].
+    pub fn decode(shape:&Shape) -> Result<Value,Error> {
+        match shape {
+            Shape::Number{base,number} => Value::decode_literal(base,number),
+            _                          => Err(Error::NotANumber(shape.kind())),
+        }
+    }
+
+    /// Decode the value of a number literal written with `number` as its digits in `base`.
+    pub fn decode_literal(base:&str, number:&str) -> Result<Value,Error> {
+        let radix = parse_base(base).ok_or_else(|| Error::InvalidBase(base.into()))?;
+        if let Some((index,digit)) = invalid_digits(number,radix).next() {
+            return Err(Error::InvalidDigit{index,digit})
+        }
+        let mut parts = number.splitn(2,POINT);
+        let integral  = parts.next().unwrap_or_default();
+        let digits    = |text:&str| BigUint::parse_bytes(text.as_bytes(),radix);
+        match parts.next() {
+            None => digits(integral).map(Value::Integer).ok_or(Error::MissingDigits),
+            Some(fractional) if !integral.is_empty() && !fractional.is_empty() => {
+                let mantissa = digits(&format!("{}{}",integral,fractional));
+                let scale    = fractional.len();
+                let decimal  = mantissa.map(|mantissa| Decimal{base:radix,mantissa,scale});
+                decimal.map(Value::Decimal).ok_or(Error::MissingDigits)
+            }
+            Some(_) => Err(Error::MissingDigits),
+        }
+    }
+}
+
+
+
+// ===============
+// === Decimal ===
+// ===============
+
+/// The exact value of a number with a fractional part, which is `mantissa / base^scale`.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Decimal {
+    /// The base that the number was written in.
+    pub base : u32,
+    /// The digits of the number, without its point, read as an integer.
+    pub mantissa : BigUint,
+    /// The number of digits after the point.
+    pub scale : usize,
+}
+
+impl Decimal {
+    /// The number that the mantissa is divided by to get the value of the decimal.
+    pub fn denominator(&self) -> BigUint {
+        num_traits::pow(BigUint::from(self.base),self.scale)
+    }
+
+    /// Approximate the value of the decimal with a floating point number.
+    ///
+    /// A decimal too large for a floating point number is approximated by infinity.
+    pub fn to_f64(&self) -> f64 {
+        // Dividing the mantissa shifted left by `shift` bits leaves a quotient of at least 64
+        // bits, which is more precise than a floating point number, so neither the mantissa nor
+        // the denominator have to fit into one.
+        let denominator = self.denominator();
+        let shift       = (denominator.bits() + 64).saturating_sub(self.mantissa.bits()) as i32;
+        let quotient    = (&self.mantissa << shift as usize) / denominator;
+        let quotient    = quotient.to_f64().unwrap_or(f64::INFINITY);
+        // The shift is undone in two halves, as a single power of two may underflow on its own.
+        let half        = shift / 2;
+        quotient * 2_f64.powi(-half) * 2_f64.powi(half - shift)
+    }
+}
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// The reasons that a number literal cannot be decoded.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub enum Error {
+    /// The token to decode is not a number, but of the provided kind.
+    NotANumber(Kind),
+    /// The base of the literal is not a supported base.
+    InvalidBase(String),
+    /// A character of the literal is not a digit in its base.
+    InvalidDigit{
+        /// The byte offset of the character in the literal.
+        index : usize,
+        /// The character.
+        digit : char,
+    },
+    /// The literal, or one side of its point, has no digits.
+    MissingDigits,
+}
+
+
+// === Trait Impls ===
+
+impl Display for Error {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotANumber(kind) => write!(f,"Expected a number, but got a {:?} token.",kind),
+            Error::InvalidBase(base) => {
+                write!(f,"Base {} is not between {} and {}.",base,MIN_BASE,MAX_BASE)
+            }
+            Error::InvalidDigit{index,digit} => {
+                write!(f,"`{}` at offset {} is not a digit in the base of the number.",digit,index)
+            }
+            Error::MissingDigits => write!(f,"The number is missing digits."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integer(value:u64) -> Result<Value,Error> {
+        Ok(Value::Integer(BigUint::from(value)))
+    }
+
+    #[test]
+    fn parse_bases() {
+        assert_eq!(parse_base(""),Some(10));
+        assert_eq!(parse_base("2"),Some(2));
+        assert_eq!(parse_base("036"),Some(36));
+        assert_eq!(parse_base("1"),None);
+        assert_eq!(parse_base("37"),None);
+        assert_eq!(parse_base("99999999999999999999"),None);
+    }
+
+    #[test]
+    fn find_invalid_digits() {
+        assert_eq!(invalid_digits("1012.01",2).collect_vec(),vec![(3,'2')]);
+        assert_eq!(invalid_digits("fFgz",16).collect_vec(),vec![(2,'g'),(3,'z')]);
+        assert_eq!(invalid_digits("zZ",36).count(),0);
+    }
+
+    #[test]
+    fn decode_integers() {
+        assert_eq!(Value::decode(&Shape::number("","123")),integer(123));
+        assert_eq!(Value::decode(&Shape::number("2","1010")),integer(10));
+        assert_eq!(Value::decode(&Shape::number("16","fF")),integer(255));
+        assert_eq!(Value::decode(&Shape::number("36","z")),integer(35));
+        let huge     = Value::decode(&Shape::number("","340282366920938463463374607431768211456"));
+        let expected = num_traits::pow(BigUint::from(2_u32),128);
+        assert_eq!(huge,Ok(Value::Integer(expected)));
+    }
+
+    #[test]
+    fn decode_decimals() {
+        let decimal = |base,mantissa:u64,scale| {
+            Ok(Value::Decimal(Decimal{base,mantissa:BigUint::from(mantissa),scale}))
+        };
+        assert_eq!(Value::decode(&Shape::number("","1.25")),decimal(10,125,2));
+        assert_eq!(Value::decode(&Shape::number("2","1.1")),decimal(2,3,1));
+        let third = Decimal{base:3,mantissa:BigUint::from(1_u32),scale:1};
+        assert_eq!(third.denominator(),BigUint::from(3_u32));
+        assert!((third.to_f64() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn decimals_with_long_fractions_to_f64() {
+        let to_f64 = |number:String| match Value::decode(&Shape::number("",number)) {
+            Ok(Value::Decimal(decimal)) => decimal.to_f64(),
+            other                       => panic!("Expected a decimal, but got {:?}.",other),
+        };
+        let zeros = "0".repeat(400);
+        assert_eq!(to_f64(format!("1.{}",zeros)),1.0);
+        assert_eq!(to_f64(format!("2.5{}",zeros)),2.5);
+        assert_eq!(to_f64(format!("0.{}1",zeros)),0.0);
+        assert_eq!(to_f64(format!("1{}.0",zeros)),f64::INFINITY);
+        let third = Decimal{base:3,mantissa:num_traits::pow(BigUint::from(3_u32),400),scale:401};
+        assert!((third.to_f64() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let invalid_digit = |index,digit| Err(Error::InvalidDigit{index,digit});
+        assert_eq!(Value::decode(&Shape::number("2","1234")),invalid_digit(1,'2'));
+        assert_eq!(Value::decode(&Shape::number("10","ff")),invalid_digit(0,'f'));
+        assert_eq!(Value::decode(&Shape::number("37","1")),Err(Error::InvalidBase("37".into())));
+        assert_eq!(Value::decode(&Shape::number("","1.")),Err(Error::MissingDigits));
+        assert_eq!(Value::decode(&Shape::number("","")),Err(Error::MissingDigits));
+        assert_eq!(Value::decode(&Shape::blank()),Err(Error::NotANumber(Kind::Blank)));
+    }
+}
@@ -0,0 +1,192 @@
+//! This module defines symbols, which hold the text of tokens so that the tokens lexed from equal
+//! matches share a single copy of it.
+
+use crate::prelude::*;
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+
+
+// ==============
+// === Symbol ===
+// ==============
+
+/// The text of a token.
+///
+/// Cloning a symbol does not copy its text, and the empty symbol does not allocate at all. Symbols
+/// can be sent between threads along with the tokens that hold them.
+#[derive(Clone,Default)]
+pub struct Symbol {
+    /// The text of the symbol, which is `None` for the empty symbol.
+    text : Option<Arc<str>>,
+}
+
+impl Symbol {
+    /// Borrow the text of the symbol.
+    pub fn as_str(&self) -> &str {
+        self.text.as_deref().unwrap_or_default()
+    }
+}
+
+
+// === Trait Impls ===
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Symbols are compared and hashed by their text, as required by their implementation of
+/// [`Borrow`].
+impl PartialEq for Symbol {
+    fn eq(&self, other:&Symbol) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H:Hasher>(&self, state:&mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other:&str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other:&&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text:&str) -> Self {
+        let text = if text.is_empty() { None } else { Some(Arc::from(text)) };
+        Symbol{text}
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(text:&String) -> Self {
+        Symbol::from(text.as_str())
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text:String) -> Self {
+        Symbol::from(text.as_str())
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(),f)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.as_str(),f)
+    }
+}
+
+
+
+// ================
+// === Interner ===
+// ================
+
+/// A set of symbols, which hands out the symbol that it already holds for a text instead of
+/// allocating a new one.
+#[derive(Clone,Debug,Default)]
+pub struct Interner {
+    /// The symbols interned so far.
+    symbols : HashSet<Symbol>,
+}
+
+impl Interner {
+    /// Get the symbol for `text`, which is only allocated if the text has not been seen before.
+    pub fn intern(&mut self, text:&str) -> Symbol {
+        match self.symbols.get(text) {
+            Some(symbol) => symbol.clone(),
+            None         => {
+                let symbol = Symbol::from(text);
+                self.symbols.insert(symbol.clone());
+                symbol
+            }
+        }
+    }
+
+    /// The number of distinct symbols interned so far.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Check whether no symbols have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Forget all of the interned symbols.
+    ///
+    /// The symbols that were handed out stay valid, as each of them shares its text.
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_symbols_share_their_text() {
+        let mut interner = Interner::default();
+        let first        = interner.intern("foo");
+        let second       = interner.intern("foo");
+        let other        = interner.intern("bar");
+        assert_eq!(first,second);
+        assert_ne!(first,other);
+        assert_eq!(interner.len(),2);
+        match (&first.text,&second.text) {
+            (Some(first),Some(second)) => assert!(Arc::ptr_eq(first,second)),
+            _                          => panic!("Non-empty symbols hold their text."),
+        }
+    }
+
+    #[test]
+    fn empty_symbol_does_not_allocate() {
+        assert!(Symbol::from("").text.is_none());
+        assert_eq!(Symbol::from(""),Symbol::default());
+        assert_eq!(Symbol::default().as_str(),"");
+    }
+}
@@ -6,6 +6,12 @@
 
 use crate::prelude::*;
 
+use crate::symbol::Symbol;
+
+use flexer::automata::symbol::Symbolic;
+use flexer::sink::Checkpoint;
+use flexer::span::Span;
+
 
 
 // =============
@@ -13,7 +19,11 @@ use crate::prelude::*;
 // =============
 
 /// A lexer token.
-#[derive(Clone,Debug,Eq,PartialEq)]
+///
+/// The text of the token is held in [`Symbol`]s, which the tokens lexed from equal matches share.
+/// The lexer also locates every token that it emits with a span, through which clients that keep
+/// the input can find the text of the token.
+#[derive(Clone,Debug,Eq)]
 pub struct Token {
     /// The shape of the token.
     pub shape : Shape,
@@ -21,67 +31,91 @@ pub struct Token {
     pub length : usize,
     /// The number of trailing spaces after this token before the next.
     pub offset : usize,
+    /// The location of the token in the input.
+    ///
+    /// The span of a token that contains others covers them along with its own delimiters. As the
+    /// line ending of a line in a block may be lexed after the blocks nested in that line, the
+    /// spans of lines in blocks cover their tokens but not their line endings. Tokens that are not
+    /// created by the lexer have the empty span at the start of the input.
+    pub span : Span,
 }
 
 impl Token {
+    /// Locate the token at `span` in the input.
+    pub fn with_span(self, span:Span) -> Token {
+        Token{span,..self}
+    }
+
+    /// Get the span that covers the spans of all of the `tokens`, if there are any.
+    pub fn covering_span(tokens:&[Token]) -> Option<Span> {
+        let mut spans = tokens.iter().map(|token| token.span);
+        let first     = spans.next()?;
+        Some(spans.fold(first,Span::cover))
+    }
+
     /// Get the length that the token takes up in the program source.
     pub fn source_length(&self) -> usize {
         self.length + self.offset
     }
+
+    /// Get the kind of the token.
+    pub fn kind(&self) -> Kind {
+        self.shape.kind()
+    }
 }
 
 /// Constructors for the various forms of token.
 #[allow(non_snake_case)]
 impl Token {
     /// Construct a token representing a referent identifier.
-    pub fn Referent(name:impl Str, offset:usize) -> Token {
+    pub fn Referent(name:impl Into<Symbol>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Referent(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a variable identifier.
-    pub fn Variable(name:impl Str, offset:usize) -> Token {
+    pub fn Variable(name:impl Into<Symbol>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Variable(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing an external identifier.
-    pub fn External(name:impl Str, offset:usize) -> Token {
+    pub fn External(name:impl Into<Symbol>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::External(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a blank identifier.
     pub fn Blank(offset:usize) -> Token {
         let shape  = Shape::Blank;
         let length = 1;
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing an operator.
-    pub fn Operator(name:impl Str, offset:usize) -> Token {
+    pub fn Operator(name:impl Into<Symbol>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count();
         let shape  = Shape::Operator(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a modifier operator.
-    pub fn Modifier(name:impl Str, offset:usize) -> Token {
+    pub fn Modifier(name:impl Into<Symbol>, offset:usize) -> Token {
         let str    = name.into();
         let length = str.chars().count() + 1;
         let shape  = Shape::Modifier(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a number literal.
-    pub fn Number(base:impl Str, num:impl Into<String>, offset:usize) -> Token {
+    pub fn Number(base:impl Into<Symbol>, num:impl Into<Symbol>, offset:usize) -> Token {
         let str      = num.into();
         let base_str = base.into();
         let length   = if base_str.is_empty() {
@@ -90,23 +124,88 @@ impl Token {
             base_str.chars().count() + 1 + str.chars().count()
         };
         let shape = Shape::Number{base:base_str,number:str};
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a dangling number base.
-    pub fn DanglingBase(base:impl Str, offset:usize) -> Token {
+    pub fn DanglingBase(base:impl Into<Symbol>, offset:usize) -> Token {
         let base_str = base.into();
         let length   = base_str.chars().count() + 1;
         let shape    = Shape::DanglingBase(base_str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a text literal on a single line.
+    pub fn TextLine(style:TextStyle, segments:Vec<Token>, offset:usize) -> Token {
+        let quotes_len = 2;
+        let length     = segments.iter().fold(quotes_len,|l,r| l + r.offset + r.length);
+        let shape      = Shape::TextLine{style,segments};
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a text literal that is delimited by layout.
+    ///
+    /// The `lines` of the block are lines and blank lines. The offset of a line is its indentation
+    /// beyond the `margin`, which is part of the text. The `opening_offset` is the number of spaces
+    /// between the opening quotes and the `start_line_ending`.
+    pub fn TextBlock
+    ( opening_offset    : usize
+    , start_line_ending : LineEnding
+    , style             : TextStyle
+    , margin            : usize
+    , lines             : Vec<Token>
+    , offset            : usize
+    ) -> Token {
+        let quotes_len  = 3 + opening_offset + start_line_ending.size();
+        let lines_len   = lines.iter().map(|line| {
+            match line.shape {
+                Shape::Line{..}     => margin + line.offset + line.length,
+                Shape::BlankLine(_) => line.offset + line.length,
+                _                   => unreachable_panic!("Text blocks contain only lines."),
+            }
+        }).sum::<usize>();
+        let length = quotes_len + lines_len;
+        let shape  = Shape::TextBlock{opening_offset,start_line_ending,style,margin,lines};
+        Token{shape,length,offset,span:default()}
     }
 
-    /// Construct a token representing a text literal.
-    pub fn Text(text:impl Str, offset:usize) -> Token {
+    /// Construct a token representing a raw segment of text.
+    pub fn TextSegmentRaw(text:impl Into<Symbol>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
-        let shape  = Shape::Text(str);
-        Token{shape,length,offset}
+        let shape  = Shape::TextSegmentRaw(str);
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing an escape sequence in text.
+    ///
+    /// The `repr` is the escape sequence as written in the source, including the leading
+    /// backslash, while `value` is the character that it stands for.
+    pub fn TextSegmentEscape
+    ( style  : EscapeStyle
+    , repr   : impl Into<Symbol>
+    , value  : char
+    , offset : usize
+    ) -> Token {
+        let repr   = repr.into();
+        let length = repr.chars().count();
+        let shape  = Shape::TextSegmentEscape{style,repr,value};
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing an expression interpolated into text.
+    ///
+    /// The `trailing_offset` is the number of spaces between the last of the `tokens` and the
+    /// closing backtick.
+    pub fn TextSegmentInterpolate
+    ( tokens          : Vec<Token>
+    , trailing_offset : usize
+    , offset          : usize
+    ) -> Token {
+        let backticks_len = 2 + trailing_offset;
+        let length        = tokens.iter().fold(backticks_len,|l,r| l + r.offset + r.length);
+        let shape         = Shape::TextSegmentInterpolate{tokens,trailing_offset};
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a line of tokens.
@@ -114,7 +213,7 @@ impl Token {
         let line_ending_len = trailing_line_ending.size();
         let length          = tokens.iter().fold(line_ending_len,|l,r| l + r.offset + r.length);
         let shape           = Shape::Line{tokens,trailing_line_ending};
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a blank line.
@@ -124,7 +223,7 @@ impl Token {
     pub fn BlankLine(offset:usize, trailing_line_ending:LineEnding) -> Token {
         let length = trailing_line_ending.size();
         let shape  = Shape::BlankLine(trailing_line_ending);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing a block.
@@ -144,23 +243,121 @@ impl Token {
             }
         }).sum();
         let shape = Shape::Block{block_type,indent,lines};
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a disable comment.
+    pub fn DisableComment(text:impl Into<Symbol>, offset:usize) -> Token {
+        let str    = text.into();
+        let length = str.chars().count() + 1;
+        let shape  = Shape::DisableComment(str);
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a documentation comment.
+    ///
+    /// The first of the `lines` is the one that contains the `##`, so it is not indented by the
+    /// `margin` of the comment.
+    pub fn DocComment(lines:Vec<Token>, margin:usize, offset:usize) -> Token {
+        let marker_len = 2;
+        let lines_len  = lines.iter().enumerate().map(|(ix,line)| {
+            match line.shape {
+                Shape::Line{..} if ix == 0 => line.length,
+                Shape::Line{..}            => margin + line.offset + line.length,
+                Shape::BlankLine(_)        => line.offset + line.length,
+                _                          => unreachable_panic!("Comments contain only lines."),
+            }
+        }).sum::<usize>();
+        let length = marker_len + lines_len;
+        let shape  = Shape::DocComment{lines,margin};
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a group of tokens enclosed in matching brackets.
+    ///
+    /// The `trailing_offset` is the number of spaces between the last of the `tokens` and the
+    /// `close` bracket. When the bracket starts a line of its own, the `trailing_line_ending`
+    /// ends the line before it, and the `trailing_offset` is the indentation of its line.
+    pub fn Group
+    ( open                 : char
+    , tokens               : Vec<Token>
+    , trailing_line_ending : LineEnding
+    , trailing_offset      : usize
+    , close                : char
+    , offset               : usize
+    ) -> Token {
+        let brackets_len = 2 + trailing_line_ending.size() + trailing_offset;
+        let length       = tokens.iter().fold(brackets_len,|l,r| l + r.offset + r.length);
+        let shape        = Shape::Group{open,tokens,trailing_line_ending,trailing_offset,close};
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing an invalid suffix.
-    pub fn InvalidSuffix(text:impl Str, offset:usize) -> Token {
+    pub fn InvalidSuffix(text:impl Into<Symbol>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
         let shape  = Shape::InvalidSuffix(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
     }
 
     /// Construct a token representing an unrecognised lexeme.
-    pub fn Unrecognized(text:impl Str, offset:usize) -> Token {
+    pub fn Unrecognized(text:impl Into<Symbol>, offset:usize) -> Token {
         let str    = text.into();
         let length = str.chars().count();
         let shape  = Shape::Unrecognized(str);
-        Token{shape,length,offset}
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a text literal that is not closed before the end of its
+    /// line.
+    pub fn UnclosedTextLine(style:TextStyle, segments:Vec<Token>, offset:usize) -> Token {
+        let quote_len = 1;
+        let length    = segments.iter().fold(quote_len,|l,r| l + r.offset + r.length);
+        let shape     = Shape::UnclosedTextLine{style,segments};
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing an invalid escape sequence in text.
+    pub fn TextSegmentInvalidEscape(repr:impl Into<Symbol>, offset:usize) -> Token {
+        let str    = repr.into();
+        let length = str.chars().count();
+        let shape  = Shape::TextSegmentInvalidEscape(str);
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing an interpolated expression that is not closed before the
+    /// end of its line.
+    pub fn TextSegmentUnclosedInterpolate(tokens:Vec<Token>, offset:usize) -> Token {
+        let backtick_len = 1;
+        let length       = tokens.iter().fold(backtick_len,|l,r| l + r.offset + r.length);
+        let shape        = Shape::TextSegmentUnclosedInterpolate(tokens);
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a group that is not closed before the end of its line.
+    pub fn UnclosedGroup(open:char, tokens:Vec<Token>, offset:usize) -> Token {
+        let bracket_len = 1;
+        let length      = tokens.iter().fold(bracket_len,|l,r| l + r.offset + r.length);
+        let shape       = Shape::UnclosedGroup{open,tokens};
+        Token{shape,length,offset,span:default()}
+    }
+
+    /// Construct a token representing a closing bracket that does not close any group.
+    pub fn UnmatchedBracket(bracket:char, offset:usize) -> Token {
+        let length = 1;
+        let shape  = Shape::UnmatchedBracket(bracket);
+        Token{shape,length,offset,span:default()}
+    }
+}
+
+
+// === Trait Impls ===
+
+/// Tokens are compared without their spans, which locate them in the input rather than being part
+/// of what they are.
+impl PartialEq for Token {
+    fn eq(&self, other:&Token) -> bool {
+        self.shape == other.shape && self.length == other.length && self.offset == other.offset
     }
 }
 
@@ -179,6 +376,46 @@ pub enum BlockType {
     Discontinuous,
 }
 
+
+
+// =================
+// === TextStyle ===
+// =================
+
+/// The style of a text literal.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum TextStyle {
+    /// A raw text literal on a single line, delimited by `"`.
+    RawLine,
+    /// A format text literal on a single line, delimited by `'`.
+    ///
+    /// Format text may contain expressions that are interpolated between backticks.
+    FormatLine,
+    /// A raw text block, opened by `"""`.
+    RawBlock,
+    /// A format text block, opened by `'''`.
+    FormatBlock,
+}
+
+
+
+// ===================
+// === EscapeStyle ===
+// ===================
+
+/// The style of an escape sequence in a text literal.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum EscapeStyle {
+    /// A single character following the backslash, such as `\n`.
+    Literal,
+    /// Exactly two hexadecimal digits giving a character code, such as `\xFF`.
+    Byte,
+    /// Up to six hexadecimal digits giving a unicode code point, such as `\u{1F600}`.
+    Unicode,
+}
+
+
+
 // ===================
 // === NewlineType ===
 // ===================
@@ -230,29 +467,65 @@ pub enum Shape {
     // === Identifiers ===
 
     /// An identifier in referent form.
-    Referent(String),
+    Referent(Symbol),
     /// An identifier in variable form.
-    Variable(String),
+    Variable(Symbol),
     /// An identifier not conforming to the Enso identifier rules (e.g. a Java identifier).
-    External(String),
+    External(Symbol),
     /// A blank identifier (`_`).
     Blank,
     /// An operator identifier.
-    Operator(String),
+    Operator(Symbol),
     /// A modifier identifier.
-    Modifier(String),
+    Modifier(Symbol),
 
     // === Literals ===
 
     /// A literal number.
-    Number{base:String, number:String},
+    Number{base:Symbol, number:Symbol},
     /// A dangling base from a number literal.
-    DanglingBase(String),
-    /// A text literal.
-    ///
-    /// This is currently way too simplistic to actually represent text, but it is a good
-    /// placeholder.
-    Text(String),
+    DanglingBase(Symbol),
+    /// A text literal on a single line.
+    TextLine{
+        /// The style of the literal.
+        style : TextStyle,
+        /// The segments of text between the quotes.
+        segments : Vec<Token>,
+    },
+
+    /// A text literal that is delimited by layout.
+    TextBlock{
+        /// The number of spaces between the opening quotes and the line ending after them.
+        opening_offset : usize,
+        /// The line ending after the opening quotes.
+        start_line_ending : LineEnding,
+        /// The style of the literal.
+        style : TextStyle,
+        /// The indentation that is removed from each line of the literal.
+        margin : usize,
+        /// The lines of the literal.
+        lines : Vec<Token>,
+    },
+
+    // === Text Segments ===
+    /// A segment of text that is taken as written.
+    TextSegmentRaw(Symbol),
+    /// An escape sequence in text.
+    TextSegmentEscape{
+        /// The style of the escape sequence.
+        style : EscapeStyle,
+        /// The escape sequence as written in the source.
+        repr : Symbol,
+        /// The character that the escape sequence stands for.
+        value : char,
+    },
+    /// An expression interpolated into format text.
+    TextSegmentInterpolate{
+        /// The tokens of the expression.
+        tokens : Vec<Token>,
+        /// The number of spaces before the closing backtick.
+        trailing_offset : usize,
+    },
 
     // === Lines ===
     /// A line containing tokens.
@@ -285,27 +558,73 @@ pub enum Shape {
         lines : Vec<Token>,
     },
 
+    // === Comments ===
+    /// A disable comment, containing the text after the `#`.
+    DisableComment(Symbol),
+    /// A documentation comment.
+    DocComment{
+        /// The lines of the comment, the first of which follows the `##`.
+        lines : Vec<Token>,
+        /// The indentation that is removed from the lines of the comment after the first.
+        margin : usize,
+    },
+
+    // === Groups ===
+    /// A group of tokens enclosed in matching brackets.
+    Group{
+        /// The opening bracket.
+        open : char,
+        /// The tokens between the brackets.
+        tokens : Vec<Token>,
+        /// The line ending before the line of the closing bracket, if the bracket starts that line.
+        trailing_line_ending : LineEnding,
+        /// The number of spaces before the closing bracket.
+        trailing_offset : usize,
+        /// The closing bracket.
+        close : char,
+    },
+
     // === Errors ===
     /// An invalid suffix.
-    InvalidSuffix(String),
+    InvalidSuffix(Symbol),
     /// An unrecognized token.
-    Unrecognized(String),
+    Unrecognized(Symbol),
+    /// A text literal that is not closed before the end of its line.
+    UnclosedTextLine{
+        /// The style of the literal.
+        style : TextStyle,
+        /// The segments of text after the opening quote.
+        segments : Vec<Token>,
+    },
+    /// An escape sequence in text that is not valid.
+    TextSegmentInvalidEscape(Symbol),
+    /// An interpolated expression that is not closed before the end of its line.
+    TextSegmentUnclosedInterpolate(Vec<Token>),
+    /// A group that is not closed before the end of its line.
+    UnclosedGroup{
+        /// The opening bracket.
+        open : char,
+        /// The tokens after the opening bracket.
+        tokens : Vec<Token>,
+    },
+    /// A closing bracket that does not close any group.
+    UnmatchedBracket(char),
 }
 
 impl Shape {
 
     /// Construct an identifier in referent form.
-    pub fn referent(name:impl Into<String>) -> Shape {
+    pub fn referent(name:impl Into<Symbol>) -> Shape {
         Shape::Referent(name.into())
     }
 
     /// Construct an identifier in variable form.
-    pub fn variable(name:impl Into<String>) -> Shape {
+    pub fn variable(name:impl Into<Symbol>) -> Shape {
         Shape::Variable(name.into())
     }
 
     /// Construct an identifier in external form.
-    pub fn external(name:impl Into<String>) -> Shape {
+    pub fn external(name:impl Into<Symbol>) -> Shape {
         Shape::External(name.into())
     }
 
@@ -317,28 +636,54 @@ impl Shape {
     }
 
     /// Construct an operator identifier.
-    pub fn operator(opr:impl Into<String>) -> Shape {
+    pub fn operator(opr:impl Into<Symbol>) -> Shape {
         Shape::Operator(opr.into())
     }
 
     /// Construct a modifier identifier.
-    pub fn modifier(opr:impl Into<String>) -> Shape {
+    pub fn modifier(opr:impl Into<Symbol>) -> Shape {
         Shape::Modifier(opr.into())
     }
 
     /// Construct a number literal.
-    pub fn number(base:impl Into<String>, num:impl Into<String>) -> Shape {
+    pub fn number(base:impl Into<Symbol>, num:impl Into<Symbol>) -> Shape {
         Shape::Number{base:base.into(),number:num.into()}
     }
 
     /// Construct a dangling base literal.
-    pub fn dangling_base(base:impl Into<String>) -> Shape {
+    pub fn dangling_base(base:impl Into<Symbol>) -> Shape {
         Shape::DanglingBase(base.into())
     }
 
-    /// Construct a text literal.
-    pub fn text(text:impl Into<String>) -> Shape {
-        Shape::Text(text.into())
+    /// Construct a text literal on a single line.
+    pub fn text_line(style:TextStyle, segments:Vec<Token>) -> Shape {
+        Shape::TextLine{style,segments}
+    }
+
+    /// Construct a text literal that is delimited by layout.
+    pub fn text_block
+    ( opening_offset    : usize
+    , start_line_ending : LineEnding
+    , style             : TextStyle
+    , margin            : usize
+    , lines             : Vec<Token>
+    ) -> Shape {
+        Shape::TextBlock{opening_offset,start_line_ending,style,margin,lines}
+    }
+
+    /// Construct a raw segment of text.
+    pub fn text_segment_raw(text:impl Into<Symbol>) -> Shape {
+        Shape::TextSegmentRaw(text.into())
+    }
+
+    /// Construct an escape sequence in text.
+    pub fn text_segment_escape(style:EscapeStyle, repr:impl Into<Symbol>, value:char) -> Shape {
+        Shape::TextSegmentEscape{style,repr:repr.into(),value}
+    }
+
+    /// Construct an expression interpolated into text.
+    pub fn text_segment_interpolate(tokens:Vec<Token>, trailing_offset:usize) -> Shape {
+        Shape::TextSegmentInterpolate{tokens,trailing_offset}
     }
 
     /// Construct a line that contains tokens.
@@ -356,15 +701,181 @@ impl Shape {
         Shape::Block{block_type,indent,lines}
     }
 
+    /// Construct a disable comment.
+    pub fn disable_comment(text:impl Into<Symbol>) -> Shape {
+        Shape::DisableComment(text.into())
+    }
+
+    /// Construct a documentation comment.
+    pub fn doc_comment(lines:Vec<Token>, margin:usize) -> Shape {
+        Shape::DocComment{lines,margin}
+    }
+
+    /// Construct a group of tokens enclosed in matching brackets.
+    pub fn group
+    ( open                 : char
+    , tokens               : Vec<Token>
+    , trailing_line_ending : LineEnding
+    , trailing_offset      : usize
+    , close                : char
+    ) -> Shape {
+        Shape::Group{open,tokens,trailing_line_ending,trailing_offset,close}
+    }
+
     /// Construct an invalid suffix.
-    pub fn invalid_suffix(text:impl Into<String>) -> Shape {
+    pub fn invalid_suffix(text:impl Into<Symbol>) -> Shape {
         Shape::InvalidSuffix(text.into())
     }
 
     /// Construct an unrecognised token.
-    pub fn unrecognized(text:impl Into<String>) -> Shape {
+    pub fn unrecognized(text:impl Into<Symbol>) -> Shape {
         Shape::Unrecognized(text.into())
     }
+
+    /// Construct a text literal that is not closed before the end of its line.
+    pub fn unclosed_text_line(style:TextStyle, segments:Vec<Token>) -> Shape {
+        Shape::UnclosedTextLine{style,segments}
+    }
+
+    /// Construct an invalid escape sequence in text.
+    pub fn text_segment_invalid_escape(repr:impl Into<Symbol>) -> Shape {
+        Shape::TextSegmentInvalidEscape(repr.into())
+    }
+
+    /// Construct an interpolated expression that is not closed.
+    pub fn text_segment_unclosed_interpolate(tokens:Vec<Token>) -> Shape {
+        Shape::TextSegmentUnclosedInterpolate(tokens)
+    }
+
+    /// Construct a group that is not closed.
+    pub fn unclosed_group(open:char, tokens:Vec<Token>) -> Shape {
+        Shape::UnclosedGroup{open,tokens}
+    }
+
+    /// Construct a closing bracket that does not close any group.
+    pub fn unmatched_bracket(bracket:char) -> Shape {
+        Shape::UnmatchedBracket(bracket)
+    }
+
+    /// Get the kind of token that has this shape.
+    pub fn kind(&self) -> Kind {
+        match self {
+            Shape::Referent(_)                       => Kind::Referent,
+            Shape::Variable(_)                       => Kind::Variable,
+            Shape::External(_)                       => Kind::External,
+            Shape::Blank                             => Kind::Blank,
+            Shape::Operator(_)                       => Kind::Operator,
+            Shape::Modifier(_)                       => Kind::Modifier,
+            Shape::Number{..}                        => Kind::Number,
+            Shape::DanglingBase(_)                   => Kind::DanglingBase,
+            Shape::TextLine{..}                      => Kind::TextLine,
+            Shape::TextBlock{..}                     => Kind::TextBlock,
+            Shape::TextSegmentRaw(_)                 => Kind::TextSegmentRaw,
+            Shape::TextSegmentEscape{..}             => Kind::TextSegmentEscape,
+            Shape::TextSegmentInterpolate{..}        => Kind::TextSegmentInterpolate,
+            Shape::Line{..}                          => Kind::Line,
+            Shape::BlankLine(_)                      => Kind::BlankLine,
+            Shape::Block{..}                         => Kind::Block,
+            Shape::DisableComment(_)                 => Kind::DisableComment,
+            Shape::DocComment{..}                    => Kind::DocComment,
+            Shape::Group{..}                         => Kind::Group,
+            Shape::InvalidSuffix(_)                  => Kind::InvalidSuffix,
+            Shape::Unrecognized(_)                   => Kind::Unrecognized,
+            Shape::UnclosedTextLine{..}              => Kind::UnclosedTextLine,
+            Shape::TextSegmentInvalidEscape(_)       => Kind::TextSegmentInvalidEscape,
+            Shape::TextSegmentUnclosedInterpolate(_) => Kind::TextSegmentUnclosedInterpolate,
+            Shape::UnclosedGroup{..}                 => Kind::UnclosedGroup,
+            Shape::UnmatchedBracket(_)               => Kind::UnmatchedBracket,
+        }
+    }
+}
+
+
+
+// ============
+// === Kind ===
+// ============
+
+/// The kinds of tokens, which are the shapes of tokens without their contents.
+///
+/// Automata can run over the kinds of tokens, so that patterns of tokens in a token stream can be
+/// matched with a [`crate::matcher::Matcher`].
+#[allow(missing_docs)]
+#[derive(Clone,Copy,Debug,Eq,Hash,Ord,PartialEq,PartialOrd)]
+pub enum Kind {
+    Referent,
+    Variable,
+    External,
+    Blank,
+    Operator,
+    Modifier,
+    Number,
+    DanglingBase,
+    TextLine,
+    TextBlock,
+    TextSegmentRaw,
+    TextSegmentEscape,
+    TextSegmentInterpolate,
+    Line,
+    BlankLine,
+    Block,
+    DisableComment,
+    DocComment,
+    Group,
+    InvalidSuffix,
+    Unrecognized,
+    UnclosedTextLine,
+    TextSegmentInvalidEscape,
+    TextSegmentUnclosedInterpolate,
+    UnclosedGroup,
+    UnmatchedBracket,
+}
+
+impl Kind {
+    /// All of the kinds of tokens, in order.
+    pub const ALL:[Kind;26] = [
+        Kind::Referent,
+        Kind::Variable,
+        Kind::External,
+        Kind::Blank,
+        Kind::Operator,
+        Kind::Modifier,
+        Kind::Number,
+        Kind::DanglingBase,
+        Kind::TextLine,
+        Kind::TextBlock,
+        Kind::TextSegmentRaw,
+        Kind::TextSegmentEscape,
+        Kind::TextSegmentInterpolate,
+        Kind::Line,
+        Kind::BlankLine,
+        Kind::Block,
+        Kind::DisableComment,
+        Kind::DocComment,
+        Kind::Group,
+        Kind::InvalidSuffix,
+        Kind::Unrecognized,
+        Kind::UnclosedTextLine,
+        Kind::TextSegmentInvalidEscape,
+        Kind::TextSegmentUnclosedInterpolate,
+        Kind::UnclosedGroup,
+        Kind::UnmatchedBracket,
+    ];
+}
+
+
+// === Trait Impls ===
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::Referent
+    }
+}
+
+impl Symbolic for Kind {
+    fn next(self) -> Option<Self> {
+        Kind::ALL.get(self as usize + 1).copied()
+    }
 }
 
 
@@ -426,6 +937,27 @@ impl Into<Vec<Token>> for Stream {
     }
 }
 
+impl TokenSink for Stream {
+    type Token  = Token;
+    type Output = Stream;
+
+    fn push(&mut self, token:Token) {
+        self.append(token)
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.tokens.len())
+    }
+
+    fn rollback(&mut self, checkpoint:Checkpoint) {
+        self.tokens.truncate(checkpoint.tokens())
+    }
+
+    fn finish(&mut self) -> Stream {
+        mem::take(self)
+    }
+}
+
 
 
 // =============
@@ -510,11 +1042,49 @@ mod tests {
     }
 
     #[test]
-    fn construct_text_token() {
-        let token = Token::Text("some prose goes here",0);
-        assert_shape(&token,Shape::text("some prose goes here"));
+    fn construct_text_line_token() {
+        let segments = vec![
+            Token::TextSegmentRaw("some prose goes here",0),
+            Token::TextSegmentEscape(EscapeStyle::Literal,"\\n",'\n',0),
+        ];
+        let token = Token::TextLine(TextStyle::RawLine,segments.clone(),0);
+        assert_shape(&token,Shape::text_line(TextStyle::RawLine,segments));
+        assert_length(&token,24);
+    }
+
+    #[test]
+    fn construct_text_block_token() {
+        let lines = vec![
+            Token::Line(vec![Token::TextSegmentRaw("foo",0)],0,LineEnding::CRLF),
+            Token::BlankLine(2,LineEnding::CRLF),
+            Token::Line(vec![Token::TextSegmentRaw("bar",0)],2,LineEnding::None),
+        ];
+        let token = Token::TextBlock(1,LineEnding::LF,TextStyle::RawBlock,4,lines.clone(),0);
+        assert_shape(&token,Shape::text_block(1,LineEnding::LF,TextStyle::RawBlock,4,lines));
+        assert_length(&token,27);
+    }
+
+    #[test]
+    fn construct_text_segment_raw_token() {
+        let token = Token::TextSegmentRaw("some prose goes here",0);
+        assert_shape(&token,Shape::text_segment_raw("some prose goes here"));
         assert_length(&token,20);
-        // TODO [AA] Make this internally account for length of quotes.
+    }
+
+    #[test]
+    fn construct_text_segment_escape_token() {
+        let style = EscapeStyle::Unicode;
+        let token = Token::TextSegmentEscape(style,"\\u{1F600}",'\u{1F600}',0);
+        assert_shape(&token,Shape::text_segment_escape(style,"\\u{1F600}",'\u{1F600}'));
+        assert_length(&token,9);
+    }
+
+    #[test]
+    fn construct_text_segment_interpolate_token() {
+        let tokens = vec![Token::Variable("name",1)];
+        let token  = Token::TextSegmentInterpolate(tokens.clone(),2,0);
+        assert_shape(&token,Shape::text_segment_interpolate(tokens,2));
+        assert_length(&token,9);
     }
 
     #[test]
@@ -554,6 +1124,33 @@ mod tests {
         assert_length(&token,16);
     }
 
+    #[test]
+    fn construct_disable_comment_token() {
+        let token = Token::DisableComment(" foo = bar",0);
+        assert_shape(&token,Shape::disable_comment(" foo = bar"));
+        assert_length(&token,11);
+    }
+
+    #[test]
+    fn construct_doc_comment_token() {
+        let lines = vec![
+            Token::Line(vec![Token::TextSegmentRaw(" Some",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("docs",0)],1,LineEnding::None),
+        ];
+        let token = Token::DocComment(lines.clone(),3,0);
+        assert_shape(&token,Shape::doc_comment(lines,3));
+        assert_length(&token,17);
+    }
+
+    #[test]
+    fn construct_group_token() {
+        let tokens = vec![Token::Variable("a",0),Token::Number("","1",1)];
+        let token  = Token::Group('[',tokens.clone(),LineEnding::LF,1,']',0);
+        assert_shape(&token,Shape::group('[',tokens,LineEnding::LF,1,']'));
+        assert_length(&token,7);
+    }
+
     #[test]
     fn construct_invalid_suffix_token() {
         let token = Token::InvalidSuffix("aaa",0);
@@ -567,4 +1164,48 @@ mod tests {
         assert_shape(&token,Shape::unrecognized("a"));
         assert_length(&token,1);
     }
+
+    #[test]
+    fn construct_unclosed_text_line_token() {
+        let segments = vec![Token::TextSegmentRaw("abc",0)];
+        let token    = Token::UnclosedTextLine(TextStyle::FormatLine,segments.clone(),0);
+        assert_shape(&token,Shape::unclosed_text_line(TextStyle::FormatLine,segments));
+        assert_length(&token,4);
+    }
+
+    #[test]
+    fn construct_text_segment_invalid_escape_token() {
+        let token = Token::TextSegmentInvalidEscape("\\q",0);
+        assert_shape(&token,Shape::text_segment_invalid_escape("\\q"));
+        assert_length(&token,2);
+    }
+
+    #[test]
+    fn construct_unclosed_group_token() {
+        let tokens = vec![Token::Variable("a",0)];
+        let token  = Token::UnclosedGroup('(',tokens.clone(),0);
+        assert_shape(&token,Shape::unclosed_group('(',tokens));
+        assert_length(&token,2);
+    }
+
+    #[test]
+    fn construct_unmatched_bracket_token() {
+        let token = Token::UnmatchedBracket('}',0);
+        assert_shape(&token,Shape::unmatched_bracket('}'));
+        assert_length(&token,1);
+    }
+
+
+    // === Tests for Spans ===
+
+    #[test]
+    fn covering_span_of_tokens() {
+        let tokens = vec![
+            Token::Variable("a",1).with_span(Span::new(2,3)),
+            Token::Operator("+",1).with_span(Span::new(4,5)),
+            Token::Variable("b",0).with_span(Span::new(6,7)),
+        ];
+        assert_eq!(Token::covering_span(&tokens),Some(Span::new(2,7)));
+        assert_eq!(Token::covering_span(&[]),None);
+    }
 }
@@ -0,0 +1,200 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![allow(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains benchmarks for the throughput and allocation behaviour of the Enso Lexer.
+//!
+//! Run them with `cargo bench -p lexer -- --nocapture` to see the number of allocations made per
+//! token alongside the throughput. The allocations are counted both for a new lexer and for one
+//! that is reset after a run, whose buffers have already grown, and compared to the number of
+//! symbols that the tokens hold, of which only the distinct ones are allocated. Adding
+//! `--features trace` benchmarks the lexer with logging compiled into it instead.
+
+extern crate test;
+
+use flexer::*;
+use lexer_definition::library::*;
+
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer::prelude::Reader;
+use lexer::generated::engine::EnsoLexer;
+use lexer_definition::library::symbol::Symbol;
+use lexer_definition::library::token::Token;
+use std::collections::HashSet;
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use test::Bencher;
+
+
+
+// ==========================
+// === Counting Allocator ===
+// ==========================
+
+/// The number of allocations made by the benchmark process so far.
+static ALLOCATIONS:AtomicUsize = AtomicUsize::new(0);
+
+/// An allocator that counts every allocation and reallocation before delegating to the system
+/// allocator.
+#[derive(Clone,Copy,Debug)]
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout:Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1,Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr:*mut u8, layout:Layout) {
+        System.dealloc(ptr,layout)
+    }
+
+    unsafe fn realloc(&self, ptr:*mut u8, layout:Layout, new_size:usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1,Ordering::Relaxed);
+        System.realloc(ptr,layout,new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR:CountingAllocator = CountingAllocator;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Generate a synthetic Enso module containing `functions` function definitions.
+fn synthetic_module(functions:usize) -> String {
+    let mut module = String::new();
+    for ix in 0..functions {
+        module.push_str(&format!("function_{} arg_a arg_b =\n",ix));
+        module.push_str(&format!("    result = arg_a + arg_b * {}\n",ix));
+        module.push_str("    other  = 16_ff - result.abs\n");
+        module.push_str("    loop = x -> if x > 0 then loop (x - 1) else result\n");
+        module.push_str("    Pair other (loop result)\n");
+        module.push('\n');
+    }
+    module
+}
+
+/// Lex the provided string.
+fn lex(input:&str) -> LexingResult<token::Stream> {
+    lex_with(&mut EnsoLexer::new(),input)
+}
+
+/// Lex the provided string with `lexer`.
+fn lex_with(lexer:&mut EnsoLexer, input:&str) -> LexingResult<token::Stream> {
+    let reader = Reader::new(input.as_bytes(),DecoderUTF8());
+    lexer.run(reader)
+}
+
+/// Count the allocations made while lexing `input` with `lexer`.
+fn count_allocations(lexer:&mut EnsoLexer, input:&str) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = lex_with(lexer,input);
+    let after  = ALLOCATIONS.load(Ordering::Relaxed);
+    assert!(matches!(result.kind,ResultKind::Success),"The synthetic module should lex.");
+    after - before
+}
+
+/// The tokens nested directly inside `token`.
+fn children(token:&Token) -> &[Token] {
+    match &token.shape {
+        token::Shape::TextLine{segments,..}                  => segments,
+        token::Shape::UnclosedTextLine{segments,..}          => segments,
+        token::Shape::TextBlock{lines,..}                    => lines,
+        token::Shape::TextSegmentInterpolate{tokens,..}      => tokens,
+        token::Shape::TextSegmentUnclosedInterpolate(tokens) => tokens,
+        token::Shape::Line{tokens,..}                        => tokens,
+        token::Shape::Block{lines,..}                        => lines,
+        token::Shape::DocComment{lines,..}                   => lines,
+        token::Shape::Group{tokens,..}                       => tokens,
+        token::Shape::UnclosedGroup{tokens,..}               => tokens,
+        _                                                    => &[],
+    }
+}
+
+/// Count the tokens in `tokens`, including all of those nested inside them.
+fn count_tokens(tokens:&[Token]) -> usize {
+    tokens.iter().map(|token| 1 + count_tokens(children(token))).sum()
+}
+
+/// Collect the symbols held by the tokens in `tokens`, including all of those nested
+/// inside them.
+fn collect_symbols<'a>(tokens:&'a [Token], symbols:&mut Vec<&'a Symbol>) {
+    for token in tokens {
+        match &token.shape {
+            token::Shape::Referent(name)                 => symbols.push(name),
+            token::Shape::Variable(name)                 => symbols.push(name),
+            token::Shape::External(name)                 => symbols.push(name),
+            token::Shape::Operator(name)                 => symbols.push(name),
+            token::Shape::Modifier(name)                 => symbols.push(name),
+            token::Shape::Number{base,number}            => symbols.extend(&[base,number]),
+            token::Shape::DanglingBase(base)             => symbols.push(base),
+            token::Shape::TextSegmentRaw(text)           => symbols.push(text),
+            token::Shape::TextSegmentEscape{repr,..}     => symbols.push(repr),
+            token::Shape::DisableComment(text)           => symbols.push(text),
+            token::Shape::InvalidSuffix(text)            => symbols.push(text),
+            token::Shape::Unrecognized(text)             => symbols.push(text),
+            token::Shape::TextSegmentInvalidEscape(repr) => symbols.push(repr),
+            _                                            => {}
+        }
+        collect_symbols(children(token),symbols);
+    }
+}
+
+
+
+// ==================
+// === Benchmarks ===
+// ==================
+
+#[bench]
+fn bench_large_module(bencher:&mut Bencher) {
+    let input = synthetic_module(1000);
+    bencher.bytes = input.len() as u64;
+    bencher.iter(|| lex(&input));
+}
+
+#[bench]
+fn bench_large_module_reused_lexer(bencher:&mut Bencher) {
+    let input     = synthetic_module(1000);
+    let mut lexer = EnsoLexer::new();
+    bencher.bytes = input.len() as u64;
+    bencher.iter(|| {
+        let result = lex_with(&mut lexer,&input);
+        lexer.reset();
+        result
+    });
+}
+
+#[bench]
+fn bench_allocations_per_token(bencher:&mut Bencher) {
+    let input       = synthetic_module(1000);
+    let result      = lex(&input);
+    let tokens      = count_tokens(result.tokens.tokens());
+    let mut symbols = Vec::new();
+    collect_symbols(result.tokens.tokens(),&mut symbols);
+    symbols.retain(|symbol| !symbol.is_empty());
+    let distinct    = symbols.iter().map(|symbol| symbol.as_str()).collect::<HashSet<_>>();
+    let mut lexer   = EnsoLexer::new();
+    let new_lexer   = count_allocations(&mut lexer,&input);
+    lexer.reset();
+    let reset_lexer = count_allocations(&mut lexer,&input);
+    let per_token   = |allocations:usize| allocations as f64 / tokens as f64;
+    println!("{} tokens, holding {} symbols of which {} are distinct ({:.2} per token).",
+        tokens,symbols.len(),distinct.len(),per_token(distinct.len()));
+    println!("New lexer: {} allocations ({:.2} per token).",new_lexer,per_token(new_lexer));
+    println!("Reset lexer: {} allocations ({:.2} per token).",reset_lexer,per_token(reset_lexer));
+    bencher.iter(|| lex(&input));
+}
@@ -2,13 +2,17 @@ use std::fs::File;
 use lexer_definition::lexer::EnsoLexer;
 use std::io::prelude::*;
 use flexer::Definition;
-use flexer::State;
+use flexer::generate::CodegenOptions;
+use flexer::generate::specialize_with_report;
 
 
 
 /// Generates the lexer engine and saves the result into the file `src/engine.rs`.
 ///
-/// The content of the generated file can be used with the `include!` macro.
+/// The content of the generated file can be used with the `include!` macro. The size of the engine
+/// is written to `engine_size.txt` in the output directory of the build script.
+///
+/// The engine only logs its progress when the `trace` feature is enabled.
 fn generate_engine() -> std::io::Result<()> {
     let definition_path  = "../definition/src/lexer.rs";
     let output_directory = "src/generated";
@@ -20,7 +24,12 @@ fn generate_engine() -> std::io::Result<()> {
     let mut contents     = String::new();
     let mut file         = File::create(output_path).expect(output_error.as_str());
     let lexer            = EnsoLexer::define();
-    let engine           = lexer.specialize().unwrap();
+    let options          = CodegenOptions::default();
+    let result           = specialize_with_report(&**lexer,"EnsoLexer","token::Stream",&options);
+    let (engine,report)  = result.unwrap();
+    let out_directory    = std::env::var("OUT_DIR").expect("Cargo sets the output directory.");
+    let report_path      = std::path::Path::new(&out_directory).join("engine_size.txt");
+    std::fs::write(report_path,format!("{}\n",report)).expect("Unable to write the size report.");
     lexer_def.read_to_string(&mut contents).expect("Unable to read lexer definition.");
     file.write_all(contents.as_bytes()).expect("Unable to write lexer definition.");
     file.write_all(engine.as_bytes()).expect("Unable to write lexer specialization.");
@@ -0,0 +1,16 @@
+use lexer_definition::grammar;
+
+
+
+// =====================
+// === ExportGrammar ===
+// =====================
+
+/// Exports the TextMate grammar for Enso into the file `grammar/enso.tmLanguage.json`.
+///
+/// The grammar is kept in the repository, so that editor extensions can use it without building
+/// the lexer, and has to be exported again whenever the annotations of the lexer change.
+fn main() -> std::io::Result<()> {
+    let output_path = concat!(env!("CARGO_MANIFEST_DIR"),"/grammar/enso.tmLanguage.json");
+    std::fs::write(output_path,grammar::grammar())
+}
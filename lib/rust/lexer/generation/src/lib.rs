@@ -11,6 +11,7 @@
 //! This module exports the interface to the generated Enso lexer.
 
 pub mod generated;
+pub mod parallel;
 
 /// Support libraries for the lexer definition.
 ///
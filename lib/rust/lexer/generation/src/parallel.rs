@@ -0,0 +1,116 @@
+//! This module provides utilities for lexing many files at once, such as all of the files in an
+//! Enso project, using a pool of threads.
+
+use crate::prelude::*;
+
+use crate::generated::engine::EnsoLexer;
+use crate::library::token;
+
+use flexer::LexingResult;
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+
+
+// ==================
+// === FileResult ===
+// ==================
+
+/// The result of lexing a single file.
+#[derive(Debug)]
+pub struct FileResult {
+    /// The path of the file.
+    pub path:PathBuf,
+    /// The result of lexing the file, or the error that prevented reading or lexing it.
+    pub result:io::Result<LexingResult<token::Stream>>,
+    /// The time spent lexing the file, not including the time spent reading it.
+    pub time:Duration,
+}
+
+
+
+// ================
+// === Lex Many ===
+// ================
+
+/// Lex the files at `paths` using up to `threads` threads, returning their results in the order of
+/// `paths`.
+///
+/// Every thread lexes files with a clone of a single lexer, which it resets before each of them.
+pub fn lex_many(paths:&[PathBuf], threads:usize) -> Vec<FileResult> {
+    lex_many_with(paths,threads,lex_file)
+}
+
+/// Lex the files at `paths` with `lex` using up to `threads` threads, returning their results in
+/// the order of `paths`.
+///
+/// The lexer is defined once, and every thread passes its own clone of it to `lex` for each of its
+/// files. The lexer reports the failure to lex a file in the kind of its result, so `lex` is not
+/// expected to panic. Panics are not recovered from, as the release profile aborts on them, and
+/// are otherwise passed on to the caller once the other threads have finished.
+pub fn lex_many_with<F>(paths:&[PathBuf], threads:usize, lex:F) -> Vec<FileResult>
+where F : Fn(&mut EnsoLexer,&Path) -> FileResult + Send + Sync + 'static {
+    let threads           = threads.max(1).min(paths.len());
+    let lexer             = EnsoLexer::new();
+    let paths             = Arc::new(paths.to_vec());
+    let lex               = Arc::new(lex);
+    let next              = Arc::new(AtomicUsize::new(0));
+    let (sender,receiver) = mpsc::channel();
+    let workers           = (0..threads).map(|_| {
+        let mut lexer = lexer.clone();
+        let paths     = paths.clone();
+        let lex       = lex.clone();
+        let next      = next.clone();
+        let sender    = sender.clone();
+        thread::spawn(move || {
+            loop {
+                let ix   = next.fetch_add(1,Ordering::Relaxed);
+                let path = match paths.get(ix) {
+                    Some(path) => path,
+                    None       => break,
+                };
+                sender.send((ix,lex(&mut lexer,path))).ok();
+            }
+        })
+    }).collect_vec();
+    drop(sender);
+    let mut results = receiver.iter().collect_vec();
+    for worker in workers {
+        if let Err(panic) = worker.join() {
+            panic::resume_unwind(panic)
+        }
+    }
+    results.sort_by_key(|(ix,_)| *ix);
+    results.into_iter().map(|(_,result)| result).collect()
+}
+
+/// Lex the file at `path` with `lexer`, resetting it first.
+pub fn lex_file(lexer:&mut EnsoLexer, path:&Path) -> FileResult {
+    let path = path.to_path_buf();
+    match fs::read(&path) {
+        Err(error) => {
+            let result = Err(error);
+            let time   = Duration::default();
+            FileResult{path,result,time}
+        }
+        Ok(input) => {
+            let start = Instant::now();
+            lexer.reset();
+            let reader = Reader::new(input.as_slice(),DecoderUTF8());
+            let result = Ok(lexer.run(reader));
+            let time   = start.elapsed();
+            FileResult{path,result,time}
+        }
+    }
+}
@@ -0,0 +1,26 @@
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file checks that the TextMate grammar committed next to the lexer is up to date.
+
+use lexer_definition::grammar;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn test_committed_grammar() {
+    let committed = include_str!("../grammar/enso.tmLanguage.json");
+    assert_eq!(grammar::grammar(),committed,
+        "The committed grammar is out of date. Run `cargo run --bin export_grammar` in \
+        `lib/rust/lexer/generation` to update it.");
+}
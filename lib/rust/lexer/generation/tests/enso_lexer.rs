@@ -15,12 +15,17 @@
 use flexer::*;
 use lexer_definition::library::*;
 
+use flexer::diagnostic::Diagnostic;
+use flexer::diagnostic::Severity;
 use flexer::prelude::reader::decoder::DecoderUTF8;
 use flexer::prelude::Reader;
+use flexer::span::Span;
 use lexer::generated::engine::EnsoLexer;
 use lexer_definition::library::token::Token;
 use lexer_definition::token::BlockType;
+use lexer_definition::token::EscapeStyle;
 use lexer_definition::token::LineEnding;
+use lexer_definition::token::TextStyle;
 
 
 
@@ -46,6 +51,11 @@ fn assert_lexes(input:impl AsRef<str>, expected:token::Stream) {
     assert_eq!(total_length,input_len);
 }
 
+/// Assert that lexing the provided input reports the `expected` diagnostics.
+fn assert_reports(input:impl AsRef<str>, expected:Vec<Diagnostic>) {
+    assert_eq!(lex(input).diagnostics,expected);
+}
+
 /// Lex the provided string.
 fn lex(input:impl AsRef<str>) -> LexingResult<token::Stream> {
     let mut lexer = EnsoLexer::new();
@@ -69,6 +79,23 @@ fn assert_block_has_length(input:impl AsRef<str>, expected_length:usize) {
     }
 }
 
+/// Get the tokens that are nested directly in `token`.
+fn children(token:&Token) -> &[Token] {
+    match &token.shape {
+        token::Shape::TextLine{segments,..}                  => segments,
+        token::Shape::UnclosedTextLine{segments,..}          => segments,
+        token::Shape::TextBlock{lines,..}                    => lines,
+        token::Shape::TextSegmentInterpolate{tokens,..}      => tokens,
+        token::Shape::TextSegmentUnclosedInterpolate(tokens) => tokens,
+        token::Shape::Line{tokens,..}                        => tokens,
+        token::Shape::Block{lines,..}                        => lines,
+        token::Shape::DocComment{lines,..}                   => lines,
+        token::Shape::Group{tokens,..}                       => tokens,
+        token::Shape::UnclosedGroup{tokens,..}               => tokens,
+        _                                                    => &[],
+    }
+}
+
 /// Makes the test text have unix line endings to ensure consistency regardless of git checkout
 /// style.
 fn make_unix_line_endings(input:&str) -> String {
@@ -208,20 +235,6 @@ fn typeset_subtraction_operator() {
     assert_lexes(input,expected);
 }
 
-#[test]
-fn disable_comment() {
-    let input    = "#";
-    let expected = token::Stream::from(vec![Token::Operator("#",0)]);
-    assert_lexes(input,expected);
-}
-
-#[test]
-fn doc_comment() {
-    let input    = "##";
-    let expected = token::Stream::from(vec![Token::Operator("##",0)]);
-    assert_lexes(input,expected);
-}
-
 #[test]
 fn arbitrary_left_operator() {
     let input    = "<!!-";
@@ -477,117 +490,342 @@ fn offset_number() {
     assert_lexes(input,expected);
 }
 
+#[test]
+fn invalid_digits_for_base() {
+    let input    = "x = 2_1012.21";
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::Number("2","1012.21",1),
+    ]);
+    assert_lexes(input,expected);
+    let message = |index:usize| {
+        format!("`2` at offset {} is not a digit in the base of the number.",index)
+    };
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,message(2),Span::new(9,10)),
+        Diagnostic::new(Severity::Error,message(4),Span::new(11,12)),
+    ]);
+}
+
+#[test]
+fn invalid_hex_digits() {
+    let input   = "16_fFgA 10_ff";
+    let message = |digit:char, index:usize| {
+        format!("`{}` at offset {} is not a digit in the base of the number.",digit,index)
+    };
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,message('g',2),Span::new(5,6)),
+        Diagnostic::new(Severity::Error,message('f',0),Span::new(11,12)),
+        Diagnostic::new(Severity::Error,message('f',1),Span::new(12,13)),
+    ]);
+}
+
+#[test]
+fn unsupported_base() {
+    let input    = "1_0 37_z 36_z";
+    let expected = token::Stream::from(vec![
+        Token::Number("1","0",0),
+        Token::Number("37","z",1),
+        Token::Number("36","z",1),
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,"Base 1 is not between 2 and 36.",Span::new(0,1)),
+        Diagnostic::new(Severity::Error,"Base 37 is not between 2 and 36.",Span::new(4,6)),
+    ]);
+}
+
 
 
 // ============
 // === Text ===
 // ============
 
+#[test]
+fn raw_text_line() {
+    let input    = r#""some prose goes here""#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::RawLine,vec![Token::TextSegmentRaw("some prose goes here",0)],0),
+    ]);
+    assert_lexes(input,expected);
+}
 
+#[test]
+fn format_text_line() {
+    let input    = "'some prose goes here'";
+    let expected = token::Stream::from(vec![
+        Token::TextLine(
+            TextStyle::FormatLine,
+            vec![Token::TextSegmentRaw("some prose goes here",0)],
+            0
+        ),
+    ]);
+    assert_lexes(input,expected);
+}
 
-// ==============
-// === Blocks ===
-// ==============
+#[test]
+fn empty_text_line() {
+    let input    = r#""" ''"#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::RawLine,vec![],0),
+        Token::TextLine(TextStyle::FormatLine,vec![],1),
+    ]);
+    assert_lexes(input,expected);
+}
 
 #[test]
-fn block_function_call() {
-    let input = make_unix_line_endings(
-r#"f
-    argument_1
-    argument_2
-    fn a1 a2 a3
-    argument_4
-    argument_5"#);
-    let block_fn_args =
+fn text_line_with_other_quote() {
+    let input    = r#""it's" '"quoted"'"#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::RawLine,vec![Token::TextSegmentRaw("it's",0)],0),
+        Token::TextLine(TextStyle::FormatLine,vec![Token::TextSegmentRaw(r#""quoted""#,0)],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_in_expression() {
+    let input    = r#"name = "Enso""#;
+    let expected = token::Stream::from(vec![
+        Token::Variable("name",0),
+        Token::Operator("=",1),
+        Token::TextLine(TextStyle::RawLine,vec![Token::TextSegmentRaw("Enso",0)],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_escapes() {
+    let input    = r#"'a\nb\t\u{1F600}\xFF\'\\'"#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentRaw("a",0),
+            Token::TextSegmentEscape(EscapeStyle::Literal,r"\n",'\n',0),
+            Token::TextSegmentRaw("b",0),
+            Token::TextSegmentEscape(EscapeStyle::Literal,r"\t",'\t',0),
+            Token::TextSegmentEscape(EscapeStyle::Unicode,r"\u{1F600}",'\u{1F600}',0),
+            Token::TextSegmentEscape(EscapeStyle::Byte,r"\xFF",'\u{FF}',0),
+            Token::TextSegmentEscape(EscapeStyle::Literal,r"\'",'\'',0),
+            Token::TextSegmentEscape(EscapeStyle::Literal,r"\\",'\\',0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_invalid_escapes() {
+    let input    = r#""\q \u{110000} \u{D800} \u{0000041} \x4""#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::RawLine,vec![
+            Token::TextSegmentInvalidEscape(r"\q",0),
+            Token::TextSegmentRaw(" ",0),
+            Token::TextSegmentInvalidEscape(r"\u{110000}",0),
+            Token::TextSegmentRaw(" ",0),
+            Token::TextSegmentInvalidEscape(r"\u{D800}",0),
+            Token::TextSegmentRaw(" ",0),
+            Token::TextSegmentInvalidEscape(r"\u{0000041}",0),
+            Token::TextSegmentRaw(" ",0),
+            Token::TextSegmentInvalidEscape(r"\x",0),
+            Token::TextSegmentRaw("4",0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_splice() {
+    let input    = "'Hello `name`!'";
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentRaw("Hello ",0),
+            Token::TextSegmentInterpolate(vec![Token::Variable("name",0)],0,0),
+            Token::TextSegmentRaw("!",0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_splice_with_spaces() {
+    let input    = "'` a + 1  `'";
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentInterpolate(vec![
+                Token::Variable("a",1),
+                Token::Operator("+",1),
+                Token::Number("","1",1),
+            ],2,0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_line_nested_splice() {
+    let input    = "'a `f 'b `c`'` d'";
+    let inner    = Token::TextLine(TextStyle::FormatLine,vec![
+        Token::TextSegmentRaw("b ",0),
+        Token::TextSegmentInterpolate(vec![Token::Variable("c",0)],0,0),
+    ],1);
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentRaw("a ",0),
+            Token::TextSegmentInterpolate(vec![Token::Variable("f",0),inner],0,0),
+            Token::TextSegmentRaw(" d",0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn raw_text_line_no_splice() {
+    let input    = r#""a `b`""#;
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::RawLine,vec![Token::TextSegmentRaw("a `b`",0)],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_splice_at_eof() {
+    let input    = "'a `b";
+    let expected = token::Stream::from(vec![
+        Token::UnclosedTextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentRaw("a ",0),
+            Token::TextSegmentUnclosedInterpolate(vec![Token::Variable("b",0)],0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_nested_splice_at_eof() {
+    let input    = "'a `'b `c";
+    let inner    = Token::UnclosedTextLine(TextStyle::FormatLine,vec![
+        Token::TextSegmentRaw("b ",0),
+        Token::TextSegmentUnclosedInterpolate(vec![Token::Variable("c",0)],0),
+    ],0);
+    let expected = token::Stream::from(vec![
+        Token::UnclosedTextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentRaw("a ",0),
+            Token::TextSegmentUnclosedInterpolate(vec![inner],0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_splice_at_newline() {
+    let input    = make_unix_line_endings(
+r#"'a `b
+c
+"#);
+    let unclosed = Token::UnclosedTextLine(TextStyle::FormatLine,vec![
+        Token::TextSegmentRaw("a ",0),
+        Token::TextSegmentUnclosedInterpolate(vec![Token::Variable("b",0)],0),
+    ],0);
+    let expected = token::Stream::from(vec![
         Token::Block(
             BlockType::Continuous,
-            4,
+            0,
             vec![
-                Token::Line(
-                    vec![Token::Variable("argument_1",0)],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_2",0),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("fn",0),
-                        Token::Variable("a1",1),
-                        Token::Variable("a2",1),
-                        Token::Variable("a3",1),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_4",0),
-                    ],
-                    0,
-                    LineEnding::LF
-                ),
-                Token::Line(
-                    vec![
-                        Token::Variable("argument_5",0),
-                    ],
-                    0,
-                    LineEnding::None
-                ),
+                Token::Line(vec![unclosed],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("c",0)],0,LineEnding::LF),
             ],
             0
-        );
-    let top_level_first_line = Token::Line(
-        vec![
-            Token::Variable("f",0),
-            block_fn_args
-        ],
-        0,
-        LineEnding::LF
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_text_line_at_eof() {
+    let input    = r#"foo "bar\"#;
+    let expected = token::Stream::from(vec![
+        Token::Variable("foo",0),
+        Token::UnclosedTextLine(TextStyle::RawLine,vec![
+            Token::TextSegmentRaw("bar",0),
+            Token::TextSegmentInvalidEscape(r"\",0),
+        ],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn unclosed_text_line_at_newline() {
+    let input    = make_unix_line_endings(
+r#"foo 'bar
+baz
+"#);
+    let unclosed = Token::UnclosedTextLine(
+        TextStyle::FormatLine,
+        vec![Token::TextSegmentRaw("bar",0)],
+        1
     );
-    let top_level_block = token::Stream::from(vec![
+    let expected = token::Stream::from(vec![
         Token::Block(
             BlockType::Continuous,
             0,
-            vec![top_level_first_line],
+            vec![
+                Token::Line(vec![Token::Variable("foo",0),unclosed],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::LF),
+            ],
             0
         )
     ]);
-    assert_lexes(input,top_level_block);
+    assert_lexes(input,expected);
 }
 
-
 #[test]
-fn block_empty_lines() {
-    let input        = "f\r\n    a\n\n    b\n";
-    let nested_block = Token::Block(
-        BlockType::Continuous,
+fn text_block() {
+    let input    = make_unix_line_endings(
+r#"x = """
+    part of the string
+        still part of the string
+
+    also part of the string
+
+y = foo
+"#);
+    let text_block = Token::TextBlock(
+        0,
+        LineEnding::LF,
+        TextStyle::RawBlock,
         4,
         vec![
-            Token::Line(vec![Token::Variable("a",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::TextSegmentRaw("part of the string",0)],0,LineEnding::LF),
+            Token::Line(
+                vec![Token::TextSegmentRaw("still part of the string",0)],
+                4,
+                LineEnding::LF
+            ),
             Token::BlankLine(0,LineEnding::LF),
-            Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF),
-        ],
-        0
-    );
-    let top_line = Token::Line(
-        vec![
-            Token::Variable("f",0),
-            nested_block
+            Token::Line(
+                vec![Token::TextSegmentRaw("also part of the string",0)],
+                0,
+                LineEnding::None
+            ),
         ],
-        0,
-        LineEnding::CRLF
+        1
     );
     let expected = token::Stream::from(vec![
         Token::Block(
             BlockType::Continuous,
             0,
-            vec![top_line],
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    text_block,
+                ],0,LineEnding::LF),
+                Token::BlankLine(0,LineEnding::LF),
+                Token::Line(vec![
+                    Token::Variable("y",0),
+                    Token::Operator("=",1),
+                    Token::Variable("foo",1),
+                ],0,LineEnding::LF),
+            ],
             0
         )
     ]);
@@ -595,24 +833,31 @@ fn block_empty_lines() {
 }
 
 #[test]
-fn block_top_level() {
-    let input = make_unix_line_endings(
-r#"
-
-foo
-bar
-baz
+fn format_text_block() {
+    let input    = make_unix_line_endings(
+r#"'''
+  Hello `name`,\tthe 'end'
+  is "near"
 "#);
     let expected = token::Stream::from(vec![
         Token::Block(
             BlockType::Continuous,
             0,
             vec![
-                Token::BlankLine(0,LineEnding::LF),
-                Token::BlankLine(0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::LF),
-                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::LF),
+                Token::Line(vec![
+                    Token::TextBlock(0,LineEnding::LF,TextStyle::FormatBlock,2,vec![
+                        Token::Line(vec![
+                            Token::TextSegmentRaw("Hello ",0),
+                            Token::TextSegmentInterpolate(vec![Token::Variable("name",0)],0,0),
+                            Token::TextSegmentRaw(",",0),
+                            Token::TextSegmentEscape(EscapeStyle::Literal,r"\t",'\t',0),
+                            Token::TextSegmentRaw("the 'end'",0),
+                        ],0,LineEnding::LF),
+                        Token::Line(vec![
+                            Token::TextSegmentRaw(r#"is "near""#,0),
+                        ],0,LineEnding::None),
+                    ],0),
+                ],0,LineEnding::LF),
             ],
             0
         )
@@ -621,23 +866,13 @@ baz
 }
 
 #[test]
-fn block_with_operator() {
-    let input = make_unix_line_endings(
-r#"x ->
-    foo x 1
-"#);
-    let nested_block = Token::Block(
-        BlockType::Discontinuous,
-        4,
-        vec![
-            Token::Line(vec![
-                Token::Variable("foo",0),
-                Token::Variable("x",1),
-                Token::Number("","1",1),
-            ], 0, LineEnding::LF)
-        ],
-        0
-    );
+fn text_block_crlf() {
+    let input    = "x = \"\"\"\r\n  foo\r\n\r\n  bar\r\ny";
+    let text_block = Token::TextBlock(0,LineEnding::CRLF,TextStyle::RawBlock,2,vec![
+        Token::Line(vec![Token::TextSegmentRaw("foo",0)],0,LineEnding::CRLF),
+        Token::BlankLine(0,LineEnding::CRLF),
+        Token::Line(vec![Token::TextSegmentRaw("bar",0)],0,LineEnding::None),
+    ],1);
     let expected = token::Stream::from(vec![
         Token::Block(
             BlockType::Continuous,
@@ -645,9 +880,10 @@ r#"x ->
             vec![
                 Token::Line(vec![
                     Token::Variable("x",0),
-                    Token::Operator("->",1),
-                    nested_block
-                ], 0, LineEnding::LF)
+                    Token::Operator("=",1),
+                    text_block,
+                ],0,LineEnding::CRLF),
+                Token::Line(vec![Token::Variable("y",0)],0,LineEnding::None),
             ],
             0
         )
@@ -656,8 +892,792 @@ r#"x ->
 }
 
 #[test]
-fn block_with_nesting() {
-    let input = make_unix_line_endings(r#"
+fn text_block_in_block() {
+    let input    = make_unix_line_endings(
+r#"f =
+    x = """
+      a
+        b
+    y
+"#);
+    let text_block = Token::TextBlock(0,LineEnding::LF,TextStyle::RawBlock,6,vec![
+        Token::Line(vec![Token::TextSegmentRaw("a",0)],0,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("b",0)],2,LineEnding::None),
+    ],1);
+    let nested_block = Token::Block(
+        BlockType::Discontinuous,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::Variable("x",0),
+                Token::Operator("=",1),
+                text_block,
+            ],0,LineEnding::LF),
+            Token::Line(vec![Token::Variable("y",0)],0,LineEnding::LF),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("f",0),
+                    Token::Operator("=",1),
+                    nested_block,
+                ],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn empty_text_block() {
+    let input    = make_unix_line_endings(
+r#"x = """
+y
+"#);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    Token::TextBlock(0,LineEnding::None,TextStyle::RawBlock,0,vec![],1),
+                ],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("y",0)],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_at_eof() {
+    let input    = "x = '''\n  foo";
+    let text_block = Token::TextBlock(0,LineEnding::LF,TextStyle::FormatBlock,2,vec![
+        Token::Line(vec![Token::TextSegmentRaw("foo",0)],0,LineEnding::None),
+    ],1);
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        text_block,
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_ending_at_eof() {
+    let input    = "x = '''\n  foo\n\n";
+    let text_block = Token::TextBlock(0,LineEnding::LF,TextStyle::FormatBlock,2,vec![
+        Token::Line(vec![Token::TextSegmentRaw("foo",0)],0,LineEnding::None),
+    ],1);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    text_block,
+                ],0,LineEnding::LF),
+                Token::BlankLine(0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_opened_at_eof() {
+    let input    = "x = \"\"\"";
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::TextBlock(0,LineEnding::None,TextStyle::RawBlock,0,vec![],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_with_trailing_spaces() {
+    let input    = "x = '''  \n  foo";
+    let text_block = Token::TextBlock(2,LineEnding::LF,TextStyle::FormatBlock,2,vec![
+        Token::Line(vec![Token::TextSegmentRaw("foo",0)],0,LineEnding::None),
+    ],1);
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        text_block,
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn text_block_with_trailing_spaces_at_eof() {
+    let input    = "x = \"\"\"   ";
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::TextBlock(3,LineEnding::None,TextStyle::RawBlock,0,vec![],1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+
+// ================
+// === Comments ===
+// ================
+
+#[test]
+fn disable_comment() {
+    let input    = "#";
+    let expected = token::Stream::from(vec![Token::DisableComment("",0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_after_code() {
+    let input    = "x = y # foo ## bar";
+    let expected = token::Stream::from(vec![
+        Token::Variable("x",0),
+        Token::Operator("=",1),
+        Token::Variable("y",1),
+        Token::DisableComment(" foo ## bar",1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn disable_comment_lines() {
+    let input    = "# foo\nx";
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![Token::DisableComment(" foo",0)],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn hash_equals_operator() {
+    let input    = "a #= b";
+    let expected = token::Stream::from(vec![
+        Token::Variable("a",0),
+        Token::Operator("#=",1),
+        Token::Variable("b",1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment() {
+    let input    = "##";
+    let lines    = vec![Token::Line(vec![],0,LineEnding::None)];
+    let expected = token::Stream::from(vec![Token::DocComment(lines,0,0)]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_multiline() {
+    let input       = "## My documentation comment\n   continues\n     down\nx";
+    let doc_comment = Token::DocComment(vec![
+        Token::Line(vec![Token::TextSegmentRaw(" My documentation comment",0)],0,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("continues",0)],0,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("down",0)],2,LineEnding::None),
+    ],3,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_blank_lines() {
+    let input       = "## foo\n\n  \n   bar\n\nx";
+    let doc_comment = Token::DocComment(vec![
+        Token::Line(vec![Token::TextSegmentRaw(" foo",0)],0,LineEnding::LF),
+        Token::BlankLine(0,LineEnding::LF),
+        Token::BlankLine(2,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("bar",0)],0,LineEnding::None),
+    ],3,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::LF),
+                Token::BlankLine(0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_crlf() {
+    let input       = "## foo\r\n   bar\r\nx";
+    let doc_comment = Token::DocComment(vec![
+        Token::Line(vec![Token::TextSegmentRaw(" foo",0)],0,LineEnding::CRLF),
+        Token::Line(vec![Token::TextSegmentRaw("bar",0)],0,LineEnding::None),
+    ],3,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::CRLF),
+                Token::Line(vec![Token::Variable("x",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_in_block() {
+    let input       = "x =\n    ## foo\n       bar\n    y\nz";
+    let doc_comment = Token::DocComment(vec![
+        Token::Line(vec![Token::TextSegmentRaw(" foo",0)],0,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("bar",0)],0,LineEnding::None),
+    ],7,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    Token::Block(
+                        BlockType::Discontinuous,
+                        4,
+                        vec![
+                            Token::Line(vec![doc_comment],0,LineEnding::LF),
+                            Token::Line(vec![Token::Variable("y",0)],0,LineEnding::LF),
+                        ],
+                        0
+                    )
+                ],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("z",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn doc_comment_at_eof() {
+    let input       = "## foo\n   bar\n";
+    let doc_comment = Token::DocComment(vec![
+        Token::Line(vec![Token::TextSegmentRaw(" foo",0)],0,LineEnding::LF),
+        Token::Line(vec![Token::TextSegmentRaw("bar",0)],0,LineEnding::None),
+    ],3,0);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![doc_comment],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+
+
+// ==============
+// === Groups ===
+// ==============
+
+#[test]
+fn group_nested() {
+    let input    = "(a [b] {c})";
+    let expected = token::Stream::from(vec![
+        Token::Group('(',vec![
+            Token::Variable("a",0),
+            Token::Group('[',vec![Token::Variable("b",0)],LineEnding::None,0,']',1),
+            Token::Group('{',vec![Token::Variable("c",0)],LineEnding::None,0,'}',1),
+        ],LineEnding::None,0,')',0),
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn group_empty() {
+    let input    = "f () []";
+    let expected = token::Stream::from(vec![
+        Token::Variable("f",0),
+        Token::Group('(',vec![],LineEnding::None,0,')',1),
+        Token::Group('[',vec![],LineEnding::None,0,']',1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn group_with_spaces() {
+    let input    = "f ( a  b )";
+    let expected = token::Stream::from(vec![
+        Token::Variable("f",0),
+        Token::Group('(',vec![Token::Variable("a",1),Token::Variable("b",2)],LineEnding::None,1,')',1),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn group_after_operator() {
+    let input    = "a+(b)";
+    let expected = token::Stream::from(vec![
+        Token::Variable("a",0),
+        Token::Operator("+",0),
+        Token::Group('(',vec![Token::Variable("b",0)],LineEnding::None,0,')',0),
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn group_unmatched_bracket() {
+    let input    = "a)";
+    let expected = token::Stream::from(vec![
+        Token::Variable("a",0),
+        Token::UnmatchedBracket(')',0),
+    ]);
+    assert_lexes(input,expected);
+    let message = "The bracket `)` does not close an open bracket.";
+    assert_reports(input,vec![Diagnostic::new(Severity::Error,message,Span::new(1,2))]);
+}
+
+#[test]
+fn group_mismatched_bracket() {
+    let input    = "(a]";
+    let expected = token::Stream::from(vec![
+        Token::UnclosedGroup('(',vec![
+            Token::Variable("a",0),
+            Token::UnmatchedBracket(']',0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,"Expected `)` to close `(`, but found `]`.",Span::new(2,3)),
+        Diagnostic::new(Severity::Error,"The bracket `(` is never closed.",Span::new(0,1)),
+    ]);
+}
+
+#[test]
+fn group_unclosed_inside_group() {
+    let input    = "([a )";
+    let expected = token::Stream::from(vec![
+        Token::Group('(',vec![
+            Token::UnclosedGroup('[',vec![Token::Variable("a",0)],0),
+        ],LineEnding::None,1,')',0),
+    ]);
+    assert_lexes(input,expected);
+    let message = "The bracket `[` is never closed.";
+    assert_reports(input,vec![Diagnostic::new(Severity::Error,message,Span::new(1,2))]);
+}
+
+#[test]
+fn group_unclosed_at_end_of_line() {
+    let input    = "f (a\ng b)";
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("f",0),
+                    Token::UnclosedGroup('(',vec![Token::Variable("a",0)],1),
+                ],0,LineEnding::LF),
+                Token::Line(vec![
+                    Token::Variable("g",0),
+                    Token::Variable("b",1),
+                    Token::UnmatchedBracket(')',0),
+                ],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,"The bracket `(` is never closed.",Span::new(2,3)),
+        Diagnostic::new(
+            Severity::Error,
+            "The bracket `)` does not close an open bracket.",
+            Span::new(8,9)
+        ),
+    ]);
+}
+
+#[test]
+fn group_around_block() {
+    let input        = "f (a\n    b)\nc";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF)],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("f",0),
+                    Token::Group('(',vec![Token::Variable("a",0),nested_block],LineEnding::None,0,')',1),
+                ],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("c",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn group_around_nested_blocks() {
+    let input        = "f (a\n    b\n        c) d\ne";
+    let inner_block  = Token::Block(
+        BlockType::Continuous,
+        8,
+        vec![Token::Line(vec![Token::Variable("c",0)],0,LineEnding::LF)],
+        0
+    );
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![Token::Line(vec![Token::Variable("b",0),inner_block],0,LineEnding::LF)],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("f",0),
+                    Token::Group('(',vec![Token::Variable("a",0),nested_block],LineEnding::None,0,')',1),
+                    Token::Variable("d",1),
+                ],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("e",0)],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn group_closed_on_own_line() {
+    let input    = "x = (a\n    )";
+    let group    = Token::Group('(',vec![Token::Variable("a",0)],LineEnding::LF,4,')',1);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    group,
+                ],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn group_closed_on_own_line_after_block() {
+    let input        = "x = [1,\n    2\n    ]";
+    let nested_block = Token::Block(
+        BlockType::Discontinuous,
+        4,
+        vec![Token::Line(vec![Token::Number("","2",0)],0,LineEnding::LF)],
+        0
+    );
+    let tokens = vec![Token::Number("","1",0),Token::Operator(",",0),nested_block];
+    let group  = Token::Group('[',tokens,LineEnding::LF,4,']',1);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("=",1),
+                    group,
+                ],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn group_unclosed_around_block() {
+    let input        = "f (a\n    b\nc)";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF)],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("f",0),
+                    Token::UnclosedGroup('(',vec![Token::Variable("a",0),nested_block],1),
+                ],0,LineEnding::LF),
+                Token::Line(vec![
+                    Token::Variable("c",0),
+                    Token::UnmatchedBracket(')',0),
+                ],0,LineEnding::None),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,"The bracket `(` is never closed.",Span::new(2,3)),
+        Diagnostic::new(
+            Severity::Error,
+            "The bracket `)` does not close an open bracket.",
+            Span::new(12,13)
+        ),
+    ]);
+}
+
+#[test]
+fn group_in_splice() {
+    let input    = "'`(a` b)'";
+    let expected = token::Stream::from(vec![
+        Token::TextLine(TextStyle::FormatLine,vec![
+            Token::TextSegmentInterpolate(vec![
+                Token::UnclosedGroup('(',vec![Token::Variable("a",0)],0),
+            ],0,0),
+            Token::TextSegmentRaw(" b)",0),
+        ],0),
+    ]);
+    assert_lexes(input,expected);
+    let message = "The bracket `(` is never closed.";
+    assert_reports(input,vec![Diagnostic::new(Severity::Error,message,Span::new(2,3))]);
+}
+
+
+
+// ==============
+// === Blocks ===
+// ==============
+
+#[test]
+fn block_function_call() {
+    let input = make_unix_line_endings(
+r#"f
+    argument_1
+    argument_2
+    fn a1 a2 a3
+    argument_4
+    argument_5"#);
+    let block_fn_args =
+        Token::Block(
+            BlockType::Continuous,
+            4,
+            vec![
+                Token::Line(
+                    vec![Token::Variable("argument_1",0)],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_2",0),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("fn",0),
+                        Token::Variable("a1",1),
+                        Token::Variable("a2",1),
+                        Token::Variable("a3",1),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_4",0),
+                    ],
+                    0,
+                    LineEnding::LF
+                ),
+                Token::Line(
+                    vec![
+                        Token::Variable("argument_5",0),
+                    ],
+                    0,
+                    LineEnding::None
+                ),
+            ],
+            0
+        );
+    let top_level_first_line = Token::Line(
+        vec![
+            Token::Variable("f",0),
+            block_fn_args
+        ],
+        0,
+        LineEnding::LF
+    );
+    let top_level_block = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![top_level_first_line],
+            0
+        )
+    ]);
+    assert_lexes(input,top_level_block);
+}
+
+
+#[test]
+fn block_empty_lines() {
+    let input        = "f\r\n    a\n\n    b\n";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![
+            Token::Line(vec![Token::Variable("a",0)],0,LineEnding::LF),
+            Token::BlankLine(0,LineEnding::LF),
+            Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF),
+        ],
+        0
+    );
+    let top_line = Token::Line(
+        vec![
+            Token::Variable("f",0),
+            nested_block
+        ],
+        0,
+        LineEnding::CRLF
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![top_line],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_top_level() {
+    let input = make_unix_line_endings(
+r#"
+
+foo
+bar
+baz
+"#);
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::BlankLine(0,LineEnding::LF),
+                Token::BlankLine(0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("foo",0)],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("bar",0)],0,LineEnding::LF),
+                Token::Line(vec![Token::Variable("baz",0)],0,LineEnding::LF),
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_with_operator() {
+    let input = make_unix_line_endings(
+r#"x ->
+    foo x 1
+"#);
+    let nested_block = Token::Block(
+        BlockType::Discontinuous,
+        4,
+        vec![
+            Token::Line(vec![
+                Token::Variable("foo",0),
+                Token::Variable("x",1),
+                Token::Number("","1",1),
+            ], 0, LineEnding::LF)
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![
+                Token::Line(vec![
+                    Token::Variable("x",0),
+                    Token::Operator("->",1),
+                    nested_block
+                ], 0, LineEnding::LF)
+            ],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+}
+
+#[test]
+fn block_with_nesting() {
+    let input = make_unix_line_endings(r#"
 some_long_thing
     foo ->
         Bar
@@ -752,6 +1772,138 @@ fn block_length_mixed() {
     assert_block_has_length(input,20);
 }
 
+#[test]
+fn block_tab_indentation() {
+    let input        = "f\n \tx\n\t y";
+    let nested_block = Token::Block(
+        BlockType::Continuous,
+        2,
+        vec![
+            Token::Line(vec![Token::Variable("x",0)],0,LineEnding::LF),
+            Token::Line(vec![Token::Variable("y",0)],0,LineEnding::None),
+        ],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![Token::Line(vec![Token::Variable("f",0),nested_block],0,LineEnding::LF)],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    let message = "Indentation must use spaces, not tabs.";
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Error,message,Span::new(2,4)),
+        Diagnostic::new(Severity::Error,message,Span::new(6,8)),
+    ]);
+}
+
+#[test]
+fn block_tab_in_blank_line() {
+    let input = "f\n    a\n\t\n    b";
+    assert_reports(input,vec![]);
+}
+
+#[test]
+fn block_indent_not_multiple_of_four() {
+    let input   = "f\n  a\n  b\n      c";
+    let message = |indent| format!("Indentation of {} spaces is not a multiple of 4.",indent);
+    assert_reports(input,vec![
+        Diagnostic::new(Severity::Warning,message(2),Span::new(2,4)),
+        Diagnostic::new(Severity::Warning,message(6),Span::new(10,16)),
+    ]);
+}
+
+#[test]
+fn block_dedent_to_unknown_level() {
+    let input       = "f\n    a\n        b\n  c";
+    let inner_block = Token::Block(
+        BlockType::Continuous,
+        8,
+        vec![Token::Line(vec![Token::Variable("b",0)],0,LineEnding::LF)],
+        0
+    );
+    let outer_block = Token::Block(
+        BlockType::Continuous,
+        4,
+        vec![Token::Line(vec![Token::Variable("a",0),inner_block],0,LineEnding::LF)],
+        0
+    );
+    let invalid_block = Token::Block(
+        BlockType::Continuous,
+        2,
+        vec![Token::Line(vec![Token::Variable("c",0)],0,LineEnding::None)],
+        0
+    );
+    let expected = token::Stream::from(vec![
+        Token::Block(
+            BlockType::Continuous,
+            0,
+            vec![Token::Line(
+                vec![Token::Variable("f",0),outer_block,invalid_block],
+                0,
+                LineEnding::LF
+            )],
+            0
+        )
+    ]);
+    assert_lexes(input,expected);
+    let message = "Indentation of 2 does not match any enclosing block.";
+    assert_reports(input,vec![Diagnostic::new(Severity::Error,message,Span::new(18,20))]);
+}
+
+
+
+// =============
+// === Spans ===
+// =============
+
+#[test]
+fn token_spans() {
+    let input     = "'ü' foo +=bar";
+    let mut lexer = EnsoLexer::new();
+    lexer.keep_input();
+    let reader    = Reader::new(input.as_bytes(),DecoderUTF8());
+    let result    = lexer.run(reader);
+    let tokens    = result.tokens.tokens();
+    let texts     = tokens.iter().map(|token| token.span.slice(input));
+    let expected  = vec![Some("'ü'"),Some("foo"),Some("+="),Some("bar")];
+    assert_eq!(lexer.input(),Some(input));
+    assert_eq!(texts.collect::<Vec<_>>(),expected);
+}
+
+#[test]
+fn every_token_has_span() {
+    let input = make_unix_line_endings(
+r#"type Maybe
+    ## Some docs
+       on two lines
+    a = 16_ff + 1.5 + 12_ + (b [c
+        d]) }
+    t = 'x `y + 1` \n' "raw" """
+        block
+
+    # comment
+"#);
+    let result     = lex(&input);
+    let mut tokens = result.tokens.tokens().iter().collect::<Vec<_>>();
+    assert!(!tokens.is_empty());
+    while let Some(token) = tokens.pop() {
+        let span = token.span;
+        assert!(span.slice(&input).is_some(),"{:?} is not within the input.",token);
+        if token.length > 0 {
+            assert!(!span.is_empty(),"{:?} has no span.",token);
+        }
+        for child in children(token) {
+            let covered = span.start <= child.span.start && child.span.end <= span.end;
+            assert!(covered,"{:?} is not covered by the span {}.",child,span);
+            tokens.push(child);
+        }
+    }
+}
+
 
 
 // ================
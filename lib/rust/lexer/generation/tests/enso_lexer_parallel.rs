@@ -0,0 +1,141 @@
+#![feature(test)]
+#![deny(unconditional_recursion)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unsafe_code)]
+#![warn(unused_import_braces)]
+
+//! This file contains tests for reusing the Enso Lexer and for lexing many files in parallel.
+
+use flexer::*;
+use lexer_definition::library::*;
+
+use flexer::prelude::reader::decoder::DecoderUTF8;
+use flexer::prelude::Reader;
+use lexer::generated::engine::EnsoLexer;
+use lexer::parallel::lex_file;
+use lexer::parallel::lex_many;
+use lexer::parallel::lex_many_with;
+use std::fs;
+use std::path::PathBuf;
+
+
+
+// =================
+// === Utilities ===
+// =================
+
+/// Lex `input` with `lexer`.
+fn lex_with(lexer:&mut EnsoLexer, input:&str) -> LexingResult<token::Stream> {
+    let reader = Reader::new(input.as_bytes(),DecoderUTF8());
+    lexer.run(reader)
+}
+
+/// Lex `input` with a new lexer.
+fn lex(input:&str) -> LexingResult<token::Stream> {
+    lex_with(&mut EnsoLexer::new(),input)
+}
+
+/// Write each of the `inputs` to a file in a new directory named after `name`, returning the
+/// directory and the paths of the files.
+fn write_inputs(name:&str, inputs:&[&str]) -> (PathBuf,Vec<PathBuf>) {
+    let directory = std::env::temp_dir().join(format!("{}_{}",name,std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let paths = inputs.iter().enumerate().map(|(ix,input)| {
+        let path = directory.join(format!("Main_{}.enso",ix));
+        fs::write(&path,input).unwrap();
+        path
+    }).collect();
+    (directory,paths)
+}
+
+/// Some inputs that leave the lexer in different states.
+const INPUTS:&[&str] = &[
+    "foo bar\n    baz\n        quux",
+    "16_ff + 0.5 * a.b",
+    "if x then\n  y\nelse\n  z\n",
+    "",
+    "a = (b -> c) <| d",
+];
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[test]
+fn test_reset_between_runs() {
+    let mut lexer = EnsoLexer::new();
+    for input in INPUTS {
+        lexer.reset();
+        let expected = lex(input);
+        let result   = lex_with(&mut lexer,input);
+        assert_eq!(result.tokens,expected.tokens);
+        assert_eq!(result.consumed,expected.consumed);
+        assert_eq!(result.state_stack,expected.state_stack);
+    }
+}
+
+#[test]
+fn test_clone() {
+    let mut lexer = EnsoLexer::new();
+    lex_with(&mut lexer,INPUTS[0]);
+    lexer.reset();
+    let mut clone = lexer.clone();
+    assert_eq!(lex_with(&mut clone,INPUTS[1]).tokens,lex(INPUTS[1]).tokens);
+    assert_eq!(lex_with(&mut lexer,INPUTS[2]).tokens,lex(INPUTS[2]).tokens);
+}
+
+#[test]
+fn test_clone_of_new_lexer() {
+    let lexer = EnsoLexer::new();
+    for input in INPUTS {
+        let mut clone = lexer.clone();
+        let expected  = lex(input);
+        let result    = lex_with(&mut clone,input);
+        assert_eq!(result.tokens,expected.tokens);
+        assert_eq!(result.diagnostics,expected.diagnostics);
+        assert_eq!(result.consumed,expected.consumed);
+    }
+}
+
+#[test]
+fn test_lex_many() {
+    let (directory,mut paths) = write_inputs("enso_lexer_parallel",INPUTS);
+    paths.push(directory.join("Missing.enso"));
+    let results = lex_many(&paths,3);
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(results.len(),paths.len());
+    for ((result,path),input) in results.iter().zip(&paths).zip(INPUTS) {
+        assert_eq!(&result.path,path);
+        let lexed = result.result.as_ref().expect("The file should have been read.");
+        assert_eq!(lexed.tokens,lex(input).tokens);
+    }
+    assert!(results.last().unwrap().result.is_err());
+}
+
+#[test]
+fn test_lex_many_with() {
+    let (directory,paths) = write_inputs("enso_lexer_parallel_with",INPUTS);
+    let results           = lex_many_with(&paths,2,|lexer,path| {
+        let mut result = lex_file(lexer,path);
+        if let Ok(lexed) = &mut result.result {
+            lexed.kind = ResultKind::Failure;
+        }
+        result
+    });
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(results.len(),paths.len());
+    for ((result,path),input) in results.iter().zip(&paths).zip(INPUTS) {
+        assert_eq!(&result.path,path);
+        let lexed = result.result.as_ref().expect("The file should have been read.");
+        assert_eq!(lexed.kind,ResultKind::Failure);
+        assert_eq!(lexed.tokens,lex(input).tokens);
+    }
+}